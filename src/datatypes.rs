@@ -1,16 +1,34 @@
+/// record of when recipes were made
+pub mod cook_log;
+
+/// allergens and diets of ingredients and recipes
+pub mod dietary;
+
+/// how recipes are displayed, like the units and number formats used
+pub mod display_preferences;
+
 /// cooking equipment
 pub mod equipment;
 
+/// cookbook wide record of which equipment is owned
+pub mod equipment_inventory;
+
 /// recipes
 pub mod recipe;
 
 /// internal ingredient representation
 pub mod ingredient;
 
+/// physical properties of ingredients, used for unit conversion
+pub mod ingredient_catalog;
+
+/// photos attached to recipes and steps
+pub mod photo;
+
 /// steps in a recipe
 pub mod step;
 
-/// string tags for recipes
+/// normalized, namespaced tags for recipes
 pub mod tag;
 
 /// intermediate structs to help with serialization/deserialization of units
@@ -19,3 +37,6 @@ mod filetypes;
 /// functions to help work around issues with uom crate
 /// not easily supporting selectable input and output units
 pub mod unit_helper;
+
+/// every supported unit, with its names, shorthands and conversion
+pub mod unit_registry;
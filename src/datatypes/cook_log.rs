@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use num_rational::Rational64;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{filetypes, recipe::Recipe};
+
+/// name of the cook log file within the recipe directory
+pub const COOK_LOG_FILE_NAME: &str = "cook_log.toml";
+
+/// `CookLog` records each time a recipe was made, keyed by the `id` of the [`Recipe`].
+///
+/// It is stored in its own file next to the recipes, so recording that a recipe was made doesn't
+/// change the recipe file itself.
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct CookLog {
+    /// log entries keyed by recipe ID, in the order they were recorded
+    pub entries: HashMap<Uuid, Vec<CookLogEntry>>,
+}
+
+/// `CookLogEntry` is a single time a recipe was made
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CookLogEntry {
+    /// the day the recipe was made
+    pub date: NaiveDate,
+    /// who made the recipe
+    pub cook: Option<String>,
+    /// the factor the recipe was scaled by when it was made
+    pub scale: Rational64,
+    /// rating out of [`MAX_RATING`]
+    pub rating: Option<u8>,
+    /// notes about how it turned out, or what to change next time
+    pub notes: Option<String>,
+}
+
+/// the highest rating a [`CookLogEntry`] can have. Ratings start at 1
+pub const MAX_RATING: u8 = 5;
+
+/// `RecipeSort` is the order recipes are listed in when browsing
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RecipeSort {
+    /// alphabetical by recipe name
+    #[default]
+    Name,
+    /// most recently made first. Recipes that have never been made are listed last
+    LastCooked,
+    /// most often made first
+    MostCooked,
+}
+
+impl RecipeSort {
+    /// `next` returns the sort order after this one, wrapping around to the first
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            Self::Name => Self::LastCooked,
+            Self::LastCooked => Self::MostCooked,
+            Self::MostCooked => Self::Name,
+        }
+    }
+
+    /// `form_value` is the value used for this sort order in web forms
+    #[must_use]
+    pub fn form_value(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::LastCooked => "last_cooked",
+            Self::MostCooked => "most_cooked",
+        }
+    }
+
+    /// `from_form_value` is the inverse of [`RecipeSort::form_value`]
+    #[must_use]
+    pub fn from_form_value(value: &str) -> Option<Self> {
+        match value {
+            "name" => Some(Self::Name),
+            "last_cooked" => Some(Self::LastCooked),
+            "most_cooked" => Some(Self::MostCooked),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RecipeSort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Name => write!(f, "Name"),
+            Self::LastCooked => write!(f, "Last Cooked"),
+            Self::MostCooked => write!(f, "Most Cooked"),
+        }
+    }
+}
+
+impl CookLogEntry {
+    /// `new` creates a [`CookLogEntry`] for making a recipe today at the provided scale
+    #[must_use]
+    pub fn new(scale: Rational64) -> Self {
+        Self {
+            date: chrono::Local::now().date_naive(),
+            cook: None,
+            scale,
+            rating: None,
+            notes: None,
+        }
+    }
+}
+
+impl fmt::Display for CookLogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.date)?;
+        if let Some(cook) = &self.cook {
+            write!(f, " by {cook}")?;
+        }
+        if self.scale != Rational64::from_integer(1) {
+            write!(f, ", scaled \u{d7}{}", self.scale)?;
+        }
+        if let Some(rating) = self.rating {
+            write!(f, ", rated {rating}/{MAX_RATING}")?;
+        }
+        if let Some(notes) = &self.notes {
+            write!(f, ": {notes}")?;
+        }
+        Ok(())
+    }
+}
+
+impl CookLog {
+    /// `record` adds an entry to the log for the recipe with id `recipe_id`
+    ///
+    /// # Errors
+    ///
+    /// Will error if the rating is not between 1 and [`MAX_RATING`]
+    pub fn record(&mut self, recipe_id: Uuid, entry: CookLogEntry) -> anyhow::Result<()> {
+        if let Some(rating) = entry.rating
+            && !(1..=MAX_RATING).contains(&rating)
+        {
+            anyhow::bail!("Rating must be between 1 and {MAX_RATING}, got {rating}");
+        }
+        self.entries.entry(recipe_id).or_default().push(entry);
+        Ok(())
+    }
+
+    /// `history` returns the log entries for a recipe, in the order they were recorded
+    #[must_use]
+    pub fn history(&self, recipe_id: &Uuid) -> &[CookLogEntry] {
+        self.entries.get(recipe_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// `last_cooked` returns the most recent date the recipe was made, if it has been made
+    #[must_use]
+    pub fn last_cooked(&self, recipe_id: &Uuid) -> Option<NaiveDate> {
+        self.history(recipe_id).iter().map(|e| e.date).max()
+    }
+
+    /// `times_cooked` returns the number of times the recipe has been made
+    #[must_use]
+    pub fn times_cooked(&self, recipe_id: &Uuid) -> usize {
+        self.history(recipe_id).len()
+    }
+
+    /// `sort_recipes` returns the recipes in the requested order. Ties are broken by recipe name.
+    #[must_use]
+    pub fn sort_recipes<'a>(&self, recipes: &'a HashMap<Uuid, Recipe>, order: RecipeSort) -> Vec<&'a Recipe> {
+        let mut sorted = recipes.values().collect::<Vec<_>>();
+        sorted.sort_by(|a, b| a.name.cmp(&b.name));
+        match order {
+            RecipeSort::Name => {}
+            // Option orders None first, so reverse to put the most recent first and never cooked last
+            RecipeSort::LastCooked => sorted.sort_by_key(|r| std::cmp::Reverse(self.last_cooked(&r.id))),
+            RecipeSort::MostCooked => sorted.sort_by_key(|r| std::cmp::Reverse(self.times_cooked(&r.id))),
+        }
+        sorted
+    }
+
+    /// `load_from_directory` loads the cook log from [`COOK_LOG_FILE_NAME`] in the provided
+    /// directory. Returns an empty log if the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - reading the cook log file fails
+    /// - the cook log file is not valid TOML in the cook log format
+    /// - a rating is not between 1 and [`MAX_RATING`]
+    pub fn load_from_directory<T>(dir: T) -> anyhow::Result<Self>
+    where
+        T: AsRef<Path>,
+    {
+        let path = dir.as_ref().join(COOK_LOG_FILE_NAME);
+        if path.is_file() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// `load` parses a cook log file
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - reading the cook log file fails
+    /// - the cook log file is not valid TOML in the cook log format
+    /// - a rating is not between 1 and [`MAX_RATING`]
+    pub fn load<T>(cook_log_file: T) -> anyhow::Result<Self>
+    where
+        T: AsRef<Path>,
+    {
+        let contents = fs::read_to_string(cook_log_file)?;
+        let output: filetypes::CookLog = toml::from_str(contents.as_str())?;
+        let cook_log: Self = output.into();
+        if let Some(rating) = cook_log
+            .entries
+            .values()
+            .flatten()
+            .filter_map(|e| e.rating)
+            .find(|r| !(1..=MAX_RATING).contains(r))
+        {
+            anyhow::bail!("Rating must be between 1 and {MAX_RATING}, got {rating}");
+        }
+        Ok(cook_log)
+    }
+
+    /// `write_to_directory` writes the cook log to [`COOK_LOG_FILE_NAME`] in the provided
+    /// directory
+    ///
+    /// # Errors
+    ///
+    /// Will error if serializing or writing the cook log file fails
+    pub fn write_to_directory<T>(&self, dir: T) -> anyhow::Result<()>
+    where
+        T: AsRef<Path>,
+    {
+        let output = toml::to_string_pretty(&filetypes::CookLog::from(self.clone()))?;
+        fs::write(dir.as_ref().join(COOK_LOG_FILE_NAME), output)?;
+        Ok(())
+    }
+}
+
+impl From<filetypes::CookLog> for CookLog {
+    fn from(input: filetypes::CookLog) -> Self {
+        let mut entries: HashMap<Uuid, Vec<CookLogEntry>> = HashMap::new();
+        for entry in input.entries {
+            entries.entry(entry.recipe_id).or_default().push(entry.into());
+        }
+        Self { entries }
+    }
+}
+
+impl From<filetypes::CookLogEntry> for CookLogEntry {
+    fn from(input: filetypes::CookLogEntry) -> Self {
+        Self {
+            date: input.date,
+            cook: input.cook,
+            scale: input.scale.unwrap_or(Rational64::from_integer(1)),
+            rating: input.rating,
+            notes: input.notes,
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::Serialize;
+
+use super::filetypes;
+
+/// `Allergen` is a common food allergen an ingredient can contain
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum Allergen {
+    /// wheat, barley, rye and anything made from them
+    Gluten,
+    /// milk and anything made from it
+    Dairy,
+    /// eggs
+    Eggs,
+    /// tree nuts and peanuts
+    Nuts,
+    /// soy beans and anything made from them
+    Soy,
+    /// fish
+    Fish,
+    /// crustaceans and molluscs
+    Shellfish,
+    /// sesame seeds and anything made from them
+    Sesame,
+}
+
+/// `Diet` is a diet that an ingredient is suitable for
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum Diet {
+    /// no meat or fish
+    Vegetarian,
+    /// no animal products at all
+    Vegan,
+}
+
+/// `DietaryFilter` is a dietary requirement recipes can be filtered by, like vegan or nut free
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DietaryFilter {
+    /// suitable for the diet
+    Diet(Diet),
+    /// does not contain the allergen
+    Free(Allergen),
+}
+
+/// `Dietary` holds the allergens an ingredient contains and the diets it is suitable for
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Dietary {
+    /// allergens the ingredient contains
+    pub allergens: BTreeSet<Allergen>,
+    /// diets the ingredient is suitable for
+    pub diets: BTreeSet<Diet>,
+}
+
+/// `RecipeDietary` holds the dietary properties of a recipe, derived from all of its ingredients
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecipeDietary {
+    /// allergens contained in any ingredient
+    pub allergens: BTreeSet<Allergen>,
+    /// diets every ingredient is suitable for
+    pub diets: BTreeSet<Diet>,
+    /// names of ingredients without dietary information in the catalog. While there are any, the
+    /// recipe can't be said to be free of an allergen or suitable for a diet
+    pub unknown: Vec<String>,
+}
+
+impl Allergen {
+    /// `ALL` lists every allergen, in the order they are shown
+    pub const ALL: [Self; 8] = [
+        Self::Gluten,
+        Self::Dairy,
+        Self::Eggs,
+        Self::Nuts,
+        Self::Soy,
+        Self::Fish,
+        Self::Shellfish,
+        Self::Sesame,
+    ];
+
+    /// `name` is the lowercase name of the allergen, used in filter names like `nut-free`
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Gluten => "gluten",
+            Self::Dairy => "dairy",
+            Self::Eggs => "egg",
+            Self::Nuts => "nut",
+            Self::Soy => "soy",
+            Self::Fish => "fish",
+            Self::Shellfish => "shellfish",
+            Self::Sesame => "sesame",
+        }
+    }
+}
+
+impl Diet {
+    /// `ALL` lists every diet, in the order they are shown
+    pub const ALL: [Self; 2] = [Self::Vegetarian, Self::Vegan];
+
+    /// `name` is the lowercase name of the diet, also used as the filter name
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Vegetarian => "vegetarian",
+            Self::Vegan => "vegan",
+        }
+    }
+}
+
+impl DietaryFilter {
+    /// `all` lists every dietary filter, diets first and then allergens
+    #[must_use]
+    pub fn all() -> Vec<Self> {
+        Diet::ALL
+            .into_iter()
+            .map(Self::Diet)
+            .chain(Allergen::ALL.into_iter().map(Self::Free))
+            .collect()
+    }
+
+    /// `name` is the name of the filter used on the command line and in web forms, like `vegan`
+    /// or `nut-free`
+    #[must_use]
+    pub fn name(self) -> String {
+        match self {
+            Self::Diet(diet) => diet.name().to_owned(),
+            Self::Free(allergen) => format!("{}-free", allergen.name()),
+        }
+    }
+
+    /// `from_name` is the inverse of [`DietaryFilter::name`]. Case and surrounding whitespace are
+    /// ignored, and `nut free` is accepted as well as `nut-free`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase().replace(' ', "-");
+        Self::all().into_iter().find(|f| f.name() == name)
+    }
+}
+
+impl RecipeDietary {
+    /// `satisfies` returns true if the recipe meets the dietary requirement.
+    ///
+    /// Recipes with ingredients that have no dietary information never satisfy a requirement, as
+    /// it isn't known if they meet it or not.
+    #[must_use]
+    pub fn satisfies(&self, filter: DietaryFilter) -> bool {
+        self.unknown.is_empty()
+            && match filter {
+                DietaryFilter::Diet(diet) => self.diets.contains(&diet),
+                DietaryFilter::Free(allergen) => !self.allergens.contains(&allergen),
+            }
+    }
+}
+
+impl fmt::Display for Allergen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl fmt::Display for Diet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl fmt::Display for DietaryFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl fmt::Display for RecipeDietary {
+    /// Ex: `contains: gluten, dairy; suitable for: vegetarian`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        if !self.allergens.is_empty() {
+            parts.push(format!(
+                "contains: {}",
+                self.allergens.iter().map(|a| a.name()).collect::<Vec<_>>().join(", ")
+            ));
+        }
+        if self.unknown.is_empty() {
+            if self.allergens.is_empty() {
+                parts.push("no common allergens".to_owned());
+            }
+            if !self.diets.is_empty() {
+                parts.push(format!(
+                    "suitable for: {}",
+                    self.diets.iter().map(|d| d.name()).collect::<Vec<_>>().join(", ")
+                ));
+            }
+        } else {
+            parts.push(format!("not known for: {}", self.unknown.join(", ")));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+impl From<filetypes::Allergen> for Allergen {
+    fn from(input: filetypes::Allergen) -> Self {
+        match input {
+            filetypes::Allergen::Gluten => Self::Gluten,
+            filetypes::Allergen::Dairy => Self::Dairy,
+            filetypes::Allergen::Eggs => Self::Eggs,
+            filetypes::Allergen::Nuts => Self::Nuts,
+            filetypes::Allergen::Soy => Self::Soy,
+            filetypes::Allergen::Fish => Self::Fish,
+            filetypes::Allergen::Shellfish => Self::Shellfish,
+            filetypes::Allergen::Sesame => Self::Sesame,
+        }
+    }
+}
+
+impl From<filetypes::Diet> for Diet {
+    fn from(input: filetypes::Diet) -> Self {
+        match input {
+            filetypes::Diet::Vegetarian => Self::Vegetarian,
+            filetypes::Diet::Vegan => Self::Vegan,
+        }
+    }
+}
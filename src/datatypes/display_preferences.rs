@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use num_rational::Rational64;
+use serde::{Deserialize, Serialize};
+use uom::si::rational64::{ThermodynamicTemperature, Time};
+
+use super::{
+    ingredient::{Ingredient, UnitType},
+    step::TimeRange,
+    unit_helper::{self, FractionStyle, MeasurementSystem, TemperatureScale, TimeFormat},
+};
+
+/// form value used for the `None` choice of the optional preferences
+const DEFAULT_FORM_VALUE: &str = "default";
+
+/// `DisplayPreferences` are how a person prefers recipes to be shown, like a US customary cook
+/// viewing metric recipes in cups and degrees Fahrenheit.
+///
+/// They are set in the `[display]` table of the configuration file, and can be overridden for a
+/// single browser in the web gui.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayPreferences {
+    /// system quantities are shown in. If `None`, each quantity is shown in the system it was
+    /// written in
+    pub system: Option<MeasurementSystem>,
+    /// scale temperatures are shown in. If `None`, the scale used by `system` is used, or the
+    /// scale the temperature was written in if `system` is also `None`
+    pub temperature: Option<TemperatureScale>,
+    /// how lengths of time are written
+    pub time_format: TimeFormat,
+    /// how amounts that aren't whole numbers are written
+    pub fraction_style: FractionStyle,
+}
+
+impl DisplayPreferences {
+    /// names of the web form fields, and browser cookies, that hold each preference
+    pub const FORM_FIELDS: [&str; 4] = [
+        "display_system",
+        "display_temperature",
+        "display_time_format",
+        "display_fraction_style",
+    ];
+
+    /// `quantity` returns `quantity` formatted for display, as in [`UnitType::readable`]
+    #[must_use]
+    pub fn quantity(&self, quantity: &UnitType) -> String {
+        quantity.readable(self.system, self.fraction_style)
+    }
+
+    /// `alternatives` returns the alternatives of `ingredient` formatted for display, as in
+    /// [`Ingredient::alternatives_text`]
+    #[must_use]
+    pub fn alternatives(&self, ingredient: &Ingredient) -> String {
+        ingredient.alternatives_text(self.system, self.fraction_style)
+    }
+
+    /// `number` returns `value` formatted for display, as in [`unit_helper::approximate_fraction`]
+    #[must_use]
+    pub fn number(&self, value: Rational64) -> String {
+        unit_helper::approximate_fraction(value, self.fraction_style)
+    }
+
+    /// `temperature` returns `value` formatted for display. `written_unit` is the abbreviation of
+    /// the unit the temperature was written in, if known
+    #[must_use]
+    pub fn temperature(&self, value: ThermodynamicTemperature, written_unit: Option<&str>) -> String {
+        let scale = self.temperature.unwrap_or_else(|| {
+            TemperatureScale::of_system(
+                self.system
+                    .unwrap_or_else(|| MeasurementSystem::of_unit(written_unit.unwrap_or("°C"))),
+            )
+        });
+        unit_helper::temperature_display(value, scale)
+    }
+
+    /// `time` returns `value` formatted for display, as in [`unit_helper::time_display`]
+    #[must_use]
+    pub fn time(&self, value: Time) -> String {
+        unit_helper::time_display(value, self.time_format)
+    }
+
+    /// `time_range` returns `value` formatted for display, as in [`TimeRange::readable`]
+    #[must_use]
+    pub fn time_range(&self, value: &TimeRange) -> String {
+        value.readable(self.time_format)
+    }
+
+    /// `with_form_values` returns these preferences overridden by any preferences in `values`,
+    /// which are web form fields or browser cookies named as in
+    /// [`DisplayPreferences::FORM_FIELDS`]. Missing and unrecognized values are ignored
+    #[must_use]
+    pub fn with_form_values(mut self, values: &HashMap<String, String>) -> Self {
+        let [system, temperature, time_format, fraction_style] = Self::FORM_FIELDS;
+        if let Some(value) = values.get(system) {
+            if value == DEFAULT_FORM_VALUE {
+                self.system = None;
+            } else if let Some(system) = MeasurementSystem::from_form_value(value) {
+                self.system = Some(system);
+            }
+        }
+        if let Some(value) = values.get(temperature) {
+            if value == DEFAULT_FORM_VALUE {
+                self.temperature = None;
+            } else if let Some(scale) = TemperatureScale::from_form_value(value) {
+                self.temperature = Some(scale);
+            }
+        }
+        if let Some(format) = values.get(time_format).and_then(|v| TimeFormat::from_form_value(v)) {
+            self.time_format = format;
+        }
+        if let Some(style) = values.get(fraction_style).and_then(|v| FractionStyle::from_form_value(v)) {
+            self.fraction_style = style;
+        }
+        self
+    }
+
+    /// `form_values` returns the name and value of the web form field, or browser cookie, for
+    /// each preference. The inverse of [`DisplayPreferences::with_form_values`]
+    #[must_use]
+    pub fn form_values(&self) -> [(&'static str, &'static str); 4] {
+        let [system, temperature, time_format, fraction_style] = Self::FORM_FIELDS;
+        [
+            (system, self.system.map_or(DEFAULT_FORM_VALUE, MeasurementSystem::form_value)),
+            (
+                temperature,
+                self.temperature.map_or(DEFAULT_FORM_VALUE, TemperatureScale::form_value),
+            ),
+            (time_format, self.time_format.form_value()),
+            (fraction_style, self.fraction_style.form_value()),
+        ]
+    }
+}
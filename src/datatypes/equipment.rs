@@ -32,7 +32,13 @@ pub struct Equipment {
     pub description: Option<String>,
     /// If item is owned. Allows filtering out recipes that require equipment you don't own so you
     /// don't get half way through a recipe and realize it needs some specialized piece of
-    /// equipment like a melon baller or pineapple corer
+    /// equipment like a melon baller or pineapple corer.
+    ///
+    /// This is the value from the recipe file, which is only used to add the item to the
+    /// equipment inventory when migrating recipe files. Use [`EquipmentInventory::is_owned`] to
+    /// check if the item is owned.
+    ///
+    /// [`EquipmentInventory::is_owned`]: super::equipment_inventory::EquipmentInventory::is_owned
     #[cfg_attr(feature = "tui", cookbook(display_order = 2))]
     #[cfg_attr(feature = "tui", cookbook(constraint_type = "Length"))]
     #[cfg_attr(feature = "tui", cookbook(constraint_value = 3))]
@@ -68,7 +74,7 @@ impl From<filetypes::Equipment> for Equipment {
             id: input.id,
             name: input.name,
             description: input.description,
-            is_owned: input.is_owned,
+            is_owned: input.is_owned.unwrap_or_default(),
         }
     }
 }
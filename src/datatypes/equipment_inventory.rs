@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{equipment::Equipment, filetypes};
+
+/// name of the equipment inventory file within the recipe directory
+pub const INVENTORY_FILE_NAME: &str = "equipment_inventory.toml";
+
+/// `EquipmentInventory` records which equipment is owned, keyed by the `id` of the
+/// [`Equipment`].
+///
+/// It is stored in its own file next to the recipes, so buying a new piece of equipment only
+/// needs one change rather than editing every recipe that uses it. Equipment that isn't in the
+/// inventory is not owned, whatever `is_owned` says in recipe files.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EquipmentInventory {
+    /// inventory entries keyed by equipment ID
+    pub entries: HashMap<Uuid, InventoryEntry>,
+}
+
+/// `InventoryEntry` is a single piece of equipment in the inventory
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InventoryEntry {
+    /// database ID. Matches the `id` of the equipment in recipes
+    pub id: Uuid,
+    /// short name of item
+    pub name: String,
+    /// if the item is owned
+    pub is_owned: bool,
+}
+
+impl EquipmentInventory {
+    /// `is_owned` returns true if `equipment` is in the inventory and owned
+    #[must_use]
+    pub fn is_owned(&self, equipment: &Equipment) -> bool {
+        self.entries.get(&equipment.id).is_some_and(|e| e.is_owned)
+    }
+
+    /// `add_owned` adds each piece of `equipment` marked as owned in its recipe file to the
+    /// inventory, if it isn't there already, and returns how many were added. Used to move
+    /// `is_owned` out of recipe files written before the inventory existed
+    pub fn add_owned<'a, I>(&mut self, equipment: I) -> usize
+    where
+        I: IntoIterator<Item = &'a Equipment>,
+    {
+        let mut added: usize = 0;
+        for item in equipment {
+            if item.is_owned && !self.entries.contains_key(&item.id) {
+                self.set_owned(item, true);
+                added = added.saturating_add(1);
+            }
+        }
+        added
+    }
+
+    /// `set_owned` records if `equipment` is owned, adding it to the inventory if it isn't there
+    /// already
+    pub fn set_owned(&mut self, equipment: &Equipment, is_owned: bool) {
+        self.entries
+            .entry(equipment.id)
+            .and_modify(|e| e.is_owned = is_owned)
+            .or_insert_with(|| InventoryEntry {
+                id: equipment.id,
+                name: equipment.name.clone(),
+                is_owned,
+            });
+    }
+
+    /// `load_from_directory` loads the equipment inventory from [`INVENTORY_FILE_NAME`] in the
+    /// provided directory. Returns an empty inventory if the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - reading the inventory file fails
+    /// - the inventory file is not valid TOML in the inventory format
+    pub fn load_from_directory<T>(dir: T) -> anyhow::Result<Self>
+    where
+        T: AsRef<Path>,
+    {
+        let path = dir.as_ref().join(INVENTORY_FILE_NAME);
+        if path.is_file() {
+            Self::load(&path).with_context(|| format!("Parsing TOML file {} failed", path.display()))
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// `load` parses an equipment inventory file
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - reading the inventory file fails
+    /// - the inventory file is not valid TOML in the inventory format
+    pub fn load<T>(inventory_file: T) -> anyhow::Result<Self>
+    where
+        T: AsRef<Path>,
+    {
+        let contents = fs::read_to_string(inventory_file)?;
+        let output: filetypes::EquipmentInventory = toml::from_str(contents.as_str())?;
+        Ok(output.into())
+    }
+
+    /// `write_to_directory` writes the equipment inventory to [`INVENTORY_FILE_NAME`] in the
+    /// provided directory
+    ///
+    /// # Errors
+    ///
+    /// Will error if serializing or writing the inventory file fails
+    pub fn write_to_directory<T>(&self, dir: T) -> anyhow::Result<()>
+    where
+        T: AsRef<Path>,
+    {
+        let output = toml::to_string_pretty(&filetypes::EquipmentInventory::from(self.clone()))?;
+        fs::write(dir.as_ref().join(INVENTORY_FILE_NAME), output)?;
+        Ok(())
+    }
+}
+
+impl From<filetypes::EquipmentInventory> for EquipmentInventory {
+    fn from(input: filetypes::EquipmentInventory) -> Self {
+        Self {
+            entries: input.equipment.into_iter().map(|e| (e.id, e.into())).collect(),
+        }
+    }
+}
+
+impl From<filetypes::InventoryEntry> for InventoryEntry {
+    fn from(input: filetypes::InventoryEntry) -> Self {
+        Self {
+            id: input.id,
+            name: input.name,
+            is_owned: input.is_owned.unwrap_or(true),
+        }
+    }
+}
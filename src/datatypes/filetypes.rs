@@ -1,8 +1,15 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use chrono::NaiveDate;
 use num_rational::Rational64;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::{equipment, ingredient, recipe, step, unit_helper};
+use super::{
+    cook_log, equipment, equipment_inventory, ingredient, photo, recipe, step,
+    unit_helper::{self, UnitParseError},
+};
 
 /// `Recipe` represents one recipe from start to finish
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -19,19 +26,24 @@ pub struct Recipe {
     pub source: String,
     /// Recipe author
     pub author: String,
-    /// Amount made
-    pub amount_made: u64,
-    /// Units for amount made.
+    /// Amount made. Either a whole number or a rational number (fraction)
+    pub amount_made: AmountMadeQuantity,
+    /// Label for the amount made, like "cookies" or "jars of jam".
     ///
-    /// These are not type checked at all and are treated as a base quantity internally.
-    /// This is just a representation of the units to display.
-    /// There may be a future addition that automatically calculates calories, or serving
-    /// sizes based on calories.
+    /// This is just a representation of the units to display and is not type checked.
     pub amount_made_units: String,
+    /// Optional. What amount_made is measured in, so it can be converted and compared
+    pub amount_made_unit: Option<YieldUnit>,
+    /// Optional. Ingredients used in the recipe, that steps can refer to by ID
+    pub ingredients: Option<Vec<IngredientDefinition>>,
+    /// Optional. Equipment used in the recipe, that steps can refer to by ID
+    pub equipment: Option<Vec<Equipment>>,
     /// List of steps in recipe
     pub steps: Vec<Step>,
     /// Tags
     pub tags: Vec<String>,
+    /// Optional. Photos of the finished recipe
+    pub photos: Option<Vec<Photo>>,
     //TODO: versions
     //TODO: maybe make comments a bit more formal, want to be able to record when recipe was last
     //made
@@ -46,10 +58,9 @@ pub struct Equipment {
     pub name: String,
     /// Longer description of item
     pub description: Option<String>,
-    /// If item is owned. Allows filtering out recipes that require equipment you don't own so you
-    /// don't get half way through a recipe and realize it needs some specialized piece of
-    /// equipment like a melon baller or pineapple corer
-    pub is_owned: bool,
+    /// Optional. If item is owned. Only used to add the item to the equipment inventory when
+    /// migrating recipe files, and never written back out. Defaults to false
+    pub is_owned: Option<bool>,
 }
 /// `Ingredient` is a unique item that represents the quantity of a particular ingredient
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -62,8 +73,63 @@ pub struct Ingredient {
     pub description: Option<String>,
     /// Quantity of ingredient
     pub unit_quantity: UnitType,
+    /// Optional. Ingredients that can be used instead of this one
+    pub alternatives: Option<Vec<Alternative>>,
+    /// Optional. If the ingredient can be left out of the recipe. Defaults to false
+    pub optional: Option<bool>,
+    /// Optional. Why the ingredient is optional
+    pub optional_reason: Option<String>,
+    /// Optional. How the ingredient is prepared for this step. Ex: diced, softened
+    pub preparation: Option<String>,
+    /// Optional. If the ingredient is split between several steps. Defaults to false
+    pub divided: Option<bool>,
     //TODO: inventory reference
-    //TODO: allow ingredient to be marked as optional
+}
+
+/// `IngredientDefinition` holds the details of an ingredient in the recipe level ingredients
+/// table, so they don't need to be repeated in every step that uses the ingredient
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IngredientDefinition {
+    /// Database ID
+    pub id: Uuid,
+    /// Ingredient short name
+    pub name: String,
+    /// Optional description
+    pub description: Option<String>,
+}
+
+/// `IngredientReference` is a use of an ingredient from the recipe level ingredients table in a
+/// step
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngredientReference {
+    /// ID of the ingredient in the recipe level ingredients table
+    pub id: Uuid,
+    /// Quantity of ingredient used in this step
+    pub unit_quantity: UnitType,
+    /// Optional. Ingredients that can be used instead of this one
+    pub alternatives: Option<Vec<Alternative>>,
+    /// Optional. If the ingredient can be left out of the recipe. Defaults to false
+    pub optional: Option<bool>,
+    /// Optional. Why the ingredient is optional
+    pub optional_reason: Option<String>,
+    /// Optional. How the ingredient is prepared for this step. Ex: diced, softened
+    pub preparation: Option<String>,
+    /// Optional. If the ingredient is split between several steps. Defaults to false
+    pub divided: Option<bool>,
+}
+
+/// `Alternative` is an ingredient that can be substituted for another ingredient
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Alternative {
+    /// Database ID
+    pub id: Uuid,
+    /// Ingredient short name
+    pub name: String,
+    /// Optional. Quantity of alternative
+    pub unit_quantity: Option<UnitType>,
+    /// Optional. Amount of alternative relative to the original ingredient.
+    /// Ignored if unit_quantity is specified
+    pub ratio: Option<Rational64>,
 }
 
 /// `UnitType` handles different unit types for an ingredient and allows flexibility rather than
@@ -73,7 +139,7 @@ pub struct Ingredient {
 pub enum UnitType {
     /// Represents a count or physical quantity of an `Ingredient`:
     /// Ex: 30 chocolate chips, 5 bananas, 10 carrots etc.
-    Quantity(Rational64),
+    Quantity(CountQuantity),
     /// Mass of an `Ingredient`. Unit specified with abbreviation
     Mass { value: Rational64, unit: String },
     /// Volume of an `Ingredent`. Unit specified with abbreviation
@@ -82,9 +148,215 @@ pub enum UnitType {
 
 impl Default for UnitType {
     fn default() -> Self {
-        Self::Quantity(Rational64::default())
+        Self::Quantity(CountQuantity::Bare(Rational64::default()))
     }
 }
+
+/// `CountQuantity` is a count of an ingredient. Older recipe files only allowed a bare number,
+/// which is still accepted and written out when nothing else is being counted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CountQuantity {
+    /// A count of the ingredient itself, like 5 bananas
+    Bare(Rational64),
+    /// A count of something holding or made of the ingredient, like 2 cans of tomatoes or 3
+    /// cloves of garlic
+    Named {
+        /// number of `unit`
+        value: Rational64,
+        /// what is being counted. Ex: can, stick, clove
+        unit: String,
+    },
+}
+
+/// `IngredientCatalog` is the file format of the ingredient catalog
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct IngredientCatalog {
+    /// Catalog entries, one for each ingredient
+    pub ingredients: Vec<CatalogEntry>,
+}
+
+/// `CatalogEntry` holds the physical properties of a single ingredient
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    /// Database ID. Matches the `id` of the ingredient in recipes
+    pub id: Uuid,
+    /// Ingredient short name
+    pub name: String,
+    /// Optional. Density of the ingredient
+    pub density: Option<Density>,
+    /// Optional. Typical mass of one of the ingredient, like one egg or one banana
+    pub unit_mass: Option<UnitMass>,
+    /// Optional. How much of the ingredient is in one of a count unit, like one stick of butter
+    pub count_units: Option<Vec<CountUnit>>,
+    /// Optional. Nutrients in an amount of the ingredient
+    pub nutrition: Option<NutritionFacts>,
+    /// Optional. Allergens the ingredient contains
+    pub allergens: Option<Vec<Allergen>>,
+    /// Optional. Diets the ingredient is suitable for
+    pub diets: Option<Vec<Diet>>,
+}
+
+/// `Allergen` is a common food allergen an ingredient can contain
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Allergen {
+    /// wheat, barley, rye and anything made from them
+    Gluten,
+    /// milk and anything made from it
+    Dairy,
+    /// eggs
+    Eggs,
+    /// tree nuts and peanuts
+    Nuts,
+    /// soy beans and anything made from them
+    Soy,
+    /// fish
+    Fish,
+    /// crustaceans and molluscs
+    Shellfish,
+    /// sesame seeds and anything made from them
+    Sesame,
+}
+
+/// `Diet` is a diet that an ingredient is suitable for
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Diet {
+    /// no meat or fish
+    Vegetarian,
+    /// no animal products at all
+    Vegan,
+}
+
+/// `NutritionFacts` are the nutrients in an amount of an ingredient, like a nutrition label.
+/// Nutrients that are left out are treated as zero.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NutritionFacts {
+    /// Amount of the ingredient the nutrients are given for. Ex: 100 g
+    pub per: UnitType,
+    /// Optional. Food energy in kcal
+    pub energy: Option<Rational64>,
+    /// Optional. Protein in g
+    pub protein: Option<Rational64>,
+    /// Optional. Total fat in g
+    pub fat: Option<Rational64>,
+    /// Optional. Saturated fat in g
+    pub saturated_fat: Option<Rational64>,
+    /// Optional. Total carbohydrates in g
+    pub carbohydrates: Option<Rational64>,
+    /// Optional. Sugars in g
+    pub sugars: Option<Rational64>,
+    /// Optional. Dietary fiber in g
+    pub fiber: Option<Rational64>,
+    /// Optional. Sodium in mg
+    pub sodium: Option<Rational64>,
+}
+
+/// `Density` is specified as the mass of a given volume of an ingredient, as that is how
+/// densities are usually found in recipes and on packaging. Ex: 120 g per 1 cup
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Density {
+    /// Mass of `volume` of the ingredient
+    pub mass: Rational64,
+    /// Units for mass. Unit specified with abbreviation
+    pub mass_unit: String,
+    /// Volume that weighs `mass`
+    pub volume: Rational64,
+    /// Units for volume. Unit specified with abbreviation
+    pub volume_unit: String,
+}
+
+/// `UnitMass` is the mass of one of an ingredient
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnitMass {
+    /// Mass of one of the ingredient
+    pub value: Rational64,
+    /// Units for value. Unit specified with abbreviation
+    pub unit: String,
+}
+
+/// `CountUnit` is how much of an ingredient is in one of a count unit, like a can or a stick
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CountUnit {
+    /// Count unit, in the singular or plural. Ex: stick
+    pub unit: String,
+    /// Mass or volume of the ingredient in one `unit`
+    pub equals: UnitType,
+}
+
+/// `AmountMadeQuantity` is the amount made by a recipe. Older recipe files only allowed whole
+/// numbers, which are still accepted and written out when the amount is whole.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AmountMadeQuantity {
+    /// A whole number amount
+    Whole(u64),
+    /// A rational number (fraction). Numerator over Denominator
+    Rational(Rational64),
+}
+
+impl Default for AmountMadeQuantity {
+    fn default() -> Self {
+        Self::Whole(0)
+    }
+}
+
+/// `YieldUnit` is the unit the amount made by a recipe is measured in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum YieldUnit {
+    /// A number of items, like cookies or loaves
+    Count,
+    /// A number of servings
+    Servings,
+    /// A mass. Unit specified with abbreviation
+    Mass(String),
+    /// A volume. Unit specified with abbreviation
+    Volume(String),
+}
+
+/// `EquipmentInventory` is the file format of the equipment inventory
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct EquipmentInventory {
+    /// Inventory entries, one for each piece of equipment
+    pub equipment: Vec<InventoryEntry>,
+}
+
+/// `InventoryEntry` is a single piece of equipment in the inventory
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryEntry {
+    /// Database ID. Matches the `id` of the equipment in recipes
+    pub id: Uuid,
+    /// Short name of item
+    pub name: String,
+    /// Optional. If the item is owned. Defaults to true
+    pub is_owned: Option<bool>,
+}
+
+/// `CookLog` is the file format of the cook log
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct CookLog {
+    /// Log entries, one for each time a recipe was made
+    pub entries: Vec<CookLogEntry>,
+}
+
+/// `CookLogEntry` is a single time a recipe was made
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CookLogEntry {
+    /// ID of the recipe that was made
+    pub recipe_id: Uuid,
+    /// The day the recipe was made. Written as YYYY-MM-DD
+    pub date: NaiveDate,
+    /// Optional. Who made the recipe
+    pub cook: Option<String>,
+    /// Optional. Factor the recipe was scaled by. Defaults to 1
+    pub scale: Option<Rational64>,
+    /// Optional. Rating from 1 to 5
+    pub rating: Option<u8>,
+    /// Optional. Notes about how it turned out
+    pub notes: Option<String>,
+}
+
 /// `Step` represents a discrete step within a recipe
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Step {
@@ -94,10 +366,15 @@ pub struct Step {
     /// Optional for informational steps, or steps that
     /// don't traditionally have durations associated.
     pub time_needed: Option<Rational64>,
+    /// Optional. Longest time needed, for steps that take a range of time like 25–30 minutes.
+    /// time_needed is the shortest time. In time_needed_unit
+    pub time_needed_max: Option<Rational64>,
+    /// Optional. If time_needed is a rough estimate. Defaults to false
+    pub time_approximate: Option<bool>,
     /// Units for time_needed.
     pub time_needed_unit: Option<String>,
-    /// Cook temperature. Optional for steps that don't involve temperature or cooking
-    /// Specified in K
+    /// Cook temperature. Optional for steps that don't involve temperature or cooking.
+    /// This is an absolute temperature, like an oven setting, in temperature_unit
     pub temperature: Option<Rational64>,
     /// Units for temperature.
     pub temperature_unit: Option<String>,
@@ -105,11 +382,51 @@ pub struct Step {
     pub instructions: String,
     /// Ingredients used in this step
     pub ingredients: Option<Vec<Ingredient>>,
+    /// Ingredients from the recipe level ingredients table used in this step
+    pub ingredient_references: Option<Vec<IngredientReference>>,
     /// Equipment used in this step
     pub equipment: Option<Vec<Equipment>>,
+    /// IDs of equipment from the recipe level equipment table used in this step
+    pub equipment_references: Option<Vec<Uuid>>,
     /// Step type
     #[allow(clippy::struct_field_names)]
     pub step_type: StepType,
+    /// Optional. Another recipe that is made as part of this step
+    pub sub_recipe: Option<SubRecipe>,
+    /// Optional. IDs of earlier steps that need to be finished before this step can start
+    pub depends_on: Option<Vec<Uuid>>,
+    /// Optional. If the step can run at the same time as the previous step. Defaults to false
+    pub parallel: Option<bool>,
+    /// Optional. Photos of the step
+    pub photos: Option<Vec<Photo>>,
+}
+
+/// `Photo` is an image file attached to a recipe or step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Photo {
+    /// Path of the image file, relative to the cookbook directory
+    pub file: PathBuf,
+    /// Optional caption
+    pub caption: Option<String>,
+}
+
+/// `SubRecipe` is a reference from a step to another recipe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubRecipe {
+    /// ID of the referenced recipe
+    pub id: Uuid,
+    /// How much of the referenced recipe is needed
+    pub quantity: SubRecipeQuantity,
+}
+
+/// `SubRecipeQuantity` is how much of a referenced recipe is needed
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SubRecipeQuantity {
+    /// Number of batches of the referenced recipe
+    Batches(Rational64),
+    /// Amount of the referenced recipe, in the units of its amount made
+    Amount(Rational64),
 }
 
 /// `StepType` represents what type of step each step is in a recipe. It is used to bucket times
@@ -128,61 +445,250 @@ pub enum StepType {
     Other,
 }
 
-impl From<recipe::Recipe> for Recipe {
-    fn from(input: recipe::Recipe) -> Self {
-        Self {
+impl Recipe {
+    /// `expand_references` replaces all references to the recipe level ingredients and equipment
+    /// tables in steps with the full ingredient or equipment, so the recipe can be converted to
+    /// the internal representation.
+    ///
+    /// # Errors
+    ///
+    /// Will error if a step references an ingredient or equipment that is not in the recipe level
+    /// tables
+    pub fn expand_references(&mut self) -> anyhow::Result<()> {
+        let ingredients = self.ingredients.take().unwrap_or_default();
+        let equipment = self.equipment.take().unwrap_or_default();
+        for (i, step) in self.steps.iter_mut().enumerate() {
+            for reference in step.ingredient_references.take().unwrap_or_default() {
+                let Some(definition) = ingredients.iter().find(|d| d.id == reference.id) else {
+                    anyhow::bail!(
+                        "Step {} of recipe {} uses ingredient {} which is not in the recipe ingredients",
+                        i.saturating_add(1),
+                        self.name,
+                        reference.id
+                    );
+                };
+                step.ingredients.get_or_insert_with(Vec::new).push(Ingredient {
+                    id: definition.id,
+                    name: definition.name.clone(),
+                    description: definition.description.clone(),
+                    unit_quantity: reference.unit_quantity,
+                    alternatives: reference.alternatives,
+                    optional: reference.optional,
+                    optional_reason: reference.optional_reason,
+                    preparation: reference.preparation,
+                    divided: reference.divided,
+                });
+            }
+            for id in step.equipment_references.take().unwrap_or_default() {
+                let Some(definition) = equipment.iter().find(|e| e.id == id) else {
+                    anyhow::bail!(
+                        "Step {} of recipe {} uses equipment {id} which is not in the recipe equipment",
+                        i.saturating_add(1),
+                        self.name
+                    );
+                };
+                step.equipment.get_or_insert_with(Vec::new).push(definition.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// `collect_references` moves the details of ingredients and equipment used in steps into the
+    /// recipe level tables, and replaces them in the steps with references by ID. This is the
+    /// inverse of [`Recipe::expand_references`].
+    ///
+    /// Ingredients or equipment with the same ID but different details in different steps are
+    /// left inline in the steps after the first.
+    pub fn collect_references(&mut self) {
+        let mut ingredients: Vec<IngredientDefinition> = self.ingredients.take().unwrap_or_default();
+        let mut equipment: Vec<Equipment> = self.equipment.take().unwrap_or_default();
+        for step in &mut self.steps {
+            let mut inline_ingredients = Vec::new();
+            for ingredient in step.ingredients.take().unwrap_or_default() {
+                let definition = IngredientDefinition {
+                    id: ingredient.id,
+                    name: ingredient.name.clone(),
+                    description: ingredient.description.clone(),
+                };
+                match ingredients.iter().find(|d| d.id == definition.id) {
+                    Some(existing) if *existing != definition => inline_ingredients.push(ingredient),
+                    existing => {
+                        if existing.is_none() {
+                            ingredients.push(definition);
+                        }
+                        step.ingredient_references
+                            .get_or_insert_with(Vec::new)
+                            .push(IngredientReference {
+                                id: ingredient.id,
+                                unit_quantity: ingredient.unit_quantity,
+                                alternatives: ingredient.alternatives,
+                                optional: ingredient.optional,
+                                optional_reason: ingredient.optional_reason,
+                                preparation: ingredient.preparation,
+                                divided: ingredient.divided,
+                            });
+                    }
+                }
+            }
+            if !inline_ingredients.is_empty() {
+                step.ingredients = Some(inline_ingredients);
+            }
+            let mut inline_equipment = Vec::new();
+            for item in step.equipment.take().unwrap_or_default() {
+                match equipment.iter().find(|e| e.id == item.id) {
+                    Some(existing) if *existing != item => inline_equipment.push(item),
+                    existing => {
+                        if existing.is_none() {
+                            equipment.push(item.clone());
+                        }
+                        step.equipment_references.get_or_insert_with(Vec::new).push(item.id);
+                    }
+                }
+            }
+            if !inline_equipment.is_empty() {
+                step.equipment = Some(inline_equipment);
+            }
+        }
+        if !ingredients.is_empty() {
+            self.ingredients = Some(ingredients);
+        }
+        if !equipment.is_empty() {
+            self.equipment = Some(equipment);
+        }
+    }
+}
+
+impl TryFrom<recipe::Recipe> for Recipe {
+    type Error = anyhow::Error;
+
+    fn try_from(input: recipe::Recipe) -> anyhow::Result<Self> {
+        let uses_references = input.uses_references;
+        let recipe_name = input.name.clone();
+        let mut output = Self {
             id: if input.id == Uuid::nil() { None } else { Some(input.id) },
             name: input.name,
             description: input.description,
             comments: input.comments,
             source: input.source,
             author: input.author,
-            amount_made: input.amount_made.quantity,
+            amount_made: input.amount_made.quantity.into(),
             amount_made_units: input.amount_made.units,
-            steps: input.steps.into_iter().map(Into::into).collect(),
-            tags: input.tags,
+            amount_made_unit: input.amount_made.unit.map(|u| match u {
+                recipe::YieldUnit::Count => YieldUnit::Count,
+                recipe::YieldUnit::Servings => YieldUnit::Servings,
+                recipe::YieldUnit::Mass(unit) => YieldUnit::Mass(unit),
+                recipe::YieldUnit::Volume(unit) => YieldUnit::Volume(unit),
+            }),
+            ingredients: None,
+            equipment: None,
+            steps: input
+                .steps
+                .into_iter()
+                .enumerate()
+                .map(|(i, step)| {
+                    Step::try_from(step).with_context(|| format!("Step {} of recipe {recipe_name}", i.saturating_add(1)))
+                })
+                .collect::<anyhow::Result<_>>()?,
+            tags: input.tags.into_iter().map(String::from).collect(),
+            photos: if input.photos.is_empty() {
+                None
+            } else {
+                Some(input.photos.into_iter().map(Into::into).collect())
+            },
+        };
+        // recipes that list ingredients and equipment in full in each step are written back the
+        // same way
+        if uses_references {
+            output.collect_references();
         }
+        Ok(output)
     }
 }
 
-impl From<step::Step> for Step {
-    fn from(input: step::Step) -> Self {
-        Self {
+impl TryFrom<step::Step> for Step {
+    type Error = anyhow::Error;
+
+    fn try_from(input: step::Step) -> anyhow::Result<Self> {
+        let time_needed_unit = input.time_needed_unit.as_deref().unwrap_or("placeholder");
+        let temperature_unit = input.temperature_unit.as_deref().unwrap_or("placeholder");
+        Ok(Self {
             id: input.id,
-            time_needed: input.time_needed.map(|tn| {
-                unit_helper::time_unit_raw_output(
-                    tn,
-                    input
-                        .time_needed_unit
-                        .clone()
-                        .expect("time_needed value specified without unit")
-                        .as_ref(),
-                )
-            }),
+            time_needed: input
+                .time_needed
+                .map(|tn| unit_helper::time_unit_raw_output(tn.min, time_needed_unit))
+                .transpose()
+                .context("time_needed_unit")?,
+            time_needed_max: input
+                .time_needed
+                .filter(|tn| !tn.is_exact())
+                .map(|tn| unit_helper::time_unit_raw_output(tn.max, time_needed_unit))
+                .transpose()
+                .context("time_needed_unit")?,
+            time_approximate: input.time_needed.is_some_and(|tn| tn.approximate).then_some(true),
+            temperature: input
+                .temperature
+                .map(|t| unit_helper::temperature_unit_raw_output(t, temperature_unit))
+                .transpose()
+                .context("temperature_unit")?,
             time_needed_unit: input.time_needed_unit,
-            temperature: input.temperature.map(|t| {
-                unit_helper::temp_interval_unit_raw_output(
-                    t,
-                    input
-                        .temperature_unit
-                        .clone()
-                        .expect("temperature value specified without unit")
-                        .as_ref(),
-                )
-            }),
             temperature_unit: input.temperature_unit,
             instructions: input.instructions,
             ingredients: if input.ingredients.is_empty() {
                 None
             } else {
-                Some(input.ingredients.into_iter().map(Into::into).collect())
+                Some(
+                    input
+                        .ingredients
+                        .into_iter()
+                        .map(|i| {
+                            let name = i.name.clone();
+                            Ingredient::try_from(i).with_context(|| format!("Ingredient {name}"))
+                        })
+                        .collect::<anyhow::Result<_>>()?,
+                )
             },
             equipment: if input.equipment.is_empty() {
                 None
             } else {
                 Some(input.equipment.into_iter().map(Into::into).collect())
             },
+            // filled in by Recipe::collect_references
+            ingredient_references: None,
+            equipment_references: None,
             step_type: input.step_type.into(),
+            sub_recipe: input.sub_recipe.map(Into::into),
+            depends_on: if input.depends_on.is_empty() {
+                None
+            } else {
+                Some(input.depends_on)
+            },
+            parallel: input.parallel.then_some(true),
+            photos: if input.photos.is_empty() {
+                None
+            } else {
+                Some(input.photos.into_iter().map(Into::into).collect())
+            },
+        })
+    }
+}
+
+impl From<photo::Photo> for Photo {
+    fn from(input: photo::Photo) -> Self {
+        Self {
+            file: input.file,
+            caption: input.caption,
+        }
+    }
+}
+
+impl From<step::SubRecipe> for SubRecipe {
+    fn from(input: step::SubRecipe) -> Self {
+        Self {
+            id: input.id,
+            quantity: match input.quantity {
+                step::SubRecipeQuantity::Batches(b) => SubRecipeQuantity::Batches(b),
+                step::SubRecipeQuantity::Amount(a) => SubRecipeQuantity::Amount(a),
+            },
         }
     }
 }
@@ -204,34 +710,128 @@ impl From<equipment::Equipment> for Equipment {
             id: input.id,
             name: input.name,
             description: input.description,
-            is_owned: input.is_owned,
+            // ownership is only stored in the equipment inventory
+            is_owned: None,
         }
     }
 }
 
-impl From<ingredient::Ingredient> for Ingredient {
-    fn from(input: ingredient::Ingredient) -> Self {
+impl From<equipment_inventory::EquipmentInventory> for EquipmentInventory {
+    fn from(input: equipment_inventory::EquipmentInventory) -> Self {
+        let mut equipment: Vec<InventoryEntry> = input.entries.into_values().map(Into::into).collect();
+        equipment.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { equipment }
+    }
+}
+
+impl From<equipment_inventory::InventoryEntry> for InventoryEntry {
+    fn from(input: equipment_inventory::InventoryEntry) -> Self {
         Self {
             id: input.id,
             name: input.name,
-            description: input.description,
-            unit_quantity: input.unit_quantity.into(),
+            // owned is the default, so only write it out for equipment that isn't owned
+            is_owned: (!input.is_owned).then_some(false),
         }
     }
 }
 
-impl From<ingredient::UnitType> for UnitType {
-    fn from(input: ingredient::UnitType) -> Self {
-        match input {
-            ingredient::UnitType::Quantity(q) => Self::Quantity(q),
+impl TryFrom<ingredient::Ingredient> for Ingredient {
+    type Error = UnitParseError;
+
+    fn try_from(input: ingredient::Ingredient) -> Result<Self, UnitParseError> {
+        Ok(Self {
+            id: input.id,
+            name: input.name,
+            description: input.description,
+            unit_quantity: input.unit_quantity.try_into()?,
+            alternatives: if input.alternatives.is_empty() {
+                None
+            } else {
+                Some(
+                    input
+                        .alternatives
+                        .into_iter()
+                        .map(TryInto::try_into)
+                        .collect::<Result<_, _>>()?,
+                )
+            },
+            optional: input.optional.then_some(true),
+            optional_reason: input.optional_reason,
+            preparation: input.preparation,
+            divided: input.divided.then_some(true),
+        })
+    }
+}
+
+impl TryFrom<ingredient::Alternative> for Alternative {
+    type Error = UnitParseError;
+
+    fn try_from(input: ingredient::Alternative) -> Result<Self, UnitParseError> {
+        Ok(Self {
+            id: input.id,
+            name: input.name,
+            unit_quantity: input.unit_quantity.map(TryInto::try_into).transpose()?,
+            ratio: input.ratio,
+        })
+    }
+}
+
+impl TryFrom<ingredient::UnitType> for UnitType {
+    type Error = UnitParseError;
+
+    fn try_from(input: ingredient::UnitType) -> Result<Self, UnitParseError> {
+        Ok(match input {
+            ingredient::UnitType::Quantity { value, unit: None } => Self::Quantity(CountQuantity::Bare(value)),
+            ingredient::UnitType::Quantity { value, unit: Some(unit) } => Self::Quantity(CountQuantity::Named { value, unit }),
             ingredient::UnitType::Mass { value: m, unit: u } => Self::Mass {
-                value: unit_helper::mass_unit_raw_output(m, u.as_ref()),
+                value: unit_helper::mass_unit_raw_output(m, u.as_ref())?,
                 unit: u,
             },
             ingredient::UnitType::Volume { value: v, unit: u } => Self::Volume {
-                value: unit_helper::volume_unit_raw_output(v, u.as_ref()),
+                value: unit_helper::volume_unit_raw_output(v, u.as_ref())?,
                 unit: u,
             },
+        })
+    }
+}
+
+impl From<cook_log::CookLog> for CookLog {
+    fn from(input: cook_log::CookLog) -> Self {
+        let mut entries: Vec<CookLogEntry> = input
+            .entries
+            .into_iter()
+            .flat_map(|(recipe_id, history)| {
+                history.into_iter().map(move |e| CookLogEntry {
+                    recipe_id,
+                    date: e.date,
+                    cook: e.cook,
+                    scale: (e.scale != Rational64::from_integer(1)).then_some(e.scale),
+                    rating: e.rating,
+                    notes: e.notes,
+                })
+            })
+            .collect();
+        // sort_by is stable, so entries for the same recipe on the same day stay in order
+        entries.sort_by(|a, b| a.date.cmp(&b.date).then(a.recipe_id.cmp(&b.recipe_id)));
+        Self { entries }
+    }
+}
+
+impl From<AmountMadeQuantity> for Rational64 {
+    fn from(input: AmountMadeQuantity) -> Self {
+        match input {
+            // amounts too large for an i64 are clamped, as no recipe makes that much
+            AmountMadeQuantity::Whole(w) => Rational64::from_integer(i64::try_from(w).unwrap_or(i64::MAX)),
+            AmountMadeQuantity::Rational(r) => r,
+        }
+    }
+}
+
+impl From<Rational64> for AmountMadeQuantity {
+    fn from(input: Rational64) -> Self {
+        match u64::try_from(input.to_integer()) {
+            Ok(w) if input.is_integer() => Self::Whole(w),
+            _ => Self::Rational(input),
         }
     }
 }
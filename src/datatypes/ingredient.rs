@@ -1,20 +1,35 @@
-use std::ops::{Add, AddAssign};
+use std::error::Error;
+use std::fmt;
+
+use anyhow::Context;
 
 #[cfg(feature = "tui")]
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_rational::Rational64;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul};
 #[cfg(feature = "tui")]
 use ranged_wrapping::RangedWrapping;
 #[cfg(feature = "tui")]
 use ratatui::{style::Stylize, widgets::Widget};
 use serde::Serialize;
-use uom::si::rational64::{Mass, Volume};
+use uom::{
+    fmt::DisplayStyle,
+    si::{
+        mass::kilogram,
+        rational64::{Mass, Volume},
+        volume::cubic_meter,
+    },
+};
 use uuid::Uuid;
 
 #[cfg(feature = "tui")]
 use cookbook_macros::{StatefulWidgetRef, WidgetRef};
 
-use super::{filetypes, unit_helper};
+use super::{
+    filetypes,
+    unit_helper::{self, FractionStyle, MeasurementSystem, UnitParseError},
+    unit_registry::UnitKind,
+};
 
 //let unit_block = Block::default()
 //    .borders(Borders::ALL)
@@ -46,9 +61,40 @@ pub struct Ingredient {
     /// Unit and quantity of ingredient
     #[cfg_attr(feature = "tui", cookbook(skip))] //TODO: unit quantity stuff
     pub unit_quantity: UnitType,
+    /// ingredients that can be used instead of this one
+    #[cfg_attr(feature = "tui", cookbook(skip))]
+    pub alternatives: Vec<Alternative>,
+    /// if the ingredient can be left out of the recipe
+    #[cfg_attr(feature = "tui", cookbook(skip))]
+    pub optional: bool,
+    /// why the ingredient is optional. Ex: for garnish
+    #[cfg_attr(feature = "tui", cookbook(skip))]
+    pub optional_reason: Option<String>,
+    /// how the ingredient is prepared for this step. Ex: diced, softened
+    #[cfg_attr(feature = "tui", cookbook(skip))]
+    pub preparation: Option<String>,
+    /// if the ingredient is split between several steps, and this is only part of the total
+    #[cfg_attr(feature = "tui", cookbook(skip))]
+    pub divided: bool,
     //TODO: inventory reference
 }
 
+/// `Alternative` is an ingredient that can be substituted for another ingredient.
+/// Ex: butter OR 3/4 the amount of oil
+#[derive(Default, Debug, Clone, Eq, PartialEq, Serialize, Hash)]
+pub struct Alternative {
+    /// database ID
+    pub id: Uuid,
+    /// ingredient short name
+    pub name: String,
+    /// Unit and quantity of the alternative. If not specified, the amount is the amount of the
+    /// original ingredient multiplied by `ratio`
+    pub unit_quantity: Option<UnitType>,
+    /// amount of the alternative to use relative to the original ingredient.
+    /// Ignored if `unit_quantity` is specified. Defaults to 1
+    pub ratio: Option<Rational64>,
+}
+
 /// `UnitType` handles different unit types for an ingredient and allows flexibility rather than
 /// needing to have 1 ingredient type per unit type
 #[non_exhaustive]
@@ -56,7 +102,10 @@ pub struct Ingredient {
 pub enum UnitType {
     /// Represents a count or physical quantity of an `Ingredient`:
     /// Ex: 30 chocolate chips, 5 bananas, 10 carrots etc.
-    Quantity(Rational64),
+    ///
+    /// `unit` is what is being counted when it isn't the ingredient itself, like the `can` in 2
+    /// cans of tomatoes, stored in the singular
+    Quantity { value: Rational64, unit: Option<String> },
     /// Mass of an `Ingredient`
     Mass { value: Mass, unit: String },
     /// Volume of an `Ingredent`
@@ -86,73 +135,439 @@ impl Default for State {
     }
 }
 
-impl Add for UnitType {
-    type Output = Self;
+impl Default for UnitType {
+    fn default() -> Self {
+        Self::Quantity {
+            value: Rational64::default(),
+            unit: None,
+        }
+    }
+}
 
-    //TODO: decide if adding two UnitTypes with different unit's is acceptable
-    #[expect(clippy::arithmetic_side_effects)] //TODO: fix this
-    fn add(self, other: Self) -> Self {
-        match (self, other) {
-            (Self::Quantity(l), Self::Quantity(r)) => Self::Quantity(l + r),
-            (Self::Mass { value: l, unit: lu }, Self::Mass { value: r, unit: ru }) => {
-                let value = l + r;
+/// `UnitTypeError` is returned when arithmetic on [`UnitType`]s fails
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnitTypeError {
+    /// The two values are of different kinds, like a mass and a volume, and can't be combined
+    IncompatibleKinds {
+        /// kind of the left hand value
+        lhs: &'static str,
+        /// kind of the right hand value
+        rhs: &'static str,
+    },
+    /// The two values are counts of different things, like cans and cloves, and can't be combined
+    IncompatibleUnits {
+        /// count unit of the left hand value, if it has one
+        lhs: Option<String>,
+        /// count unit of the right hand value, if it has one
+        rhs: Option<String>,
+    },
+    /// The result does not fit within a `Rational64`
+    Overflow,
+}
 
-                if lu != ru {
-                    panic!("attempted to add two unit types together with different file units")
-                }
+impl fmt::Display for UnitTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IncompatibleKinds { lhs, rhs } => write!(f, "can not combine a {lhs} with a {rhs}"),
+            Self::IncompatibleUnits { lhs, rhs } => {
+                let name = |unit: &Option<String>| {
+                    unit.as_deref()
+                        .map_or_else(|| "items".to_owned(), unit_helper::count_unit_plural)
+                };
+                write!(f, "can not combine a count of {} with a count of {}", name(lhs), name(rhs))
+            }
+            Self::Overflow => write!(f, "quantity overflowed"),
+        }
+    }
+}
 
-                Self::Mass { value, unit: lu }
+impl Error for UnitTypeError {}
+
+impl UnitType {
+    /// `kind` returns the name of the kind of quantity this is
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Quantity { .. } => "quantity",
+            Self::Mass { .. } => "mass",
+            Self::Volume { .. } => "volume",
+        }
+    }
+
+    /// `unit` returns the unit abbreviation used for display and output, or the count unit of a
+    /// quantity, if there is one
+    #[must_use]
+    pub fn unit(&self) -> Option<&str> {
+        match self {
+            Self::Quantity { unit, .. } => unit.as_deref(),
+            Self::Mass { unit, .. } | Self::Volume { unit, .. } => Some(unit),
+        }
+    }
+
+    /// `is_compatible` returns true if `self` and `other` can be added together or compared
+    /// directly: both masses, both volumes, or counts of the same thing
+    #[must_use]
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Quantity { unit: l, .. }, Self::Quantity { unit: r, .. }) => {
+                unit_helper::same_count_unit(l.as_deref(), r.as_deref())
+            }
+            (Self::Mass { .. }, Self::Mass { .. }) | (Self::Volume { .. }, Self::Volume { .. }) => true,
+            _ => false,
+        }
+    }
+
+    /// `readable` returns the quantity formatted for display in the easiest to read unit from
+    /// `system`, like `2 tsp` rather than a fraction of a cup, with fractions written in `style`.
+    /// If `system` is `None`, the system the quantity was specified in is used
+    #[must_use]
+    pub fn readable(&self, system: Option<MeasurementSystem>, style: FractionStyle) -> String {
+        match self {
+            Self::Quantity { value, unit: None } => unit_helper::approximate_fraction(*value, style),
+            Self::Quantity { value, unit: Some(unit) } => format!(
+                "{} {}",
+                unit_helper::approximate_fraction(*value, style),
+                unit_helper::count_unit_name(unit, *value)
+            ),
+            Self::Mass { value, unit } => {
+                unit_helper::mass_display(*value, system.unwrap_or_else(|| MeasurementSystem::of_unit(unit)), style)
+            }
+            Self::Volume { value, unit } => {
+                unit_helper::volume_display(*value, system.unwrap_or_else(|| MeasurementSystem::of_unit(unit)), style)
             }
+        }
+    }
 
-            (Self::Volume { value: l, unit: lu }, Self::Volume { value: r, unit: ru }) => {
-                let value = l + r;
+    /// `checked_ratio` returns how many times `other` fits into `self`, like 250 g being 5/2 of
+    /// 100 g.
+    ///
+    /// Returns `None` if the two values are of different kinds or counts of different things,
+    /// `other` is zero, or the division overflows.
+    #[must_use]
+    pub fn checked_ratio(&self, other: &Self) -> Option<Rational64> {
+        if !self.is_compatible(other) {
+            return None;
+        }
+        match (self, other) {
+            (Self::Quantity { value: l, .. }, Self::Quantity { value: r, .. }) => l.checked_div(r),
+            (Self::Mass { value: l, .. }, Self::Mass { value: r, .. }) => l.value.checked_div(&r.value),
+            (Self::Volume { value: l, .. }, Self::Volume { value: r, .. }) => l.value.checked_div(&r.value),
+            _ => None,
+        }
+    }
 
-                if lu != ru {
-                    panic!("attempted to add two unit types together with different file units")
+    /// `checked_add` adds two values of the same kind together.
+    ///
+    /// Masses and volumes are added regardless of the unit they were specified in. The result is
+    /// normalized to the unit of `self`.
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - the two values are of different kinds, like a mass and a volume
+    /// - the two values are counts of different things, like cans and cloves
+    /// - the sum overflows
+    pub fn checked_add(&self, other: &Self) -> Result<Self, UnitTypeError> {
+        match (self, other) {
+            (Self::Quantity { value: l, unit: lu }, Self::Quantity { value: r, unit: ru }) => {
+                if !unit_helper::same_count_unit(lu.as_deref(), ru.as_deref()) {
+                    return Err(UnitTypeError::IncompatibleUnits {
+                        lhs: lu.clone(),
+                        rhs: ru.clone(),
+                    });
                 }
-
-                Self::Volume { value, unit: lu }
+                l.checked_add(r)
+                    .map(|value| Self::Quantity { value, unit: lu.clone() })
+                    .ok_or(UnitTypeError::Overflow)
             }
+            (Self::Mass { value: l, unit }, Self::Mass { value: r, .. }) => l
+                .value
+                .checked_add(&r.value)
+                .map(|v| Self::Mass {
+                    value: Mass::new::<kilogram>(v),
+                    unit: unit.clone(),
+                })
+                .ok_or(UnitTypeError::Overflow),
+            (Self::Volume { value: l, unit }, Self::Volume { value: r, .. }) => l
+                .value
+                .checked_add(&r.value)
+                .map(|v| Self::Volume {
+                    value: Volume::new::<cubic_meter>(v),
+                    unit: unit.clone(),
+                })
+                .ok_or(UnitTypeError::Overflow),
+            (l, r) => Err(UnitTypeError::IncompatibleKinds {
+                lhs: l.kind(),
+                rhs: r.kind(),
+            }),
+        }
+    }
 
-            _ => panic!("Attempted to add different unit types together. This should not have happened"),
+    /// `checked_scale` multiplies the quantity by `factor` using exact rational arithmetic,
+    /// keeping the unit used for display and output.
+    ///
+    /// Returns `None` if the multiplication overflows.
+    #[must_use]
+    pub fn checked_scale(&self, factor: Rational64) -> Option<Self> {
+        // uom stores rational quantities in their base SI unit, so scaling the raw value
+        // keeps the conversion exact and lets us check for overflow
+        match self {
+            Self::Quantity { value, unit } => value.checked_mul(&factor).map(|v| Self::Quantity {
+                value: v,
+                unit: unit.clone(),
+            }),
+            Self::Mass { value, unit } => value.value.checked_mul(&factor).map(|v| Self::Mass {
+                value: Mass::new::<kilogram>(v),
+                unit: unit.clone(),
+            }),
+            Self::Volume { value, unit } => value.value.checked_mul(&factor).map(|v| Self::Volume {
+                value: Volume::new::<cubic_meter>(v),
+                unit: unit.clone(),
+            }),
         }
     }
 }
-impl AddAssign for UnitType {
-    #[expect(clippy::arithmetic_side_effects)] //TODO: fix this
-    fn add_assign(&mut self, other: Self) {
-        *self = self.clone() + other;
+
+impl Ingredient {
+    /// `alternative_amount` returns the amount of `alternative` to use in place of this
+    /// ingredient.
+    ///
+    /// Returns `None` if scaling by the alternative ratio overflows.
+    #[must_use]
+    pub fn alternative_amount(&self, alternative: &Alternative) -> Option<UnitType> {
+        match &alternative.unit_quantity {
+            Some(unit_quantity) => Some(unit_quantity.clone()),
+            None => self
+                .unit_quantity
+                .checked_scale(alternative.ratio.unwrap_or_else(|| Rational64::from_integer(1))),
+        }
+    }
+
+    /// `substitute` returns the alternative with id `alternative_id` as an ingredient, in the
+    /// amount used in place of this ingredient.
+    ///
+    /// Returns `None` if there is no alternative with that id, or calculating the amount of the
+    /// alternative overflows.
+    #[must_use]
+    pub fn substitute(&self, alternative_id: Uuid) -> Option<Self> {
+        let alternative = self.alternatives.iter().find(|a| a.id == alternative_id)?;
+        Some(Self {
+            id: alternative.id,
+            name: alternative.name.clone(),
+            description: None,
+            unit_quantity: self.alternative_amount(alternative)?,
+            alternatives: Vec::new(),
+            optional: self.optional,
+            optional_reason: self.optional_reason.clone(),
+            preparation: self.preparation.clone(),
+            divided: self.divided,
+        })
+    }
+
+    /// `checked_scale` multiplies the amount of the ingredient and any alternatives with a
+    /// specified amount by `factor`.
+    ///
+    /// Returns `None` if any of the multiplications overflow.
+    #[must_use]
+    pub fn checked_scale(&self, factor: Rational64) -> Option<Self> {
+        let mut scaled = self.clone();
+        scaled.unit_quantity = self.unit_quantity.checked_scale(factor)?;
+        for alternative in &mut scaled.alternatives {
+            // alternatives specified with a ratio follow the original ingredient
+            if let Some(unit_quantity) = &alternative.unit_quantity {
+                alternative.unit_quantity = Some(unit_quantity.checked_scale(factor)?);
+            }
+        }
+        Some(scaled)
+    }
+
+    /// `alternatives_text` returns the alternatives formatted for display, with amounts in units
+    /// from `system` and fractions in `style` as in [`UnitType::readable`].
+    /// Ex: ` or Oil: ¾ cup`. Returns an empty string if there are no alternatives
+    #[must_use]
+    pub fn alternatives_text(&self, system: Option<MeasurementSystem>, style: FractionStyle) -> String {
+        self.alternatives
+            .iter()
+            .map(|a| match self.alternative_amount(a) {
+                Some(amount) => format!(" or {}: {}", a.name, amount.readable(system, style)),
+                None => format!(" or {}", a.name),
+            })
+            .collect()
+    }
+
+    /// `optional_text` returns a note that the ingredient is optional, along with the reason if
+    /// there is one. Ex: ` (optional, for garnish)`. Returns an empty string if the ingredient is
+    /// required
+    #[must_use]
+    pub fn optional_text(&self) -> String {
+        match (&self.optional, &self.optional_reason) {
+            (false, _) => String::new(),
+            (true, Some(reason)) => format!(" (optional, {reason})"),
+            (true, None) => " (optional)".to_owned(),
+        }
+    }
+
+    /// `preparation_text` returns how the ingredient is prepared, and a note if it is divided
+    /// between steps. Ex: `, diced (divided)`. Returns an empty string if neither is specified
+    #[must_use]
+    pub fn preparation_text(&self) -> String {
+        let mut text = self.preparation.as_ref().map(|p| format!(", {p}")).unwrap_or_default();
+        if self.divided {
+            text.push_str(" (divided)");
+        }
+        text
     }
 }
-impl Default for UnitType {
-    fn default() -> Self {
-        Self::Quantity(Rational64::default())
+
+impl fmt::Display for UnitType {
+    /// formats the quantity in the unit it was specified in, or in the SI base unit if that
+    /// isn't a supported unit
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Quantity { value, unit: None } => write!(f, "{value}"),
+            Self::Quantity { value, unit: Some(unit) } => write!(f, "{value} {}", unit_helper::count_unit_name(unit, *value)),
+            Self::Mass { value, unit } => match unit_helper::mass_unit_format_output(*value, unit, DisplayStyle::Abbreviation) {
+                Ok(text) => write!(f, "{text}"),
+                Err(_) => write!(f, "{} {}", value.value, UnitKind::Mass.base_unit()),
+            },
+            Self::Volume { value, unit } => {
+                match unit_helper::volume_unit_format_output(*value, unit, DisplayStyle::Abbreviation) {
+                    Ok(text) => write!(f, "{text}"),
+                    Err(_) => write!(f, "{} {}", value.value, UnitKind::Volume.base_unit()),
+                }
+            }
+        }
     }
 }
 
-impl From<filetypes::Ingredient> for Ingredient {
-    fn from(input: filetypes::Ingredient) -> Self {
-        Self {
+impl TryFrom<filetypes::Ingredient> for Ingredient {
+    type Error = anyhow::Error;
+
+    fn try_from(input: filetypes::Ingredient) -> anyhow::Result<Self> {
+        Ok(Self {
             id: input.id,
+            unit_quantity: input
+                .unit_quantity
+                .try_into()
+                .with_context(|| format!("Ingredient {} unit_quantity", input.name))?,
+            alternatives: input
+                .alternatives
+                .unwrap_or_default()
+                .into_iter()
+                .map(|a| {
+                    let name = a.name.clone();
+                    Alternative::try_from(a).with_context(|| format!("Ingredient {} alternative {name}", input.name))
+                })
+                .collect::<anyhow::Result<_>>()?,
             name: input.name,
             description: input.description,
-            unit_quantity: input.unit_quantity.into(),
-        }
+            optional: input.optional.unwrap_or_default(),
+            optional_reason: input.optional_reason,
+            preparation: input.preparation,
+            divided: input.divided.unwrap_or_default(),
+        })
     }
 }
 
-impl From<filetypes::UnitType> for UnitType {
-    fn from(input: filetypes::UnitType) -> Self {
-        match input {
-            filetypes::UnitType::Quantity(q) => Self::Quantity(q),
-            filetypes::UnitType::Mass { value: m, unit: u } => Self::Mass {
-                value: unit_helper::mass_unit_input_parser(m, u.as_str()),
-                unit: u,
-            },
-            filetypes::UnitType::Volume { value: v, unit: u } => Self::Volume {
-                value: unit_helper::volume_unit_input_parser(v, u.as_str()),
-                unit: u,
-            },
+impl TryFrom<filetypes::Alternative> for Alternative {
+    type Error = anyhow::Error;
+
+    fn try_from(input: filetypes::Alternative) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: input.id,
+            name: input.name,
+            unit_quantity: input
+                .unit_quantity
+                .map(TryInto::try_into)
+                .transpose()
+                .context("unit_quantity")?,
+            ratio: input.ratio,
+        })
+    }
+}
+
+impl TryFrom<filetypes::UnitType> for UnitType {
+    type Error = UnitParseError;
+
+    fn try_from(input: filetypes::UnitType) -> Result<Self, UnitParseError> {
+        Ok(match input {
+            // count units are stored in the singular, so `2 cans` and `1 can` are counts of the
+            // same thing
+            filetypes::UnitType::Quantity(q) => {
+                let (value, unit) = match q {
+                    filetypes::CountQuantity::Bare(value) => (value, None),
+                    filetypes::CountQuantity::Named { value, unit } => (value, Some(unit)),
+                };
+                Self::Quantity {
+                    value,
+                    unit: unit.map(|u| unit_helper::count_unit_singular(&u)).filter(|u| !u.is_empty()),
+                }
+            }
+            // units are stored as their abbreviation, however they were written in the file
+            filetypes::UnitType::Mass { value: m, unit: u } => {
+                let unit = unit_helper::mass_unit_abbreviation(u.as_str())?;
+                Self::Mass {
+                    value: unit_helper::mass_unit_input_parser(m, unit)?,
+                    unit: unit.to_owned(),
+                }
+            }
+            filetypes::UnitType::Volume { value: v, unit: u } => {
+                let unit = unit_helper::volume_unit_abbreviation(u.as_str())?;
+                Self::Volume {
+                    value: unit_helper::volume_unit_input_parser(v, unit)?,
+                    unit: unit.to_owned(),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_rational::Rational64;
+
+    use super::UnitType;
+    use crate::datatypes::unit_helper::{
+        mass_unit_input_parser, mass_unit_raw_output, volume_unit_input_parser, volume_unit_raw_output,
+    };
+
+    /// `volume` returns `value` `unit`s as a [`UnitType::Volume`]
+    fn volume(value: i64, unit: &str) -> UnitType {
+        UnitType::Volume {
+            value: volume_unit_input_parser(Rational64::from_integer(value), unit).expect("volume parses"),
+            unit: unit.to_owned(),
+        }
+    }
+
+    /// `mass` returns `value` `unit`s as a [`UnitType::Mass`]
+    fn mass(value: i64, unit: &str) -> UnitType {
+        UnitType::Mass {
+            value: mass_unit_input_parser(Rational64::from_integer(value), unit).expect("mass parses"),
+            unit: unit.to_owned(),
+        }
+    }
+
+    /// `raw_value` returns `amount` in the unit it is displayed in
+    fn raw_value(amount: &UnitType) -> Rational64 {
+        match amount {
+            UnitType::Mass { value, unit } => mass_unit_raw_output(*value, unit).expect("mass converts"),
+            UnitType::Volume { value, unit } => volume_unit_raw_output(*value, unit).expect("volume converts"),
+            UnitType::Quantity { value, .. } => *value,
+        }
+    }
+
+    #[test]
+    fn checked_add_combines_kitchen_units() {
+        for (lhs, rhs, sum) in [
+            (volume(1, "tsp"), volume(1, "tbsp"), Rational64::from_integer(4)),
+            (volume(1, "tbsp"), volume(1, "tsp"), Rational64::new(4, 3)),
+            (volume(1, "cup"), volume(1, "tbsp"), Rational64::new(17, 16)),
+            (volume(1, "cup"), volume(1, "tsp"), Rational64::new(49, 48)),
+            (mass(1, "lb"), mass(4, "oz"), Rational64::new(5, 4)),
+        ] {
+            let total = lhs.checked_add(&rhs).expect("amounts add");
+            assert_eq!(raw_value(&total), sum, "{lhs} + {rhs}");
         }
     }
 }
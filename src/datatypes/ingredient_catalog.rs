@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use num_rational::Rational64;
+use num_traits::{CheckedAdd, CheckedMul, ToPrimitive};
+use serde::Serialize;
+use uom::si::{
+    energy::{joule, kilocalorie},
+    mass::{gram, kilogram, milligram},
+    rational64::{Energy, Mass, MassDensity},
+};
+use uuid::Uuid;
+
+use super::{
+    dietary::{Diet, Dietary},
+    filetypes,
+    ingredient::{Ingredient, UnitType},
+    unit_helper::{self, UnitParseError},
+};
+
+/// name of the ingredient catalog file within the recipe directory
+pub const CATALOG_FILE_NAME: &str = "ingredient_catalog.toml";
+
+/// `NutritionError` is returned when the nutrients in an amount of an ingredient can't be worked
+/// out
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NutritionError {
+    /// The ingredient isn't in the catalog, or its entry has no nutrition facts
+    Missing,
+    /// The amount couldn't be converted to the amount the nutrition facts are given for, like a
+    /// volume of an ingredient without a density, or the calculation overflowed
+    Unconverted,
+}
+
+impl fmt::Display for NutritionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "no nutrition facts in the ingredient catalog"),
+            Self::Unconverted => write!(f, "amount could not be converted to the amount the nutrition facts are for"),
+        }
+    }
+}
+
+impl Error for NutritionError {}
+
+/// `IngredientCatalog` holds physical properties of ingredients, keyed by the `id` of the
+/// [`Ingredient`]. It is used to convert between masses, volumes and counts of the same
+/// ingredient, and to work out the nutrition and dietary properties of recipes.
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct IngredientCatalog {
+    /// catalog entries keyed by ingredient ID
+    pub entries: HashMap<Uuid, CatalogEntry>,
+}
+
+/// `CatalogEntry` holds the physical properties of a single ingredient
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct CatalogEntry {
+    /// database ID. Matches the `id` of the ingredient in recipes
+    pub id: Uuid,
+    /// ingredient short name
+    pub name: String,
+    /// density of the ingredient, used to convert between mass and volume
+    pub density: Option<MassDensity>,
+    /// typical mass of one of the ingredient, like one egg or one banana. Used to convert between
+    /// counts and mass
+    pub unit_mass: Option<Mass>,
+    /// how much of the ingredient is in one of each count unit, like one stick of butter. Used to
+    /// convert between counts of these units and masses or volumes
+    pub count_units: Vec<CountUnit>,
+    /// nutrients in an amount of the ingredient
+    pub nutrition: Option<NutritionFacts>,
+    /// allergens the ingredient contains and diets it is suitable for. `None` if this isn't known
+    pub dietary: Option<Dietary>,
+}
+
+/// `CountUnit` is how much of an ingredient is in one of a count unit, like 113 g in one stick of
+/// butter
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct CountUnit {
+    /// count unit, in the singular. Ex: stick
+    pub unit: String,
+    /// mass or volume of the ingredient in one `unit`
+    pub amount: UnitType,
+}
+
+/// `NutritionFacts` are the nutrients in a given amount of an ingredient, like a nutrition label
+/// that lists nutrients per 100 g or per serving
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct NutritionFacts {
+    /// the amount of the ingredient that contains `nutrients`
+    pub per: UnitType,
+    /// nutrients in `per` of the ingredient
+    pub nutrients: Nutrition,
+}
+
+/// `Nutrition` holds amounts of nutrients. Nutrients that aren't known are zero.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Nutrition {
+    /// food energy
+    pub energy: Energy,
+    /// protein
+    pub protein: Mass,
+    /// total fat
+    pub fat: Mass,
+    /// saturated fat, included in `fat`
+    pub saturated_fat: Mass,
+    /// total carbohydrates
+    pub carbohydrates: Mass,
+    /// sugars, included in `carbohydrates`
+    pub sugars: Mass,
+    /// dietary fiber, included in `carbohydrates`
+    pub fiber: Mass,
+    /// sodium
+    pub sodium: Mass,
+}
+
+impl Nutrition {
+    /// `checked_add` adds each nutrient of `other` to this one.
+    ///
+    /// Returns `None` if any sum overflows.
+    #[must_use]
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        // uom stores rational quantities in their base SI unit, so working with the raw values
+        // keeps the arithmetic exact and lets us check for overflow
+        let add_mass = |l: Mass, r: Mass| l.value.checked_add(&r.value).map(Mass::new::<kilogram>);
+        Some(Self {
+            energy: Energy::new::<joule>(self.energy.value.checked_add(&other.energy.value)?),
+            protein: add_mass(self.protein, other.protein)?,
+            fat: add_mass(self.fat, other.fat)?,
+            saturated_fat: add_mass(self.saturated_fat, other.saturated_fat)?,
+            carbohydrates: add_mass(self.carbohydrates, other.carbohydrates)?,
+            sugars: add_mass(self.sugars, other.sugars)?,
+            fiber: add_mass(self.fiber, other.fiber)?,
+            sodium: add_mass(self.sodium, other.sodium)?,
+        })
+    }
+
+    /// `checked_scale` multiplies each nutrient by `factor`.
+    ///
+    /// Returns `None` if any product overflows.
+    #[must_use]
+    pub fn checked_scale(&self, factor: Rational64) -> Option<Self> {
+        let scale_mass = |m: Mass| m.value.checked_mul(&factor).map(Mass::new::<kilogram>);
+        Some(Self {
+            energy: Energy::new::<joule>(self.energy.value.checked_mul(&factor)?),
+            protein: scale_mass(self.protein)?,
+            fat: scale_mass(self.fat)?,
+            saturated_fat: scale_mass(self.saturated_fat)?,
+            carbohydrates: scale_mass(self.carbohydrates)?,
+            sugars: scale_mass(self.sugars)?,
+            fiber: scale_mass(self.fiber)?,
+            sodium: scale_mass(self.sodium)?,
+        })
+    }
+
+    /// `rows` returns the name and formatted amount of each nutrient, in the order they appear on
+    /// a nutrition label. Energy is in kcal, sodium in mg and everything else in g.
+    #[must_use]
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let grams = |m: Mass| format!("{} g", unit_helper::readable_number(m.get::<gram>()));
+        vec![
+            (
+                "Energy",
+                format!("{:.0} kcal", self.energy.get::<kilocalorie>().to_f64().unwrap_or_default()),
+            ),
+            ("Protein", grams(self.protein)),
+            ("Fat", grams(self.fat)),
+            ("Saturated Fat", grams(self.saturated_fat)),
+            ("Carbohydrates", grams(self.carbohydrates)),
+            ("Sugars", grams(self.sugars)),
+            ("Fiber", grams(self.fiber)),
+            (
+                "Sodium",
+                format!("{:.0} mg", self.sodium.get::<milligram>().to_f64().unwrap_or_default()),
+            ),
+        ]
+    }
+}
+
+impl CatalogEntry {
+    /// `count_unit` returns the mass or volume of the ingredient in one `unit`, if the entry has
+    /// that count unit
+    #[must_use]
+    pub fn count_unit(&self, unit: &str) -> Option<&UnitType> {
+        let unit = unit_helper::count_unit_singular(unit);
+        self.count_units
+            .iter()
+            .find(|c| unit_helper::same_count_unit(Some(&c.unit), Some(&unit)))
+            .map(|c| &c.amount)
+    }
+
+    /// `convert` converts `value` to the same kind and unit as `target`, using the properties of
+    /// the ingredient.
+    ///
+    /// Counts of a count unit, like 2 sticks, are converted through the mass or volume of one of
+    /// that unit. Everything else is converted as in [`unit_helper::unit_type_convert`].
+    ///
+    /// Returns `None` if the entry is missing the needed density, unit mass or count unit, or the
+    /// conversion overflows.
+    #[must_use]
+    pub fn convert(&self, value: &UnitType, target: &UnitType) -> Option<UnitType> {
+        if value.is_compatible(target) {
+            return unit_helper::unit_type_convert(value, target, self.density, self.unit_mass);
+        }
+        let value = match value {
+            UnitType::Quantity {
+                value: count,
+                unit: Some(unit),
+            } => self.count_unit(unit)?.checked_scale(*count)?,
+            _ => value.clone(),
+        };
+        match target {
+            UnitType::Quantity { unit: Some(unit), .. } if !value.is_compatible(target) => {
+                let one = self.count_unit(unit)?;
+                let count = unit_helper::unit_type_convert(&value, one, self.density, self.unit_mass)?.checked_ratio(one)?;
+                Some(UnitType::Quantity {
+                    value: count,
+                    unit: Some(unit.clone()),
+                })
+            }
+            _ => unit_helper::unit_type_convert(&value, target, self.density, self.unit_mass),
+        }
+    }
+}
+
+impl IngredientCatalog {
+    /// `get` returns the catalog entry for the ingredient, if there is one
+    #[must_use]
+    pub fn get(&self, ingredient: &Ingredient) -> Option<&CatalogEntry> {
+        self.entries.get(&ingredient.id)
+    }
+
+    /// `convert` converts `value` to the same kind and unit as `target`, using the catalog entry
+    /// for the ingredient with id `ingredient_id`.
+    ///
+    /// Returns `None` if the ingredient isn't in the catalog, the entry is missing the needed
+    /// density, unit mass or count unit, or the conversion overflows.
+    #[must_use]
+    pub fn convert(&self, ingredient_id: &Uuid, value: &UnitType, target: &UnitType) -> Option<UnitType> {
+        self.entries.get(ingredient_id)?.convert(value, target)
+    }
+
+    /// `nutrition` returns the nutrients in `amount` of the ingredient with id `ingredient_id`.
+    ///
+    /// # Errors
+    ///
+    /// Will error with:
+    /// - [`NutritionError::Missing`] if the ingredient isn't in the catalog or has no nutrition
+    ///   facts
+    /// - [`NutritionError::Unconverted`] if `amount` can't be converted to the amount the
+    ///   nutrition facts are given for, or the calculation overflows
+    pub fn nutrition(&self, ingredient_id: &Uuid, amount: &UnitType) -> Result<Nutrition, NutritionError> {
+        let facts = self
+            .entries
+            .get(ingredient_id)
+            .and_then(|entry| entry.nutrition.as_ref())
+            .ok_or(NutritionError::Missing)?;
+        let converted = if amount.is_compatible(&facts.per) {
+            Some(amount.clone())
+        } else {
+            self.convert(ingredient_id, amount, &facts.per)
+        };
+        converted
+            .and_then(|converted| converted.checked_ratio(&facts.per))
+            .and_then(|ratio| facts.nutrients.checked_scale(ratio))
+            .ok_or(NutritionError::Unconverted)
+    }
+
+    /// `load_from_directory` loads the ingredient catalog from [`CATALOG_FILE_NAME`] in the
+    /// provided directory. Returns an empty catalog if the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - reading the catalog file fails
+    /// - the catalog file is not valid TOML in the catalog format
+    /// - a unit in the catalog file is not supported
+    pub fn load_from_directory<T>(dir: T) -> anyhow::Result<Self>
+    where
+        T: AsRef<Path>,
+    {
+        let path = dir.as_ref().join(CATALOG_FILE_NAME);
+        if path.is_file() {
+            Self::load(&path).with_context(|| format!("Parsing TOML file {} failed", path.display()))
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// `load` parses an ingredient catalog file
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - reading the catalog file fails
+    /// - the catalog file is not valid TOML in the catalog format
+    /// - a unit in the catalog file is not supported
+    pub fn load<T>(catalog_file: T) -> anyhow::Result<Self>
+    where
+        T: AsRef<Path>,
+    {
+        let contents = fs::read_to_string(catalog_file)?;
+        let output: filetypes::IngredientCatalog = toml::from_str(contents.as_str())?;
+        output.try_into()
+    }
+}
+
+impl TryFrom<filetypes::IngredientCatalog> for IngredientCatalog {
+    type Error = anyhow::Error;
+
+    fn try_from(input: filetypes::IngredientCatalog) -> anyhow::Result<Self> {
+        Ok(Self {
+            entries: input
+                .ingredients
+                .into_iter()
+                .map(|e| {
+                    let name = e.name.clone();
+                    CatalogEntry::try_from(e)
+                        .map(|e| (e.id, e))
+                        .with_context(|| format!("Catalog entry {name}"))
+                })
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+impl TryFrom<filetypes::CatalogEntry> for CatalogEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(input: filetypes::CatalogEntry) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: input.id,
+            name: input.name,
+            density: input
+                .density
+                .map(|d| -> anyhow::Result<MassDensity> {
+                    unit_helper::mass_density(
+                        unit_helper::mass_unit_input_parser(d.mass, d.mass_unit.as_str()).context("mass_unit")?,
+                        unit_helper::volume_unit_input_parser(d.volume, d.volume_unit.as_str()).context("volume_unit")?,
+                    )
+                    .context("volume must not be zero")
+                })
+                .transpose()
+                .context("density")?,
+            unit_mass: input
+                .unit_mass
+                .map(|m| unit_helper::mass_unit_input_parser(m.value, m.unit.as_str()))
+                .transpose()
+                .context("unit_mass")?,
+            count_units: input
+                .count_units
+                .unwrap_or_default()
+                .into_iter()
+                .map(|c| -> anyhow::Result<CountUnit> {
+                    let amount = UnitType::try_from(c.equals).with_context(|| format!("{} equals", c.unit))?;
+                    if matches!(amount, UnitType::Quantity { .. }) {
+                        anyhow::bail!("{} must equal a mass or volume", c.unit);
+                    }
+                    Ok(CountUnit {
+                        unit: unit_helper::count_unit_singular(&c.unit),
+                        amount,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()
+                .context("count_units")?,
+            nutrition: input.nutrition.map(TryInto::try_into).transpose().context("nutrition per")?,
+            // an entry with neither allergens nor diets listed has no dietary information, rather
+            // than containing no allergens
+            dietary: (input.allergens.is_some() || input.diets.is_some()).then(|| {
+                let mut dietary = Dietary {
+                    allergens: input.allergens.unwrap_or_default().into_iter().map(Into::into).collect(),
+                    diets: input.diets.unwrap_or_default().into_iter().map(Into::into).collect(),
+                };
+                // anything vegan is also vegetarian
+                if dietary.diets.contains(&Diet::Vegan) {
+                    dietary.diets.insert(Diet::Vegetarian);
+                }
+                dietary
+            }),
+        })
+    }
+}
+
+impl TryFrom<filetypes::NutritionFacts> for NutritionFacts {
+    type Error = UnitParseError;
+
+    fn try_from(input: filetypes::NutritionFacts) -> Result<Self, UnitParseError> {
+        let grams = |value: Option<Rational64>| Mass::new::<gram>(value.unwrap_or_default());
+        Ok(Self {
+            per: input.per.try_into()?,
+            nutrients: Nutrition {
+                energy: Energy::new::<kilocalorie>(input.energy.unwrap_or_default()),
+                protein: grams(input.protein),
+                fat: grams(input.fat),
+                saturated_fat: grams(input.saturated_fat),
+                carbohydrates: grams(input.carbohydrates),
+                sugars: grams(input.sugars),
+                fiber: grams(input.fiber),
+                sodium: Mass::new::<milligram>(input.sodium.unwrap_or_default()),
+            },
+        })
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+
+use super::filetypes;
+
+/// image file extensions that can be attached as photos, and their content types
+pub const IMAGE_TYPES: [(&str, &str); 6] = [
+    ("avif", "image/avif"),
+    ("gif", "image/gif"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("png", "image/png"),
+    ("webp", "image/webp"),
+];
+
+/// `Photo` is an image attached to a recipe or step, like a photo of the finished dish or of a
+/// tricky step.
+///
+/// The image file lives in the cookbook directory alongside the recipe files, so it is tracked in
+/// the same git repository.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Photo {
+    /// path of the image file, relative to the cookbook directory
+    pub file: PathBuf,
+    /// optional caption, also used as the description of the image for screen readers
+    pub caption: Option<String>,
+}
+
+impl Photo {
+    /// `content_type` returns the content type of the image file, based on its extension.
+    /// Returns `None` if the file is not a supported image type
+    #[must_use]
+    pub fn content_type(&self) -> Option<&'static str> {
+        content_type(&self.file)
+    }
+
+    /// `is_valid_path` checks that `path` is a relative path that stays within the cookbook
+    /// directory, so it can't be used to refer to other files on the system
+    #[must_use]
+    pub fn is_valid_path(path: &Path) -> bool {
+        path.components().next().is_some() && path.components().all(|c| matches!(c, Component::Normal(_)))
+    }
+
+    /// `check` checks that the photo is a supported image type, and that the image file exists
+    /// within the cookbook directory `dir`
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - the photo path is not relative to the cookbook directory, or leaves it
+    /// - the photo is not a supported image type
+    /// - the image file does not exist
+    pub fn check<T>(&self, dir: T) -> anyhow::Result<()>
+    where
+        T: AsRef<Path>,
+    {
+        if !Self::is_valid_path(&self.file) {
+            anyhow::bail!(
+                "Photo {} must be a path within the cookbook directory, like photos/dish.jpg",
+                self.file.display()
+            );
+        }
+        if self.content_type().is_none() {
+            anyhow::bail!(
+                "Photo {} is not a supported image type. Supported types are: {}",
+                self.file.display(),
+                IMAGE_TYPES.map(|(ext, _)| ext).join(", ")
+            );
+        }
+        if !dir.as_ref().join(&self.file).is_file() {
+            anyhow::bail!("Photo {} not found in {}", self.file.display(), dir.as_ref().display());
+        }
+        Ok(())
+    }
+
+    /// `save` writes uploaded image data to a new file in the cookbook directory `dir`, named
+    /// after `file_name`, and returns a [`Photo`] referring to it.
+    ///
+    /// Characters other than letters, numbers, `-`, `_` and `.` in `file_name` are replaced with
+    /// `_`. If a file with that name already exists, a number is added to the name rather than
+    /// overwriting it.
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - `file_name` is not a supported image type
+    /// - writing the image file fails
+    pub fn save<T>(dir: T, file_name: &str, data: &[u8]) -> anyhow::Result<Self>
+    where
+        T: AsRef<Path>,
+    {
+        // only keep the last component, browsers may send the full path of the uploaded file
+        let file_name = file_name.rsplit(['/', '\\']).next().unwrap_or_default();
+        let file_name: String = file_name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let file = PathBuf::from(file_name.trim_start_matches('.'));
+        if content_type(&file).is_none() {
+            anyhow::bail!(
+                "{file_name} is not a supported image type. Supported types are: {}",
+                IMAGE_TYPES.map(|(ext, _)| ext).join(", ")
+            );
+        }
+        let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("photo").to_owned();
+        let ext = file.extension().and_then(|e| e.to_str()).unwrap_or_default().to_owned();
+        let mut file = PathBuf::from(format!("{stem}.{ext}"));
+        let mut count = 1_u32;
+        while dir.as_ref().join(&file).exists() {
+            count = count.saturating_add(1);
+            file = PathBuf::from(format!("{stem}-{count}.{ext}"));
+        }
+        fs::write(dir.as_ref().join(&file), data)?;
+        Ok(Self { file, caption: None })
+    }
+}
+
+/// `content_type` returns the content type of an image file, based on its extension.
+/// Returns `None` if the file is not a supported image type
+#[must_use]
+pub fn content_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    IMAGE_TYPES
+        .iter()
+        .find(|(e, _)| *e == ext)
+        .map(|(_, content_type)| *content_type)
+}
+
+impl fmt::Display for Photo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some(caption) = &self.caption {
+            write!(f, ": {caption}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<filetypes::Photo> for Photo {
+    fn from(input: filetypes::Photo) -> Self {
+        Self {
+            file: input.file,
+            caption: input.caption,
+        }
+    }
+}
@@ -1,13 +1,14 @@
 use std::fs;
-use std::io;
-use std::path::Path;
-use std::{
-    collections::{HashMap, HashSet},
-    fmt,
-};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::{cmp::Ordering, collections::HashMap, fmt};
 
+use anyhow::Context;
+use log::warn;
 #[cfg(feature = "tui")]
 use num_derive::{FromPrimitive, ToPrimitive};
+use num_rational::Rational64;
+use num_traits::{CheckedDiv, CheckedMul, Zero};
 #[cfg(feature = "tui")]
 use ranged_wrapping::RangedWrapping;
 #[cfg(feature = "tui")]
@@ -20,17 +21,19 @@ use uuid::Uuid;
 use cookbook_macros::{StatefulWidgetRef, WidgetRef};
 
 use super::{
+    cook_log,
+    dietary::{Diet, DietaryFilter, RecipeDietary},
     equipment::Equipment,
+    equipment_inventory::{self, EquipmentInventory},
     filetypes,
-    ingredient::Ingredient,
-    step::{Step, StepType},
-    tag::Tag,
+    ingredient::{Alternative, Ingredient, UnitType, UnitTypeError},
+    ingredient_catalog::{self, IngredientCatalog, Nutrition, NutritionError},
+    photo::Photo,
+    step::{Step, StepType, SubRecipeQuantity, TimeRange},
+    tag::{Tag, TagAliases, TagGroups},
+    unit_helper::{self, FractionStyle, UnitParseError},
 };
 
-//TODO: associate equipment with recipe and steps, so you don't have to re-enter info for equipment
-//that is used on multiple steps. Maybe do this with ingredients as well? May have to use ref_cell
-//for this
-//
 //TODO: change the macro generating the rendering to print list of steps with ingredients/equipment at
 //the top for display only
 //
@@ -52,8 +55,8 @@ pub struct Recipe {
     #[cfg_attr(feature = "tui", cookbook(constraint_type = "Min"))]
     #[cfg_attr(feature = "tui", cookbook(constraint_value = 7))]
     pub description: Option<String>,
-    //TODO: maybe make comments a bit more formal, want to be able to record when recipe was last
-    //made
+    //TODO: maybe make comments a bit more formal. When a recipe was made is recorded in
+    //[`crate::datatypes::cook_log::CookLog`]
     /// recipe comments
     #[cfg_attr(feature = "tui", cookbook(display_order = 2))]
     #[cfg_attr(feature = "tui", cookbook(constraint_type = "Min"))]
@@ -81,6 +84,14 @@ pub struct Recipe {
     /// list of tags on recipe
     #[cfg_attr(feature = "tui", cookbook(skip))]
     pub tags: Vec<Tag>,
+    /// photos of the finished recipe
+    #[cfg_attr(feature = "tui", cookbook(skip))]
+    pub photos: Vec<Photo>,
+    /// if the recipe file lists ingredients and equipment at the recipe level and refers to them
+    /// by id in steps, rather than listing them in full in each step. Recipes are saved the same
+    /// way they were loaded
+    #[cfg_attr(feature = "tui", cookbook(skip))]
+    pub uses_references: bool,
     //TODO: versions
     /// if the recipe has unsaved changes or not
     //TODO: figure out a save system
@@ -88,21 +99,140 @@ pub struct Recipe {
     pub saved: bool,
 }
 
+/// `TimelineEntry` is when a step starts and ends, relative to the start of the recipe
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimelineEntry {
+    /// index of the step in [`Recipe::steps`]
+    pub step: usize,
+    /// when the step starts
+    pub start: Time,
+    /// when the step ends
+    pub end: Time,
+}
+
+/// `IngredientListOptions` controls how [`Recipe::ingredient_list`] combines ingredients
+#[derive(Default, Debug, Clone)]
+pub struct IngredientListOptions<'a> {
+    /// catalog used to convert between masses, volumes and counts of the same ingredient
+    pub catalog: Option<&'a IngredientCatalog>,
+    /// alternatives to use instead of the original ingredient, keyed by the `id` of the original
+    /// ingredient, with the `id` of the chosen alternative as the value
+    pub substitutions: HashMap<Uuid, Uuid>,
+    /// leave optional ingredients out of the list, like when making a shopping list
+    pub exclude_optional: bool,
+}
+
+/// `Migration` is what [`Recipe::migrate_recipe_files`] changed
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    /// number of recipe files rewritten
+    pub recipe_files: usize,
+    /// number of pieces of owned equipment added to the equipment inventory
+    pub owned_equipment: usize,
+}
+
+/// `RecipeNutrition` is the nutrition of a recipe, calculated by [`Recipe::nutrition`]
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct RecipeNutrition {
+    /// nutrients in the whole recipe
+    pub total: Nutrition,
+    /// nutrients in one of the amount the recipe makes, like one serving or one cookie. `None`
+    /// if the recipe doesn't say how much it makes, or makes a mass or volume rather than a
+    /// number of servings or items
+    pub per_serving: Option<Nutrition>,
+    /// names of ingredients that aren't included in the totals, because they have no nutrition
+    /// facts in the catalog
+    pub missing: Vec<String>,
+    /// names of ingredients that aren't included in the totals, because their amount couldn't be
+    /// converted to the amount their nutrition facts are given for
+    pub unconverted: Vec<String>,
+}
+
 /// [`AmountMade`] represents the total finished quantity that the recipe makes, like 24 cookies,
-/// 24 servings, 6 portions, etc.
+/// 24 servings, 1 1/2 loaves, 600 g of jam etc.
 #[derive(Default, Debug, Clone, PartialEq, Serialize)]
 pub struct AmountMade {
-    /// amount made
-    pub quantity: u64,
-    /// units for amount made.
+    /// amount made, in `unit` if it is specified
+    pub quantity: Rational64,
+    /// what `quantity` is measured in. Amounts made without a unit can only be compared to
+    /// amounts with the same `units` label.
+    pub unit: Option<YieldUnit>,
+    /// label for the amount made, like "cookies" or "jars of jam".
     ///
-    /// Thse are not type checked at all and are treated as a base quantity internally.
-    /// This is just a representation of the units to display.
-    /// There may be a future addition that automatically calculates calories, or serving
-    /// sizes based on calories.
+    /// This is just a representation of the units to display, and is not type checked.
     pub units: String,
 }
 
+/// [`YieldUnit`] is the unit the amount made by a recipe is measured in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub enum YieldUnit {
+    /// a number of items, like cookies or loaves
+    Count,
+    /// a number of servings
+    Servings,
+    /// a mass. Unit specified with abbreviation
+    Mass(String),
+    /// a volume. Unit specified with abbreviation
+    Volume(String),
+}
+
+impl AmountMade {
+    /// `base_quantity` returns the kind of quantity and the amount made in the base unit of that
+    /// kind, so amounts made in different units of the same kind can be compared
+    fn base_quantity(&self) -> Result<(&'static str, Rational64), UnitParseError> {
+        Ok(match &self.unit {
+            None => ("untyped", self.quantity),
+            Some(YieldUnit::Count) => ("count", self.quantity),
+            Some(YieldUnit::Servings) => ("servings", self.quantity),
+            Some(YieldUnit::Mass(unit)) => ("mass", unit_helper::mass_unit_input_parser(self.quantity, unit)?.value),
+            Some(YieldUnit::Volume(unit)) => ("volume", unit_helper::volume_unit_input_parser(self.quantity, unit)?.value),
+        })
+    }
+
+    /// `checked_ratio` returns how many times `other` fits into this amount made, like 600 g
+    /// being 3/2 of 400 g, or 3 loaves being 3/2 of 2 loaves.
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - either amount has a mass or volume unit that is not supported
+    /// - the two amounts are of different kinds, like a mass and a number of servings
+    /// - neither amount has a typed unit, and their `units` labels differ
+    /// - `other` is zero
+    /// - the division overflows
+    pub fn checked_ratio(&self, other: &Self) -> anyhow::Result<Rational64> {
+        let (kind, value) = self.base_quantity()?;
+        let (other_kind, other_value) = other.base_quantity()?;
+        if kind != other_kind || (kind == "untyped" && self.units.trim() != other.units.trim()) {
+            anyhow::bail!("Can not compare an amount of {} to an amount of {}", self.units, other.units);
+        }
+        if other_value.is_zero() {
+            anyhow::bail!("Can not compare an amount to zero {}", other.units);
+        }
+        value.checked_div(&other_value).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Comparing {} {} to {} {} overflowed",
+                self.quantity,
+                self.units,
+                other.quantity,
+                other.units
+            )
+        })
+    }
+
+    /// `readable` returns the amount made formatted for display, with fractions written in
+    /// `style`. Ex: `Makes: ½ loaf`
+    #[must_use]
+    pub fn readable(&self, style: FractionStyle) -> String {
+        format!(
+            "Makes: {} {}",
+            unit_helper::approximate_fraction(self.quantity, style),
+            self.units
+        )
+    }
+}
+
 impl fmt::Display for AmountMade {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Makes: {} {}", self.quantity, self.units)
@@ -123,82 +253,393 @@ impl Recipe {
             amount_made: AmountMade::default(),
             steps: Vec::new(),
             tags: Vec::new(),
+            photos: Vec::new(),
+            uses_references: false,
             //TODO: versions
             saved: false,
         }
     }
 
-    /// `step_time_totals` provides the time required for each type of step as a `HashMap`
+    /// `step_time_totals` provides the time required for each type of step as a `HashMap`,
+    /// including the steps of any sub-recipes. Steps that take a range of time add to both ends
+    /// of the range, so the totals are ranges as well
     #[must_use]
-    pub fn step_time_totals(&self) -> HashMap<StepType, Option<Time>> {
-        let mut out_map: HashMap<StepType, Option<Time>> = HashMap::new();
+    pub fn step_time_totals(&self) -> HashMap<StepType, Option<TimeRange>> {
+        let mut out_map: HashMap<StepType, Option<TimeRange>> = HashMap::new();
         for step in &self.steps {
-            out_map
-                .entry(step.step_type)
-                .and_modify(|e: &mut Option<Time>| {
-                    add(e, step.time_needed);
-                })
-                .or_insert(step.time_needed);
+            let total = out_map.entry(step.step_type).or_default();
+            *total = add(*total, step.time_needed);
+            if let Some(sub_recipe) = step.sub_recipe.as_ref().and_then(|s| s.recipe.as_ref()) {
+                for (step_type, time) in sub_recipe.step_time_totals() {
+                    let total = out_map.entry(step_type).or_default();
+                    *total = add(*total, time);
+                }
+            }
         }
         out_map
     }
-    /// `total_time` returns the total time required for a recipe
+    /// `total_time` returns the total time required for a recipe, including the time required
+    /// for any sub-recipes. This is the total effort, see [`Recipe::elapsed_time`] for how long
+    /// the recipe takes from start to finish
     #[must_use]
-    pub fn total_time(&self) -> Time {
-        let mut time: Time = Time::default();
+    #[expect(clippy::arithmetic_side_effects)] //TODO: change this to saturating
+    pub fn total_time(&self) -> TimeRange {
+        let mut time = TimeRange::default();
         for step in &self.steps {
-            time += step.time_needed.unwrap_or(Time::default());
+            time += step.time_needed.unwrap_or_default();
+            if let Some(sub_recipe) = step.sub_recipe.as_ref().and_then(|s| s.recipe.as_ref()) {
+                time += sub_recipe.total_time();
+            }
         }
         time
     }
-    /// `ingredient_list` returns the total amount of ingredients required to make the recipe
+    /// `timeline` returns when each step starts and ends relative to the start of the recipe,
+    /// ordered by start time.
+    ///
+    /// Steps start once all the steps they depend on are finished. Steps without dependencies
+    /// start after the previous step, or at the same time as it if they are marked as parallel.
+    /// Steps without a time needed take no time, and the time of any sub-recipe is included in
+    /// the step that uses it. Dependencies on steps that are not earlier in the recipe are
+    /// ignored. Steps that take a range of time use the shortest time.
+    #[must_use]
+    pub fn timeline(&self) -> Vec<TimelineEntry> {
+        self.timeline_by(|time| time.min)
+    }
+
+    /// `timeline_by` returns the timeline of the recipe, using `duration` to pick how long each
+    /// step takes from its time range. See [`Recipe::timeline`]
+    #[expect(clippy::arithmetic_side_effects)] //TODO: change this to saturating
+    fn timeline_by(&self, duration: fn(&TimeRange) -> Time) -> Vec<TimelineEntry> {
+        let mut entries: Vec<TimelineEntry> = Vec::with_capacity(self.steps.len());
+        // dependencies of each step, as indexes into steps
+        let mut dependencies: Vec<Vec<usize>> = Vec::with_capacity(self.steps.len());
+        for (i, step) in self.steps.iter().enumerate() {
+            let step_dependencies: Vec<usize> = if !step.depends_on.is_empty() {
+                step.depends_on
+                    .iter()
+                    .filter_map(|id| self.steps[..i].iter().position(|s| s.id == Some(*id)))
+                    .collect()
+            } else if step.parallel {
+                dependencies.last().cloned().unwrap_or_default()
+            } else {
+                i.checked_sub(1).map(|previous| vec![previous]).unwrap_or_default()
+            };
+            let mut start = Time::default();
+            for dependency in &step_dependencies {
+                if entries[*dependency].end > start {
+                    start = entries[*dependency].end;
+                }
+            }
+            let mut step_duration = step.time_needed.as_ref().map(duration).unwrap_or_default();
+            if let Some(sub_recipe) = step.sub_recipe.as_ref().and_then(|s| s.recipe.as_ref()) {
+                step_duration += duration(&sub_recipe.elapsed_time());
+            }
+            entries.push(TimelineEntry {
+                step: i,
+                start,
+                end: start + step_duration,
+            });
+            dependencies.push(step_dependencies);
+        }
+        entries.sort_by(|a, b| {
+            a.start
+                .partial_cmp(&b.start)
+                .unwrap_or(Ordering::Equal)
+                .then(a.step.cmp(&b.step))
+        });
+        entries
+    }
+
+    /// `elapsed_time` returns the time it takes to make the recipe from start to finish, taking
+    /// into account steps that can be done at the same time. See [`Recipe::timeline`].
+    ///
+    /// This is a range from when every step takes its shortest time, to when every step takes its
+    /// longest time.
+    ///
+    /// This is different from [`Recipe::total_time`], which is the sum of the time of all steps.
+    #[must_use]
+    pub fn elapsed_time(&self) -> TimeRange {
+        let end = |timeline: Vec<TimelineEntry>| {
+            let mut elapsed = Time::default();
+            for entry in timeline {
+                if entry.end > elapsed {
+                    elapsed = entry.end;
+                }
+            }
+            elapsed
+        };
+        TimeRange {
+            min: end(self.timeline_by(|time| time.min)),
+            max: end(self.timeline_by(|time| time.max)),
+            approximate: self.total_time().approximate,
+        }
+    }
+
+    /// `ingredient_list` returns the total amount of ingredients required to make the recipe.
+    ///
+    /// Ingredients with the same `id` are combined into a single entry, normalized to the unit
+    /// the ingredient was first listed in. If a catalog is provided in `options`, masses, volumes
+    /// and counts of the same ingredient are converted using it so they can be combined. Amounts
+    /// of an ingredient that still can't be combined, like a mass and a volume of an ingredient
+    /// not in the catalog, or uses of an ingredient with different alternatives, are kept as
+    /// separate entries. Entries are in the order the ingredients first appear in the recipe.
+    ///
+    /// Ingredients listed in `options.substitutions` are replaced with the chosen alternative
+    /// before being combined. Optional ingredients are kept separate from required uses of the
+    /// same ingredient, and are left out entirely if `options.exclude_optional` is set.
+    ///
+    /// Uses of an ingredient that only differ in how it is prepared, like diced and sliced onion,
+    /// are combined, and the preparation and divided marker are left out of the totals.
+    ///
+    /// Ingredients of sub-recipes are included, scaled to the quantity of the sub-recipe used.
     #[must_use]
-    pub fn ingredient_list(&self) -> HashSet<Ingredient> {
-        let mut out: HashSet<Ingredient> = HashSet::new();
+    pub fn ingredient_list(&self, options: &IngredientListOptions) -> Vec<Ingredient> {
+        let mut all_ingredients: Vec<Ingredient> = Vec::new();
         for step in &self.steps {
-            for ingredient in &step.ingredients {
-                if out.contains(ingredient) {
-                    let mut new_ingredient = out.get(ingredient).unwrap().clone();
-                    new_ingredient.unit_quantity += ingredient.unit_quantity.clone();
-                    out.remove(ingredient);
-                    out.insert(new_ingredient);
-                } else {
-                    //TODO: figure out if ingredients should be tracked using RC or not
-                    out.insert(ingredient.clone());
+            all_ingredients.extend(step.ingredients.iter().cloned());
+            if let Some(sub_recipe) = step.sub_recipe.as_ref().and_then(|s| s.recipe.as_ref()) {
+                all_ingredients.extend(sub_recipe.ingredient_list(options));
+            }
+        }
+        let mut out: Vec<Ingredient> = Vec::new();
+        for ingredient in all_ingredients.iter().filter(|i| !(options.exclude_optional && i.optional)) {
+            let mut ingredient = options
+                .substitutions
+                .get(&ingredient.id)
+                .and_then(|alternative_id| ingredient.substitute(*alternative_id))
+                .unwrap_or_else(|| resolve_alternatives(ingredient));
+            // preparation only applies to the individual step, the total is of the whole ingredient
+            ingredient.preparation = None;
+            ingredient.divided = false;
+            // find the first entry for this ingredient that this amount can be added to
+            let combined = out.iter_mut().filter(|i| i.id == ingredient.id).any(|existing| {
+                match combine_ingredients(existing, &ingredient, options.catalog) {
+                    Some(sum) => {
+                        *existing = sum;
+                        true
+                    }
+                    None => false,
                 }
+            });
+            if !combined {
+                //TODO: figure out if ingredients should be tracked using RC or not
+                out.push(ingredient);
             }
         }
         out
     }
+
+    /// `nutrition` adds up the nutrients of all ingredients in the recipe, using the nutrition
+    /// facts in `catalog`, and divides them by the amount the recipe makes, if that is a number of
+    /// servings or items. Dividing by a mass or volume made, like 600 g of jam, would give the
+    /// nutrients per gram rather than per serving, so isn't done.
+    ///
+    /// Optional ingredients are left out. Ingredients without nutrition facts are listed in
+    /// [`RecipeNutrition::missing`], and ingredients whose amount couldn't be converted to the
+    /// amount their nutrition facts are for are listed in [`RecipeNutrition::unconverted`], rather
+    /// than causing an error, so the totals are a lower bound when either list isn't empty.
+    ///
+    /// # Errors
+    ///
+    /// Will error if adding up or dividing the nutrients overflows
+    pub fn nutrition(&self, catalog: &IngredientCatalog) -> anyhow::Result<RecipeNutrition> {
+        let options = IngredientListOptions {
+            catalog: Some(catalog),
+            exclude_optional: true,
+            ..Default::default()
+        };
+        let mut nutrition = RecipeNutrition::default();
+        for ingredient in self.ingredient_list(&options) {
+            let left_out = match catalog.nutrition(&ingredient.id, &ingredient.unit_quantity) {
+                Ok(nutrients) => {
+                    nutrition.total = nutrition
+                        .total
+                        .checked_add(&nutrients)
+                        .ok_or_else(|| anyhow::anyhow!("Adding up nutrition of recipe {} overflowed", self.name))?;
+                    continue;
+                }
+                Err(NutritionError::Missing) => &mut nutrition.missing,
+                Err(_) => &mut nutrition.unconverted,
+            };
+            if !left_out.contains(&ingredient.name) {
+                left_out.push(ingredient.name);
+            }
+        }
+        let made_in_servings = matches!(self.amount_made.unit, None | Some(YieldUnit::Count | YieldUnit::Servings));
+        if made_in_servings && !self.amount_made.quantity.is_zero() {
+            nutrition.per_serving = Some(
+                nutrition
+                    .total
+                    .checked_scale(self.amount_made.quantity.recip())
+                    .ok_or_else(|| anyhow::anyhow!("Dividing nutrition of recipe {} overflowed", self.name))?,
+            );
+        }
+        Ok(nutrition)
+    }
+
+    /// `dietary` works out the allergens the recipe contains and the diets it is suitable for,
+    /// from the dietary information of its ingredients in `catalog`.
+    ///
+    /// Optional ingredients are included, as the recipe is checked as written. Alternatives are
+    /// not. Ingredients without dietary information are listed in [`RecipeDietary::unknown`].
+    #[must_use]
+    pub fn dietary(&self, catalog: &IngredientCatalog) -> RecipeDietary {
+        let mut dietary = RecipeDietary {
+            diets: Diet::ALL.into_iter().collect(),
+            ..Default::default()
+        };
+        for ingredient in self.ingredient_list(&IngredientListOptions::default()) {
+            match catalog.get(&ingredient).and_then(|e| e.dietary.as_ref()) {
+                Some(info) => {
+                    dietary.allergens.extend(info.allergens.iter().copied());
+                    dietary.diets.retain(|d| info.diets.contains(d));
+                }
+                None => {
+                    if !dietary.unknown.contains(&ingredient.name) {
+                        dietary.unknown.push(ingredient.name);
+                    }
+                }
+            }
+        }
+        dietary
+    }
+
     /// `equipment_list` returns the overall list of equipment needed to make the recipe
     #[must_use]
     pub fn equipment_list(&self) -> Vec<Equipment> {
-        let mut out = Vec::new();
+        let mut out: Vec<Equipment> = Vec::new();
         for step in &self.steps {
-            for equipment in &step.equipment {
-                // all short circuits if the closure returns false, and then returns false. We
-                // invert that false to true to see if a value is not contained in the vector
-                if !out.iter().all(|e| e == equipment) {
+            let sub_recipe_equipment = step
+                .sub_recipe
+                .as_ref()
+                .and_then(|s| s.recipe.as_ref())
+                .map(|r| r.equipment_list())
+                .unwrap_or_default();
+            for equipment in step.equipment.iter().chain(sub_recipe_equipment.iter()) {
+                if !out.contains(equipment) {
                     out.push(equipment.clone());
                 }
             }
         }
         out
     }
-    /// `all_equipment_owned` returns the overall list of equipment needed to make the recipe
+    /// `all_equipment_owned` returns true if all of the equipment needed to make the recipe is
+    /// owned, according to `inventory`
     #[must_use]
-    pub fn all_equipment_owned(&self) -> bool {
-        // iterate through all equipment in all steps, short circuiting if e.is_owned is false
-        self.steps.iter().all(|s| s.equipment.iter().all(|e| e.is_owned))
+    pub fn all_equipment_owned(&self, inventory: &EquipmentInventory) -> bool {
+        // iterate through all equipment in all steps, short circuiting if any is not owned
+        self.steps.iter().all(|s| {
+            s.equipment.iter().all(|e| inventory.is_owned(e))
+                && s.sub_recipe
+                    .as_ref()
+                    .and_then(|sr| sr.recipe.as_ref())
+                    .is_none_or(|r| r.all_equipment_owned(inventory))
+        })
+    }
+
+    /// `scale` returns a copy of the recipe with every ingredient quantity and the amount made
+    /// multiplied by `factor`.
+    ///
+    /// All arithmetic is done with exact rational numbers. Step times and temperatures are left
+    /// as is, since they do not scale linearly with the size of a batch. The returned copy keeps
+    /// the same `id`, so callers need to assign a new one if it is to be saved as a new recipe.
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - `factor` is not greater than zero
+    /// - any of the scaled quantities overflow
+    pub fn scale(&self, factor: Rational64) -> anyhow::Result<Self> {
+        if factor <= Rational64::zero() {
+            anyhow::bail!("Scale factor must be greater than zero, got {factor}");
+        }
+        let amount_made = self
+            .amount_made
+            .quantity
+            .checked_mul(&factor)
+            .ok_or_else(|| anyhow::anyhow!("Scaling amount made of recipe {} by {factor} overflowed", self.name))?;
+        let mut scaled = self.clone();
+        scaled.amount_made.quantity = amount_made;
+        scaled.scale_ingredients(factor)?;
+        scaled.saved = false;
+        Ok(scaled)
+    }
+
+    /// `scale_ingredients` multiplies every ingredient quantity and sub-recipe quantity by
+    /// `factor`, leaving the amount made as is.
+    fn scale_ingredients(&mut self, factor: Rational64) -> anyhow::Result<()> {
+        for step in &mut self.steps {
+            for ingredient in &mut step.ingredients {
+                *ingredient = ingredient.checked_scale(factor).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Scaling ingredient {} of recipe {} by {factor} overflowed",
+                        ingredient.name,
+                        self.name
+                    )
+                })?;
+            }
+            if let Some(sub_recipe) = &mut step.sub_recipe {
+                let overflow = || anyhow::anyhow!("Scaling sub-recipe of recipe {} by {factor} overflowed", self.name);
+                sub_recipe.quantity = match sub_recipe.quantity {
+                    SubRecipeQuantity::Batches(b) => SubRecipeQuantity::Batches(b.checked_mul(&factor).ok_or_else(overflow)?),
+                    SubRecipeQuantity::Amount(a) => SubRecipeQuantity::Amount(a.checked_mul(&factor).ok_or_else(overflow)?),
+                };
+                if let Some(recipe) = &mut sub_recipe.recipe {
+                    recipe.scale_ingredients(factor)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `scale_to` returns a copy of the recipe scaled so that it makes `target`.
+    ///
+    /// Amounts made with a typed unit are converted, so a recipe that makes 1 kg of jam can be
+    /// scaled to make 600 g. The scaled recipe keeps the unit and label of the original recipe.
+    ///
+    /// See [`Recipe::scale`] for details on what is scaled.
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - `target` can not be compared to the recipe amount made. See [`AmountMade::checked_ratio`]
+    /// - the recipe does not specify an amount made
+    /// - scaling the recipe fails
+    pub fn scale_to(&self, target: &AmountMade) -> anyhow::Result<Self> {
+        if self.amount_made.quantity.is_zero() {
+            anyhow::bail!("Can not scale recipe {} as it does not specify an amount made", self.name);
+        }
+        let factor = target
+            .checked_ratio(&self.amount_made)
+            .with_context(|| format!("Can not scale recipe {} to {} {}", self.name, target.quantity, target.units))?;
+        self.scale(factor)
+    }
+
+    /// `new_scaled_copy` returns a copy of the recipe scaled by `factor` with a new `id`, and the
+    /// scale factor noted in the name, so it can be saved alongside the original recipe.
+    ///
+    /// # Errors
+    ///
+    /// Will error if scaling the recipe fails. See [`Recipe::scale`].
+    pub fn new_scaled_copy(&self, factor: Rational64) -> anyhow::Result<Self> {
+        let mut scaled = self.scale(factor)?;
+        scaled.id = Uuid::new_v4();
+        scaled.name = format!("{} (\u{d7}{factor})", self.name);
+        Ok(scaled)
     }
 
     /// `load_recipes_from_directory` recursively parses the provided directory path to parse all
     /// `*.toml` files found and return a `HashMap<Uuid, Recipe>` with the parsed `Recipe`s.
     ///
+    /// The ingredient catalog file is skipped, see
+    /// [`IngredientCatalog::load_from_directory`]. Sub-recipes are resolved after all recipes are
+    /// loaded, see [`Recipe::resolve_sub_recipes`].
+    ///
     /// # Errors
     ///
     /// Will error if:
     /// - reading any of the individual recipes fails
+    /// - resolving sub-recipes fails
     /// - the specified path is not a directory
     /// - [`OsStr`](std::ffi::OsStr) failed to parse to UTF-8
     pub fn load_recipes_from_directory<T>(dir: T) -> anyhow::Result<HashMap<Uuid, Self>>
@@ -207,7 +648,15 @@ impl Recipe {
     {
         if dir.as_ref().is_dir() {
             let mut recipes: HashMap<Uuid, Self> = HashMap::new();
-            Self::load_recipes_from_directory_inner(dir, &mut recipes)?;
+            Self::load_recipes_from_directory_inner(&dir, &mut recipes)?;
+            Self::resolve_sub_recipes(&mut recipes)?;
+            // a missing or mistyped photo only means the photo isn't shown, so is logged rather than
+            // stopping the cookbook from loading. See Recipe::photo_problems
+            for recipe in recipes.values() {
+                for problem in recipe.photo_problems(&dir) {
+                    warn!("{problem:#}");
+                }
+            }
             //recipes.sort_unstable_by_key(|r| r.id);
             Ok(recipes)
         } else {
@@ -234,13 +683,13 @@ impl Recipe {
             },
             None => "",
         };
-        if inner_dir.as_ref().is_file() && ext == "toml" {
+        if inner_dir.as_ref().is_file() && ext == "toml" && !Self::is_sidecar_file(inner_dir.as_ref()) {
             let recipe = match Self::parse_recipe(inner_dir.as_ref()) {
                 Ok(r) => r,
                 Err(error) => {
                     return Err(anyhow::Error::new(io::Error::new(
                         io::ErrorKind::InvalidData,
-                        format! {"Parsing TOML file {} failed: {}", &inner_dir.as_ref().display(), error},
+                        format! {"Parsing TOML file {} failed: {:#}", &inner_dir.as_ref().display(), error},
                     )));
                 }
             };
@@ -259,58 +708,318 @@ impl Recipe {
         }
     }
 
+    /// `resolve_sub_recipes` fills in the copy of the referenced recipe for every sub-recipe in
+    /// `recipes`, scaled to the quantity used. Sub-recipes of sub-recipes are resolved as well.
+    ///
+    /// This needs to be called again after any recipe is changed, so the copies stay up to date.
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - a recipe references a recipe that is not in `recipes`
+    /// - recipes reference each other in a cycle, like A uses B which uses A
+    /// - scaling a referenced recipe fails
+    pub fn resolve_sub_recipes(recipes: &mut HashMap<Uuid, Self>) -> anyhow::Result<()> {
+        let mut resolved: HashMap<Uuid, Self> = HashMap::with_capacity(recipes.len());
+        for id in recipes.keys() {
+            Self::resolve_sub_recipes_inner(*id, recipes, &mut resolved, &mut Vec::new())?;
+        }
+        *recipes = resolved;
+        Ok(())
+    }
+
+    /// `resolve_sub_recipes_inner` resolves the recipe with id `id` into `resolved`, after
+    /// resolving any recipes it references. `path` is the chain of recipes currently being
+    /// resolved, used to detect cycles.
+    fn resolve_sub_recipes_inner(
+        id: Uuid,
+        recipes: &HashMap<Uuid, Self>,
+        resolved: &mut HashMap<Uuid, Self>,
+        path: &mut Vec<Uuid>,
+    ) -> anyhow::Result<()> {
+        if resolved.contains_key(&id) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|p| *p == id) {
+            let cycle: Vec<&str> = path[start..].iter().chain([&id]).map(|p| recipes[p].name.as_str()).collect();
+            anyhow::bail!("Recipes reference each other in a cycle: {}", cycle.join(" -> "));
+        }
+        path.push(id);
+        let mut recipe = recipes[&id].clone();
+        for step in &mut recipe.steps {
+            if let Some(sub_recipe) = &mut step.sub_recipe {
+                if !recipes.contains_key(&sub_recipe.id) {
+                    anyhow::bail!(
+                        "Recipe {} uses recipe with id {} which was not found",
+                        recipe.name,
+                        sub_recipe.id
+                    );
+                }
+                Self::resolve_sub_recipes_inner(sub_recipe.id, recipes, resolved, path)?;
+                let mut sub = resolved[&sub_recipe.id].clone();
+                let factor = sub_recipe.scale_factor(&sub)?;
+                sub.scale_ingredients(factor)?;
+                sub_recipe.recipe = Some(Box::new(sub));
+            }
+        }
+        path.pop();
+        resolved.insert(id, recipe);
+        Ok(())
+    }
+
     fn parse_recipe<T>(recipe_file: T) -> anyhow::Result<Self>
     where
         T: AsRef<Path>,
     {
         let contents = fs::read_to_string(recipe_file)?;
-        let output: filetypes::Recipe = toml::from_str(contents.as_str())?;
-        let mut output: Self = output.into();
+        let mut output: filetypes::Recipe = toml::from_str(contents.as_str())?;
+        let uses_references = output.ingredients.is_some() || output.equipment.is_some();
+        output.expand_references()?;
+        let mut output = Self::try_from(output)?;
+        output.uses_references = uses_references;
         if output.id.is_nil() {
             output.id = Uuid::new_v4();
         }
+        output.check_step_dependencies()?;
+        output.check_time_ranges()?;
         Ok(output)
     }
+
+    /// `photo_problems` checks that every photo on the recipe and its steps is a supported image
+    /// type and that its image file exists in the cookbook directory `dir`, and returns an error
+    /// for each photo that isn't
+    #[must_use]
+    pub fn photo_problems<T>(&self, dir: T) -> Vec<anyhow::Error>
+    where
+        T: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        let recipe_photos = self
+            .photos
+            .iter()
+            .map(|photo| photo.check(dir).with_context(|| format!("Recipe {}", self.name)));
+        let step_photos = self.steps.iter().enumerate().flat_map(|(i, step)| {
+            step.photos.iter().map(move |photo| {
+                photo
+                    .check(dir)
+                    .with_context(|| format!("Step {} of recipe {}", i.saturating_add(1), self.name))
+            })
+        });
+        recipe_photos.chain(step_photos).filter_map(Result::err).collect()
+    }
+
+    /// `check_time_ranges` checks that no step takes longer at the short end of its time range
+    /// than at the long end
+    fn check_time_ranges(&self) -> anyhow::Result<()> {
+        for (i, step) in self.steps.iter().enumerate() {
+            if let Some(time) = step.time_needed
+                && time.max < time.min
+            {
+                anyhow::bail!(
+                    "Step {} of recipe {} has a time_needed_max shorter than its time_needed",
+                    i.saturating_add(1),
+                    self.name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// `check_step_dependencies` checks that every step only depends on earlier steps
+    fn check_step_dependencies(&self) -> anyhow::Result<()> {
+        for (i, step) in self.steps.iter().enumerate() {
+            for id in &step.depends_on {
+                if !self.steps[..i].iter().any(|s| s.id == Some(*id)) {
+                    anyhow::bail!(
+                        "Step {} of recipe {} depends on step {id} which is not an earlier step in the recipe",
+                        i.saturating_add(1),
+                        self.name
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+    /// `migrate_recipe_files` parses every recipe file in the directory and writes it back in place
+    /// using the current file format.
+    ///
+    /// Step temperatures are read as absolute cook temperatures in their `temperature_unit`, so
+    /// oven settings like 350 °F or 180 °C keep the same value and unit when rewritten.
+    ///
+    /// Recipes keep listing ingredients and equipment the way they already did, unless
+    /// `use_references` is true, in which case they are all moved to the recipe level lists. See
+    /// [`Recipe::uses_references`].
+    ///
+    /// Equipment marked as owned in the recipe files is added to the equipment inventory in the
+    /// directory first, since the rewritten files no longer say whether it is owned.
+    pub fn migrate_recipe_files<T>(dir: T, use_references: bool) -> anyhow::Result<Migration>
+    where
+        T: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        let recipes = Self::load_recipes_from_directory(dir)?;
+        let mut inventory = EquipmentInventory::load_from_directory(dir)?;
+        let owned_equipment = inventory.add_owned(recipes.values().flat_map(|r| r.steps.iter()).flat_map(|s| s.equipment.iter()));
+        if owned_equipment > 0 {
+            inventory.write_to_directory(dir)?;
+        }
+        let mut recipe_files = 0;
+        Self::migrate_recipe_files_inner(dir, use_references, &mut recipe_files)?;
+        Ok(Migration {
+            recipe_files,
+            owned_equipment,
+        })
+    }
+
+    fn migrate_recipe_files_inner<T>(inner_dir: T, use_references: bool, count: &mut usize) -> anyhow::Result<()>
+    where
+        T: AsRef<Path>,
+    {
+        let path = inner_dir.as_ref();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "toml") && !Self::is_sidecar_file(path) {
+            let mut recipe = Self::parse_recipe(path).with_context(|| format!("Parsing TOML file {} failed", path.display()))?;
+            recipe.uses_references |= use_references;
+            Self::write_recipe(recipe, path).with_context(|| format!("Writing TOML file {} failed", path.display()))?;
+            *count += 1;
+        } else if path.is_dir() {
+            for entry in fs::read_dir(path)? {
+                Self::migrate_recipe_files_inner(entry?.path(), use_references, count)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `is_sidecar_file` returns true for the files stored alongside recipes in the recipe
+    /// directory that aren't recipes, like the ingredient catalog and cook log
+    fn is_sidecar_file(path: &Path) -> bool {
+        path.file_name().is_some_and(|name| {
+            name == ingredient_catalog::CATALOG_FILE_NAME
+                || name == cook_log::COOK_LOG_FILE_NAME
+                || name == equipment_inventory::INVENTORY_FILE_NAME
+        })
+    }
+
     /// `write_recipe` writes an individual recipe to a toml file
     pub fn write_recipe<T>(recipe: Recipe, out_path: T) -> anyhow::Result<()>
     where
         T: AsRef<Path>,
     {
-        let output = toml::to_string_pretty(&filetypes::Recipe::from(recipe))?;
+        let output = recipe.to_toml_string()?;
         fs::write(out_path, output)?;
         Ok(())
     }
 
-    /// `compile_tag_list` scans through all tags on a `Vec<cookbook_core::datatypes::recipe::Recipe>`,
-    /// and returns a `Vec<cookbook_core::datatypes::tag::Tag>` with all tags found.
-    /// The resulting `Vec<cookbook_core::datatypes::tag::Tag>` is sorted and deduplicated before
-    /// being returned
-    pub fn compile_tag_list(recipes: HashMap<Uuid, Self>) -> Vec<Tag> {
-        let mut tags: Vec<Tag> = Vec::new();
-        for recipe in recipes.values() {
-            //TODO: maybe switch to using try_reserve instead
-            tags.reserve(recipe.tags.len());
-            tags.extend(recipe.tags.clone());
+    /// `write_new_recipe` writes a recipe that isn't in the cookbook yet, like a scaled copy, to a
+    /// new file named by [`Recipe::file_name`] in `dir`, and returns the path of the file.
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - a file with the same name already exists in `dir`
+    /// - writing the file fails
+    pub fn write_new_recipe<T>(recipe: Recipe, dir: T) -> anyhow::Result<PathBuf>
+    where
+        T: AsRef<Path>,
+    {
+        let path = dir.as_ref().join(recipe.file_name());
+        let output = recipe.to_toml_string()?;
+        // create_new refuses to replace a recipe that is already saved with this name
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .with_context(|| format!("Creating recipe file {} failed", path.display()))?;
+        file.write_all(output.as_bytes())?;
+        Ok(path)
+    }
+
+    /// `file_name` returns the name of the file the recipe is saved to, made from the recipe name
+    /// with spaces and anything that can't be part of a file name, like the `/` in a scale factor
+    /// of `3/2`, replaced by `_`. The `id` is used for recipes whose name has nothing usable in it.
+    #[must_use]
+    pub fn file_name(&self) -> String {
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_whitespace() || c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+                    '_'
+                } else {
+                    c
+                }
+            })
+            .collect();
+        // a leading period would hide the file, or refer to a directory like `..`
+        let name = name.trim_start_matches('.');
+        if name.is_empty() {
+            format!("{}.toml", self.id)
+        } else {
+            format!("{name}.toml")
+        }
+    }
+
+    /// `to_toml_string` returns the recipe formatted the same way as a recipe file
+    pub fn to_toml_string(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string_pretty(&filetypes::Recipe::try_from(self.clone())?)?)
+    }
+
+    /// `compile_tag_list` scans through all tags on a `HashMap` of recipes, and returns all tags
+    /// found grouped by namespace. Each group is sorted and deduplicated before being returned
+    #[must_use]
+    pub fn compile_tag_list(recipes: HashMap<Uuid, Self>) -> TagGroups {
+        Tag::group(recipes.values().flat_map(|recipe| recipe.tags.iter()))
+    }
+
+    /// `apply_tag_aliases` replaces any tags on the recipes that are aliases with the tag they are an
+    /// alias of, removing any duplicates this creates
+    pub fn apply_tag_aliases(recipes: &mut HashMap<Uuid, Self>, aliases: &TagAliases) {
+        for recipe in recipes.values_mut() {
+            let mut tags: Vec<Tag> = Vec::with_capacity(recipe.tags.len());
+            for tag in recipe.tags.iter().map(|t| aliases.resolve(t)) {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            recipe.tags = tags;
         }
-        // don't care about order of duplicate elements since we are removing them
-        tags.sort_unstable();
-        tags.dedup();
-        tags.shrink_to_fit();
-        tags
     }
 
     /// `filter_recipes_by_tags` accepts a list of tags, and a HashMap of recipes and outputs a
     /// filtered HashMap of recipes, where the included recipes include at least one of the tags
     /// listed.
     pub fn filter_recipes_by_tags(recipes: HashMap<Uuid, Self>, tags: &[Tag]) -> HashMap<Uuid, Self> {
+        recipes
+            .into_iter()
+            .filter(|recipe| tags.iter().any(|tag| recipe.1.tags.contains(tag)))
+            .collect()
+    }
+
+    /// `filter_recipes_by_equipment` accepts a HashMap of recipes and outputs a filtered HashMap
+    /// of recipes, where the included recipes only use equipment that is owned according to
+    /// `inventory`.
+    pub fn filter_recipes_by_equipment(recipes: HashMap<Uuid, Self>, inventory: &EquipmentInventory) -> HashMap<Uuid, Self> {
+        recipes
+            .into_iter()
+            .filter(|recipe| recipe.1.all_equipment_owned(inventory))
+            .collect()
+    }
+
+    /// `filter_recipes_by_diet` accepts a list of dietary requirements, and a HashMap of recipes
+    /// and outputs a filtered HashMap of recipes, where the included recipes meet all of the
+    /// requirements listed. Dietary information of ingredients is looked up in `catalog`.
+    pub fn filter_recipes_by_diet(
+        recipes: HashMap<Uuid, Self>,
+        filters: &[DietaryFilter],
+        catalog: &IngredientCatalog,
+    ) -> HashMap<Uuid, Self> {
+        if filters.is_empty() {
+            return recipes;
+        }
         recipes
             .into_iter()
             .filter(|recipe| {
-                let mut tag_in_recipe = false;
-                for tag in tags {
-                    tag_in_recipe = recipe.1.tags.contains(tag);
-                }
-                tag_in_recipe
+                let dietary = recipe.1.dietary(catalog);
+                filters.iter().all(|filter| dietary.satisfies(*filter))
             })
             .collect()
     }
@@ -343,34 +1052,209 @@ impl Default for State {
     }
 }
 
+/// `resolve_alternatives` returns a copy of `ingredient` with the amounts of any alternatives
+/// specified by ratio calculated, so they can be combined with other uses of the ingredient
+fn resolve_alternatives(ingredient: &Ingredient) -> Ingredient {
+    let mut resolved = ingredient.clone();
+    for alternative in &mut resolved.alternatives {
+        // leave the ratio in place if the amount overflows, so it won't be combined
+        if let Some(amount) = ingredient.alternative_amount(alternative) {
+            alternative.unit_quantity = Some(amount);
+            alternative.ratio = None;
+        }
+    }
+    resolved
+}
+
+/// `combine_ingredients` adds the amounts of two uses of the same ingredient together, along with
+/// the amounts of their alternatives.
+///
+/// Returns `None` if the amounts can't be combined, the two uses have different alternatives, or
+/// only one of them is optional.
+fn combine_ingredients(lhs: &Ingredient, rhs: &Ingredient, catalog: Option<&IngredientCatalog>) -> Option<Ingredient> {
+    if lhs.alternatives.len() != rhs.alternatives.len() || lhs.optional != rhs.optional {
+        return None;
+    }
+    let mut out = lhs.clone();
+    out.unit_quantity = combine_amounts(&lhs.id, &lhs.unit_quantity, &rhs.unit_quantity, catalog)?;
+    for alternative in &mut out.alternatives {
+        let other: &Alternative = rhs.alternatives.iter().find(|a| a.id == alternative.id)?;
+        alternative.unit_quantity = Some(combine_amounts(
+            &alternative.id,
+            alternative.unit_quantity.as_ref()?,
+            other.unit_quantity.as_ref()?,
+            catalog,
+        )?);
+    }
+    Some(out)
+}
+
+/// `combine_amounts` adds two amounts of the ingredient with id `id` together, converting `rhs`
+/// using the catalog if they are different kinds or counts of different things.
+///
+/// Returns `None` if they can't be combined. Sums that overflow are logged, since those amounts
+/// could otherwise be combined
+fn combine_amounts(id: &Uuid, lhs: &UnitType, rhs: &UnitType, catalog: Option<&IngredientCatalog>) -> Option<UnitType> {
+    let sum = match lhs.checked_add(rhs) {
+        Err(UnitTypeError::IncompatibleKinds { .. } | UnitTypeError::IncompatibleUnits { .. }) => {
+            lhs.checked_add(&catalog?.convert(id, rhs, lhs)?)
+        }
+        sum => sum,
+    };
+    sum.inspect_err(|e| warn!("Adding {rhs} to {lhs} of ingredient {id} failed, listing them separately: {e}"))
+        .ok()
+}
+
 //https://www.reddit.com/r/learnrust/comments/1b1xwci/best_way_to_add_an_optiont_to_an_optiont/
 /// helper function for `step_time_totals` to allow adding an option and an option togther
-fn add(lhs: &mut Option<Time>, rhs: Option<Time>) -> Option<Time> {
+fn add(lhs: Option<TimeRange>, rhs: Option<TimeRange>) -> Option<TimeRange> {
     #[expect(clippy::arithmetic_side_effects)] //TODO: change this to saturating
     match (lhs, rhs) {
-        (Some(l), Some(r)) => Some(*l + r),
-        (Some(l), None) => Some(*l),
+        (Some(l), Some(r)) => Some(l + r),
+        (Some(l), None) => Some(l),
         (None, Some(r)) => Some(r),
         (None, None) => None,
     }
 }
 
-impl From<filetypes::Recipe> for Recipe {
-    fn from(input: filetypes::Recipe) -> Self {
-        Self {
+impl TryFrom<filetypes::Recipe> for Recipe {
+    type Error = anyhow::Error;
+
+    fn try_from(input: filetypes::Recipe) -> anyhow::Result<Self> {
+        Ok(Self {
             id: input.id.unwrap_or_default(),
+            amount_made: AmountMade {
+                quantity: input.amount_made.into(),
+                unit: input
+                    .amount_made_unit
+                    .map(|u| -> Result<YieldUnit, UnitParseError> {
+                        Ok(match u {
+                            filetypes::YieldUnit::Count => YieldUnit::Count,
+                            filetypes::YieldUnit::Servings => YieldUnit::Servings,
+                            // parse the unit once while loading, so bad units are caught early
+                            filetypes::YieldUnit::Mass(unit) => {
+                                YieldUnit::Mass(unit_helper::mass_unit_abbreviation(&unit)?.to_owned())
+                            }
+                            filetypes::YieldUnit::Volume(unit) => {
+                                YieldUnit::Volume(unit_helper::volume_unit_abbreviation(&unit)?.to_owned())
+                            }
+                        })
+                    })
+                    .transpose()
+                    .with_context(|| format!("Recipe {} amount_made_unit", input.name))?,
+                units: input.amount_made_units,
+            },
+            steps: input
+                .steps
+                .into_iter()
+                .enumerate()
+                .map(|(i, step)| {
+                    Step::try_from(step).with_context(|| format!("Step {} of recipe {}", i.saturating_add(1), input.name))
+                })
+                .collect::<anyhow::Result<_>>()?,
             name: input.name,
             description: input.description,
             comments: input.comments,
             source: input.source,
             author: input.author,
-            amount_made: AmountMade {
-                quantity: input.amount_made,
-                units: input.amount_made_units,
-            },
-            steps: input.steps.into_iter().map(Into::into).collect(),
-            tags: input.tags,
+            tags: input.tags.into_iter().map(Tag::from).collect(),
+            photos: input.photos.unwrap_or_default().into_iter().map(Into::into).collect(),
+            // set by Recipe::parse_recipe, as the references are expanded before this conversion
+            uses_references: false,
             saved: false,
-        }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use num_rational::Rational64;
+    use uuid::Uuid;
+
+    use super::{IngredientListOptions, Recipe};
+    use crate::datatypes::{
+        equipment::Equipment,
+        equipment_inventory::EquipmentInventory,
+        ingredient::{Ingredient, UnitType},
+        step::Step,
+        unit_helper::volume_unit_input_parser,
+    };
+
+    #[test]
+    fn ingredient_list_combines_teaspoons_and_tablespoons() {
+        let salt_id = Uuid::new_v4();
+        let salt = |unit: &str| Ingredient {
+            id: salt_id,
+            name: "Salt".to_owned(),
+            unit_quantity: UnitType::Volume {
+                value: volume_unit_input_parser(Rational64::from_integer(1), unit).expect("volume parses"),
+                unit: unit.to_owned(),
+            },
+            ..Ingredient::default()
+        };
+        let recipe = Recipe {
+            steps: vec![
+                Step {
+                    ingredients: vec![salt("tsp")],
+                    ..Step::default()
+                },
+                Step {
+                    ingredients: vec![salt("tbsp")],
+                    ..Step::default()
+                },
+            ],
+            ..Recipe::new()
+        };
+        let list = recipe.ingredient_list(&IngredientListOptions::default());
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].unit_quantity.to_string(), "4 tsp");
+    }
+
+    #[test]
+    fn migrating_moves_owned_equipment_to_the_inventory() {
+        let dir = std::env::temp_dir().join(format!("cookbook-migrate-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("temporary directory is created");
+        let toaster_id = Uuid::new_v4();
+        let recipe_path = dir.join("toast.toml");
+        fs::write(
+            &recipe_path,
+            format!(
+                r#"id = "{}"
+name = "Toast"
+source = ""
+author = ""
+amount_made = 2
+amount_made_units = "slices"
+tags = []
+
+[[steps]]
+instructions = "Toast the bread"
+step_type = "Cook"
+
+[[steps.equipment]]
+id = "{toaster_id}"
+name = "Toaster"
+is_owned = true
+"#,
+                Uuid::new_v4()
+            ),
+        )
+        .expect("recipe file is written");
+
+        let migration = Recipe::migrate_recipe_files(&dir, false).expect("recipe files migrate");
+        let rewritten = fs::read_to_string(&recipe_path).expect("recipe file is read");
+        let inventory = EquipmentInventory::load_from_directory(&dir).expect("inventory loads");
+        fs::remove_dir_all(&dir).expect("temporary directory is removed");
+
+        assert_eq!(migration.recipe_files, 1);
+        assert_eq!(migration.owned_equipment, 1);
+        assert!(!rewritten.contains("is_owned"), "{rewritten}");
+        let toaster = Equipment {
+            id: toaster_id,
+            ..Equipment::default()
+        };
+        assert!(inventory.is_owned(&toaster));
     }
 }
@@ -1,11 +1,19 @@
 use std::fmt;
+use std::ops::{Add, AddAssign};
+
+use anyhow::Context;
 
 #[cfg(feature = "tui")]
 use num_derive::{FromPrimitive, ToPrimitive};
+use num_rational::Rational64;
+use num_traits::{CheckedDiv, Zero};
 #[cfg(feature = "tui")]
 use ratatui::{style::Stylize, widgets::Widget};
 use serde::Serialize;
-use uom::si::rational64::{TemperatureInterval, Time};
+use uom::{
+    fmt::DisplayStyle,
+    si::rational64::{ThermodynamicTemperature, Time},
+};
 use uuid::Uuid;
 
 #[cfg(feature = "tui")]
@@ -14,7 +22,14 @@ use ranged_wrapping::RangedWrapping;
 #[cfg(feature = "tui")]
 use cookbook_macros::{StatefulWidgetRef, WidgetRef};
 
-use super::{equipment::Equipment, filetypes, ingredient::Ingredient, unit_helper};
+use super::{
+    equipment::Equipment,
+    filetypes,
+    ingredient::Ingredient,
+    photo::Photo,
+    recipe::Recipe,
+    unit_helper::{self, FractionStyle, TimeFormat, UnitParseError},
+};
 #[cfg(feature = "tui")]
 use crate::tui::dropdown::{Dropdown, DropdownState};
 
@@ -25,21 +40,23 @@ pub struct Step {
     /// database ID
     #[cfg_attr(feature = "tui", cookbook(skip))]
     pub id: Option<Uuid>,
-    /// time needed to perform this step in the recipe
+    /// time needed to perform this step in the recipe, either an exact time or a range like
+    /// 25–30 minutes
     /// Optional for informational steps, or steps that
     /// don't traditionally have durations associated
     #[cfg_attr(feature = "tui", cookbook(display_order = 0))]
     #[cfg_attr(feature = "tui", cookbook(constraint_type = "Length"))]
     #[cfg_attr(feature = "tui", cookbook(constraint_value = 3))]
-    pub time_needed: Option<Time>,
+    pub time_needed: Option<TimeRange>,
     /// Units for time_needed.
     #[cfg_attr(feature = "tui", cookbook(skip))]
     pub time_needed_unit: Option<String>,
-    /// cook temperature. Optional for steps that don't involve temperature or cooking
+    /// cook temperature, like an oven setting. Optional for steps that don't involve temperature
+    /// or cooking
     #[cfg_attr(feature = "tui", cookbook(display_order = 1))]
     #[cfg_attr(feature = "tui", cookbook(constraint_type = "Length"))]
     #[cfg_attr(feature = "tui", cookbook(constraint_value = 3))]
-    pub temperature: Option<TemperatureInterval>,
+    pub temperature: Option<ThermodynamicTemperature>,
     /// Units for temperature.
     #[cfg_attr(feature = "tui", cookbook(skip))]
     pub temperature_unit: Option<String>,
@@ -67,6 +84,56 @@ pub struct Step {
         cookbook(display_widget_options(StepType::Prep, StepType::Cook, StepType::Wait, StepType::Other))
     )]
     pub step_type: StepType,
+    /// another recipe that is made as part of this step, like a pie crust or stock
+    #[cfg_attr(feature = "tui", cookbook(skip))]
+    pub sub_recipe: Option<SubRecipe>,
+    /// IDs of earlier steps that need to be finished before this step can start. If empty, the
+    /// step starts after the previous step, or with it if `parallel` is set
+    #[cfg_attr(feature = "tui", cookbook(skip))]
+    pub depends_on: Vec<Uuid>,
+    /// if the step can run at the same time as the previous step, like making a sauce while
+    /// dough rises. Ignored if `depends_on` is not empty
+    #[cfg_attr(feature = "tui", cookbook(skip))]
+    pub parallel: bool,
+    /// photos of the step, for steps that are easier to show than describe
+    #[cfg_attr(feature = "tui", cookbook(skip))]
+    pub photos: Vec<Photo>,
+}
+
+/// `TimeRange` is how long something takes, either an exact time or a range like "25–30 minutes".
+/// It can also be marked as approximate, like "about 10 minutes".
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TimeRange {
+    /// shortest time it takes
+    pub min: Time,
+    /// longest time it takes. The same as `min` for exact times
+    pub max: Time,
+    /// if the time is a rough estimate, like "until golden, about 10 minutes"
+    pub approximate: bool,
+}
+
+/// `SubRecipe` is a reference from a step to another recipe that is made as part of the step
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubRecipe {
+    /// ID of the referenced recipe
+    pub id: Uuid,
+    /// how much of the referenced recipe is needed
+    pub quantity: SubRecipeQuantity,
+    /// copy of the referenced recipe, scaled to the quantity used in this step.
+    ///
+    /// Filled in by [`Recipe::resolve_sub_recipes`] when recipes are loaded. `None` until then.
+    #[serde(skip)]
+    pub recipe: Option<Box<Recipe>>,
+}
+
+/// `SubRecipeQuantity` is how much of a referenced recipe is needed
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SubRecipeQuantity {
+    /// number of batches of the referenced recipe
+    Batches(Rational64),
+    /// amount of the referenced recipe, in the units of its amount made. Ex: 2 cups of stock
+    Amount(Rational64),
 }
 
 /// `State` contains the state of the Step widget
@@ -124,24 +191,198 @@ impl fmt::Display for StepType {
     }
 }
 
-impl From<filetypes::Step> for Step {
-    fn from(input: filetypes::Step) -> Self {
+impl TimeRange {
+    /// `exact` creates a [`TimeRange`] that takes exactly `time`
+    #[must_use]
+    pub fn exact(time: Time) -> Self {
         Self {
+            min: time,
+            max: time,
+            approximate: false,
+        }
+    }
+
+    /// `is_exact` returns true if the range is a single time rather than a range of times
+    #[must_use]
+    pub fn is_exact(&self) -> bool {
+        self.min == self.max
+    }
+
+    /// `format` returns the time range formatted for display in the unit `unit_string`.
+    /// Ex: `25 min`, `25–30 min`, `about 10 min`
+    ///
+    /// # Errors
+    ///
+    /// Will error if `unit_string` is not a supported time unit
+    pub fn format(&self, unit_string: &str, style: DisplayStyle) -> Result<String, UnitParseError> {
+        let approximate = if self.approximate { "about " } else { "" };
+        Ok(if self.is_exact() {
+            format!(
+                "{approximate}{}",
+                unit_helper::time_unit_format_output(self.min, unit_string, style)?
+            )
+        } else {
+            format!(
+                "{approximate}{}\u{2013}{}",
+                unit_helper::time_unit_raw_output(self.min, unit_string)?,
+                unit_helper::time_unit_format_output(self.max, unit_string, style)?
+            )
+        })
+    }
+
+    /// `readable` returns the time range formatted for display in `format`, as in
+    /// [`unit_helper::time_display`]. Ex: `1 h 15 min`, `25 min–30 min`, `about 10 min`
+    #[must_use]
+    pub fn readable(&self, format: TimeFormat) -> String {
+        let approximate = if self.approximate { "about " } else { "" };
+        if self.is_exact() {
+            format!("{approximate}{}", unit_helper::time_display(self.min, format))
+        } else {
+            format!(
+                "{approximate}{}\u{2013}{}",
+                unit_helper::time_display(self.min, format),
+                unit_helper::time_display(self.max, format)
+            )
+        }
+    }
+}
+
+impl fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.readable(TimeFormat::default()))
+    }
+}
+
+impl Add for TimeRange {
+    type Output = Self;
+
+    #[expect(clippy::arithmetic_side_effects)] //TODO: change this to saturating
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            min: self.min + rhs.min,
+            max: self.max + rhs.max,
+            approximate: self.approximate || rhs.approximate,
+        }
+    }
+}
+
+impl AddAssign for TimeRange {
+    #[expect(clippy::arithmetic_side_effects)] //TODO: change this to saturating
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubRecipe {
+    /// `scale_factor` returns the factor `recipe` needs to be scaled by to make the quantity
+    /// needed.
+    ///
+    /// # Errors
+    ///
+    /// Will error if:
+    /// - the quantity is given as an amount and `recipe` does not specify an amount made
+    /// - dividing the amount by the amount made by `recipe` overflows
+    pub fn scale_factor(&self, recipe: &Recipe) -> anyhow::Result<Rational64> {
+        match self.quantity {
+            SubRecipeQuantity::Batches(batches) => Ok(batches),
+            SubRecipeQuantity::Amount(amount) => {
+                if recipe.amount_made.quantity.is_zero() {
+                    anyhow::bail!(
+                        "Can not use {amount} {} of recipe {} as it does not specify an amount made",
+                        recipe.amount_made.units,
+                        recipe.name
+                    );
+                }
+                amount
+                    .checked_div(&recipe.amount_made.quantity)
+                    .ok_or_else(|| anyhow::anyhow!("Using {amount} of recipe {} overflowed", recipe.name))
+            }
+        }
+    }
+
+    /// `name` returns the name of the referenced recipe, or its ID if it hasn't been resolved
+    #[must_use]
+    pub fn name(&self) -> String {
+        self.recipe.as_ref().map_or_else(|| self.id.to_string(), |r| r.name.clone())
+    }
+
+    /// `quantity_text` returns the quantity of the referenced recipe formatted for display, with
+    /// fractions written in `style`. Ex: `½ batch`, `2 cups`
+    #[must_use]
+    pub fn quantity_text(&self, style: FractionStyle) -> String {
+        match self.quantity {
+            SubRecipeQuantity::Batches(batches) => {
+                let plural = if batches <= Rational64::from_integer(1) {
+                    "batch"
+                } else {
+                    "batches"
+                };
+                format!("{} {plural}", unit_helper::approximate_fraction(batches, style))
+            }
+            SubRecipeQuantity::Amount(amount) => {
+                let amount = unit_helper::approximate_fraction(amount, style);
+                match &self.recipe {
+                    Some(recipe) => format!("{amount} {}", recipe.amount_made.units),
+                    None => amount,
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<filetypes::Step> for Step {
+    type Error = anyhow::Error;
+
+    fn try_from(input: filetypes::Step) -> anyhow::Result<Self> {
+        // units are stored as their abbreviation, however they were written in the file
+        let time_needed_unit = input
+            .time_needed_unit
+            .as_deref()
+            .map(unit_helper::time_unit_abbreviation)
+            .transpose()
+            .context("time_needed_unit")?;
+        let temperature_unit = input
+            .temperature_unit
+            .as_deref()
+            .map(unit_helper::temperature_unit_abbreviation)
+            .transpose()
+            .context("temperature_unit")?;
+        // the long end of a time range is meaningless without the short end
+        if input.time_needed.is_none() && input.time_needed_max.is_some() {
+            anyhow::bail!("time_needed_max specified without time_needed");
+        }
+        Ok(Self {
             id: input.id,
-            time_needed: input.time_needed.map(|x| {
-                unit_helper::time_unit_input_parser(x, &input.time_needed_unit.clone().unwrap_or("placeholder".to_string()))
-            }),
-            time_needed_unit: input.time_needed_unit,
-            temperature: input.temperature.map(|x| {
-                unit_helper::temp_interval_unit_input_parser(
-                    x,
-                    &input.temperature_unit.clone().unwrap_or("placeholder".to_string()),
-                )
-            }),
-            temperature_unit: input.temperature_unit,
+            time_needed: input
+                .time_needed
+                .map(|x| -> Result<TimeRange, UnitParseError> {
+                    let unit = time_needed_unit.unwrap_or("placeholder");
+                    let min = unit_helper::time_unit_input_parser(x, unit)?;
+                    Ok(TimeRange {
+                        min,
+                        max: input
+                            .time_needed_max
+                            .map_or(Ok(min), |max| unit_helper::time_unit_input_parser(max, unit))?,
+                        approximate: input.time_approximate.unwrap_or_default(),
+                    })
+                })
+                .transpose()
+                .context("time_needed_unit")?,
+            time_needed_unit: time_needed_unit.map(str::to_owned),
+            temperature: input
+                .temperature
+                .map(|x| unit_helper::temperature_unit_input_parser(x, temperature_unit.unwrap_or("placeholder")))
+                .transpose()
+                .context("temperature_unit")?,
+            temperature_unit: temperature_unit.map(str::to_owned),
             instructions: input.instructions,
             ingredients: if input.ingredients.is_some() {
-                input.ingredients.unwrap().into_iter().map(Into::into).collect()
+                input
+                    .ingredients
+                    .unwrap()
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<anyhow::Result<_>>()?
             } else {
                 Vec::new()
             },
@@ -151,6 +392,23 @@ impl From<filetypes::Step> for Step {
                 Vec::new()
             },
             step_type: input.step_type.into(),
+            sub_recipe: input.sub_recipe.map(Into::into),
+            depends_on: input.depends_on.unwrap_or_default(),
+            parallel: input.parallel.unwrap_or_default(),
+            photos: input.photos.unwrap_or_default().into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+impl From<filetypes::SubRecipe> for SubRecipe {
+    fn from(input: filetypes::SubRecipe) -> Self {
+        Self {
+            id: input.id,
+            quantity: match input.quantity {
+                filetypes::SubRecipeQuantity::Batches(b) => SubRecipeQuantity::Batches(b),
+                filetypes::SubRecipeQuantity::Amount(a) => SubRecipeQuantity::Amount(a),
+            },
+            recipe: None,
         }
     }
 }
@@ -1,2 +1,117 @@
-/// [`Tag`] datatype
-pub type Tag = String;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use serde::Serialize;
+
+/// [`Tag`] is a label on a recipe, with an optional namespace to group related tags, like
+/// `cuisine:thai` or `course:main`.
+///
+/// Tags are normalized when created, so `Dessert`, `dessert ` and ` DESSERT` are the same tag.
+/// Names and namespaces are lowercased, trimmed, and runs of whitespace are collapsed to a single
+/// space.
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(into = "String")]
+pub struct Tag {
+    /// the group this tag belongs to, like `cuisine` in `cuisine:thai`
+    pub namespace: Option<String>,
+    /// the tag itself, like `thai` in `cuisine:thai`
+    pub name: String,
+}
+
+/// [`TagGroups`] are tags grouped by namespace. Tags without a namespace are under `None`, which
+/// sorts first.
+pub type TagGroups = BTreeMap<Option<String>, Vec<Tag>>;
+
+/// [`TagAliases`] maps alternate spellings of tags to the tag they should be treated as, like
+/// `desserts` to `course:dessert`.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct TagAliases {
+    /// canonical tags keyed by their alias
+    pub aliases: HashMap<Tag, Tag>,
+}
+
+impl Tag {
+    /// `new` parses and normalizes a tag. Everything before the first `:` is the namespace.
+    #[must_use]
+    pub fn new(tag: &str) -> Self {
+        match tag.split_once(':') {
+            Some((namespace, name)) if !normalize(namespace).is_empty() => Self {
+                namespace: Some(normalize(namespace)),
+                name: normalize(name),
+            },
+            Some((_, name)) => Self {
+                namespace: None,
+                name: normalize(name),
+            },
+            None => Self {
+                namespace: None,
+                name: normalize(tag),
+            },
+        }
+    }
+
+    /// `group` groups tags by namespace. Tags within each namespace are sorted and deduplicated.
+    #[must_use]
+    pub fn group<'a, I>(tags: I) -> TagGroups
+    where
+        I: IntoIterator<Item = &'a Tag>,
+    {
+        let mut groups = TagGroups::new();
+        for tag in tags {
+            groups.entry(tag.namespace.clone()).or_default().push(tag.clone());
+        }
+        for group in groups.values_mut() {
+            group.sort_unstable();
+            group.dedup();
+        }
+        groups
+    }
+}
+
+impl TagAliases {
+    /// `resolve` returns the tag `tag` is an alias of, or `tag` itself if it isn't an alias
+    #[must_use]
+    pub fn resolve(&self, tag: &Tag) -> Tag {
+        self.aliases.get(tag).unwrap_or(tag).clone()
+    }
+}
+
+/// `normalize` lowercases `input`, trims it and collapses runs of whitespace to a single space
+fn normalize(input: &str) -> String {
+    input.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.namespace {
+            Some(namespace) => write!(f, "{namespace}:{}", self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl From<&str> for Tag {
+    fn from(input: &str) -> Self {
+        Self::new(input)
+    }
+}
+
+impl From<String> for Tag {
+    fn from(input: String) -> Self {
+        Self::new(&input)
+    }
+}
+
+impl From<Tag> for String {
+    fn from(input: Tag) -> Self {
+        input.to_string()
+    }
+}
+
+impl From<&HashMap<String, String>> for TagAliases {
+    fn from(input: &HashMap<String, String>) -> Self {
+        Self {
+            aliases: input.iter().map(|(alias, tag)| (Tag::new(alias), Tag::new(tag))).collect(),
+        }
+    }
+}
@@ -1,546 +1,1286 @@
+use std::{error::Error, fmt};
+
 use num_rational::Rational64;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
 use uom::{
     fmt::DisplayStyle,
     si::{
-        Unit,
-        mass::{
-            centigram, decagram, decigram, gigagram, gram, hectogram, kilogram, megagram, microgram, milligram, nanogram, ounce,
-            picogram, pound, teragram,
-        },
-        rational64::{Mass, TemperatureInterval, Time, Volume},
-        temperature_interval::{
-            centikelvin, decakelvin, decikelvin, degree_celsius, degree_fahrenheit, degree_rankine, gigakelvin, hectokelvin,
-            kelvin, kilokelvin, megakelvin, microkelvin, millikelvin, nanokelvin, picokelvin, terakelvin,
-        },
-        time::{
-            centisecond, day, decasecond, decisecond, gigasecond, hectosecond, hour, kilosecond, megasecond, microsecond,
-            millisecond, minute, nanosecond, picosecond, second, terasecond, year,
-        },
-        volume::{
-            acre_foot, barrel, bushel, centiliter, cord, cubic_centimeter, cubic_decameter, cubic_decimeter, cubic_foot,
-            cubic_gigameter, cubic_hectometer, cubic_inch, cubic_kilometer, cubic_megameter, cubic_meter, cubic_micrometer,
-            cubic_mile, cubic_millimeter, cubic_nanometer, cubic_picometer, cubic_terameter, cubic_yard, cup, decaliter,
-            deciliter, fluid_ounce, fluid_ounce_imperial, gallon, gallon_imperial, gigaliter, gill, gill_imperial, hectoliter,
-            kiloliter, liter, megaliter, microliter, milliliter, nanoliter, peck, picoliter, pint_dry, pint_liquid, quart_dry,
-            quart_liquid, tablespoon, teaspoon, teraliter,
-        },
+        mass::{gram, kilogram, pound},
+        mass_density::kilogram_per_cubic_meter,
+        rational64::{Mass, MassDensity, TemperatureInterval, ThermodynamicTemperature, Time, Volume},
+        temperature_interval::kelvin,
+        thermodynamic_temperature as thermo,
+        time::second,
+        volume::{cubic_meter, cup, fluid_ounce_imperial, gallon, gallon_imperial, liter, tablespoon},
     },
 };
 
-/// takes in a value and unit string and returns a `[uom::si::Time]` value.
-pub fn time_unit_input_parser(value: Rational64, unit_string: &str) -> Time {
-    match unit_string {
-        "Ts" => Time::new::<terasecond>(value),
-        "Gs" => Time::new::<gigasecond>(value),
-        "Ms" => Time::new::<megasecond>(value),
-        "ks" => Time::new::<kilosecond>(value),
-        "hs" => Time::new::<hectosecond>(value),
-        "das" => Time::new::<decasecond>(value),
-        "s" => Time::new::<second>(value),
-        "ds" => Time::new::<decisecond>(value),
-        "cs" => Time::new::<centisecond>(value),
-        "ms" => Time::new::<millisecond>(value),
-        "µs" => Time::new::<microsecond>(value),
-        "ns" => Time::new::<nanosecond>(value),
-        "ps" => Time::new::<picosecond>(value),
-        "d" => Time::new::<day>(value),
-        "h" => Time::new::<hour>(value),
-        "min" => Time::new::<minute>(value),
-        "a" => Time::new::<year>(value),
-        "placeholder" => panic!("Unit not specified for time_needed"),
-        x => panic!("{x} not recognized as a supported time unit abbreviation"),
+use super::{
+    ingredient::UnitType,
+    unit_registry::{self, UnitDefinition, UnitKind},
+};
+
+/// `UnitParseError` is returned when a unit in a recipe or catalog file can't be parsed
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnitParseError {
+    /// No unit was given for a value that needs one
+    Missing {
+        /// kind of quantity the unit is for, like `mass`
+        kind: &'static str,
+    },
+    /// The unit is not a supported unit of this kind
+    Unknown {
+        /// kind of quantity the unit is for, like `mass`
+        kind: &'static str,
+        /// the unit as it was written
+        unit: String,
+        /// supported units that are closest to what was written
+        suggestions: Vec<&'static str>,
+    },
+    /// The unit is a shorthand for more than one supported unit, like `t` for either a teaspoon
+    /// or a tablespoon
+    Ambiguous {
+        /// kind of quantity the unit is for, like `mass`
+        kind: &'static str,
+        /// the unit as it was written
+        unit: String,
+        /// abbreviations of the units it could mean
+        candidates: Vec<&'static str>,
+    },
+    /// The value is too large or too precise to convert to or from the unit
+    OutOfRange {
+        /// kind of quantity the unit is for, like `mass`
+        kind: &'static str,
+        /// abbreviation of the unit
+        unit: &'static str,
+        /// the value that couldn't be converted
+        value: Rational64,
+    },
+}
+
+impl UnitParseError {
+    /// `out_of_range` builds an [`UnitParseError::OutOfRange`] for `value` in `unit`
+    fn out_of_range(unit: &UnitDefinition, value: Rational64) -> Self {
+        Self::OutOfRange {
+            kind: unit.kind.name(),
+            unit: unit.abbreviation,
+            value,
+        }
+    }
+
+    /// `unknown` builds an [`UnitParseError::Unknown`] for `unit`, suggesting the units from
+    /// `supported` that are closest to it
+    fn unknown(kind: &'static str, unit: &str, supported: &[&'static str]) -> Self {
+        let lowercase = unit.to_lowercase();
+        let max_distance = lowercase.chars().count().saturating_sub(1).min(2);
+        let distances: Vec<(usize, &'static str)> = supported
+            .iter()
+            .map(|s| (edit_distance(&lowercase, &s.to_lowercase()), *s))
+            .collect();
+        // only suggest the closest units, so a near miss like `tbs` isn't buried among units that
+        // just happen to be short
+        let closest = distances.iter().map(|(distance, _)| *distance).min().unwrap_or_default();
+        Self::Unknown {
+            kind,
+            unit: unit.to_owned(),
+            suggestions: if closest <= max_distance {
+                distances
+                    .into_iter()
+                    .filter(|(distance, _)| *distance == closest)
+                    .map(|(_, s)| s)
+                    .take(3)
+                    .collect()
+            } else {
+                Vec::new()
+            },
+        }
+    }
+}
+
+impl fmt::Display for UnitParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Missing { kind } => write!(f, "no {kind} unit specified"),
+            Self::Unknown { kind, unit, suggestions } => {
+                write!(f, "\"{unit}\" is not a supported {kind} unit")?;
+                if suggestions.is_empty() {
+                    write!(f, ". Run with --print-units to list the supported units")
+                } else {
+                    write!(
+                        f,
+                        ". Did you mean {}?",
+                        suggestions
+                            .iter()
+                            .map(|s| format!("\"{s}\""))
+                            .collect::<Vec<_>>()
+                            .join(" or ")
+                    )
+                }
+            }
+            Self::Ambiguous { kind, unit, candidates } => write!(
+                f,
+                "\"{unit}\" could be more than one {kind} unit. Use one of {} instead",
+                candidates.iter().map(|c| format!("\"{c}\"")).collect::<Vec<_>>().join(" or ")
+            ),
+            Self::OutOfRange { kind, unit, value } => {
+                write!(f, "{value} is too large or too precise to convert as a {kind} in {unit}")
+            }
+        }
+    }
+}
+
+impl Error for UnitParseError {}
+
+/// `edit_distance` returns the number of single character insertions, deletions or substitutions
+/// needed to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i.saturating_add(1)];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j].saturating_add(usize::from(a_char != *b_char));
+            let insertion = current[j].saturating_add(1);
+            let deletion = previous[j.saturating_add(1)].saturating_add(1);
+            current.push(substitution.min(insertion).min(deletion));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// `parse_unit` returns the unit of `kind` written as `unit_string`.
+///
+/// Exact matches of the abbreviations, full names and kitchen shorthands in the
+/// [`unit_registry`] are tried first, so `T` is a tablespoon and `Mg` a megagram. Then case is
+/// ignored, so `TSP` and `Cups` also work. Periods and extra spaces are ignored throughout, so
+/// `tsp.` is a teaspoon.
+fn parse_unit(kind: UnitKind, unit_string: &str) -> Result<&'static UnitDefinition, UnitParseError> {
+    if unit_string == "placeholder" {
+        return Err(UnitParseError::Missing { kind: kind.name() });
+    }
+    let cleaned = unit_string.replace('.', " ").split_whitespace().collect::<Vec<_>>().join(" ");
+    if let Some(unit) = unit_registry::unit(kind, &cleaned) {
+        return Ok(unit);
+    }
+    // every way of writing each unit, along with the unit it means. Abbreviations and full names
+    // come before shorthands, so they are suggested first
+    let spellings: Vec<(&'static str, &'static UnitDefinition)> = unit_registry::units_of(kind)
+        .flat_map(|u| [u.abbreviation, u.singular, u.plural].map(|spelling| (spelling, u)))
+        .chain(unit_registry::units_of(kind).flat_map(|u| u.aliases.iter().map(move |alias| (*alias, u))))
+        .collect();
+    let lowercase = cleaned.to_lowercase();
+    for case_sensitive in [true, false] {
+        let mut candidates: Vec<&'static UnitDefinition> = Vec::new();
+        for (spelling, unit) in &spellings {
+            let matches = if case_sensitive {
+                *spelling == cleaned
+            } else {
+                spelling.to_lowercase() == lowercase
+            };
+            if matches && !candidates.contains(unit) {
+                candidates.push(unit);
+            }
+        }
+        match candidates.as_slice() {
+            [] => {}
+            [unit] => return Ok(unit),
+            _ => {
+                return Err(UnitParseError::Ambiguous {
+                    kind: kind.name(),
+                    unit: unit_string.to_owned(),
+                    candidates: candidates.iter().map(|u| u.abbreviation).collect(),
+                });
+            }
+        }
     }
+    Err(UnitParseError::unknown(
+        kind.name(),
+        unit_string,
+        &spellings.iter().map(|(spelling, _)| *spelling).collect::<Vec<_>>(),
+    ))
+}
+
+/// `time_unit_abbreviation` returns the abbreviation of the time unit written as `unit_string`,
+/// which can also be a full name like `minutes` or a shorthand like `hrs`
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported time unit, or is a shorthand for more than one
+pub fn time_unit_abbreviation(unit_string: &str) -> Result<&'static str, UnitParseError> {
+    parse_unit(UnitKind::Time, unit_string).map(|u| u.abbreviation)
+}
+
+/// `temperature_unit_abbreviation` returns the abbreviation of the temperature unit written as
+/// `unit_string`, which can also be a full name like `degrees Celsius` or a shorthand like `F`.
+/// Temperatures and temperature intervals use the same units
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported temperature unit, or is a shorthand for more
+/// than one
+pub fn temperature_unit_abbreviation(unit_string: &str) -> Result<&'static str, UnitParseError> {
+    parse_unit(UnitKind::Temperature, unit_string).map(|u| u.abbreviation)
+}
+
+/// `mass_unit_abbreviation` returns the abbreviation of the mass unit written as `unit_string`,
+/// which can also be a full name like `pounds` or a shorthand like `lbs`
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported mass unit, or is a shorthand for more than one
+pub fn mass_unit_abbreviation(unit_string: &str) -> Result<&'static str, UnitParseError> {
+    parse_unit(UnitKind::Mass, unit_string).map(|u| u.abbreviation)
+}
+
+/// `volume_unit_abbreviation` returns the abbreviation of the volume unit written as
+/// `unit_string`, which can also be a full name like `teaspoons` or a shorthand like `T`
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported volume unit, or is a shorthand for more than
+/// one, like `t`
+pub fn volume_unit_abbreviation(unit_string: &str) -> Result<&'static str, UnitParseError> {
+    parse_unit(UnitKind::Volume, unit_string).map(|u| u.abbreviation)
+}
+
+/// takes in a value and unit string and returns a `[uom::si::Time]` value.
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported time unit, or `value` is too large or too precise
+/// to convert to it
+pub fn time_unit_input_parser(value: Rational64, unit_string: &str) -> Result<Time, UnitParseError> {
+    let unit = parse_unit(UnitKind::Time, unit_string)?;
+    let base = unit.to_base(value).ok_or_else(|| UnitParseError::out_of_range(unit, value))?;
+    Ok(Time::new::<second>(base))
 }
 
 /// takes in a `[uom::si::Time]` value and unit string and returns the raw value in the
 /// specified unit for display or output to file.
-pub fn time_unit_raw_output(value: Time, unit_string: &str) -> Rational64 {
-    match unit_string {
-        "Ts" => value.get::<terasecond>(),
-        "Gs" => value.get::<gigasecond>(),
-        "Ms" => value.get::<megasecond>(),
-        "ks" => value.get::<kilosecond>(),
-        "hs" => value.get::<hectosecond>(),
-        "das" => value.get::<decasecond>(),
-        "s" => value.get::<second>(),
-        "ds" => value.get::<decisecond>(),
-        "cs" => value.get::<centisecond>(),
-        "ms" => value.get::<millisecond>(),
-        "µs" => value.get::<microsecond>(),
-        "ns" => value.get::<nanosecond>(),
-        "ps" => value.get::<picosecond>(),
-        "d" => value.get::<day>(),
-        "h" => value.get::<hour>(),
-        "min" => value.get::<minute>(),
-        "a" => value.get::<year>(),
-        "placeholder" => panic!("Unit not specified for time_needed"),
-        x => panic!("{x} not recognized as a supported time unit abbreviation"),
-    }
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported time unit, or `value` is too large or too precise
+/// to convert to it
+pub fn time_unit_raw_output(value: Time, unit_string: &str) -> Result<Rational64, UnitParseError> {
+    let unit = parse_unit(UnitKind::Time, unit_string)?;
+    unit.from_base(value.value)
+        .ok_or_else(|| UnitParseError::out_of_range(unit, value.value))
 }
 
 /// takes in a `[uom::si::Time]` value, unit string and `[uom::fmt::DisplayStyle]` and returns a formatted string in the
 /// specified unit for display or output to file.
-pub fn time_unit_format_output(value: Time, unit_string: &str, style: DisplayStyle) -> String {
-    match unit_string {
-        "Ts" => format!("{}", value.into_format_args(terasecond, style)),
-        "Gs" => format!("{}", value.into_format_args(gigasecond, style)),
-        "Ms" => format!("{}", value.into_format_args(megasecond, style)),
-        "ks" => format!("{}", value.into_format_args(kilosecond, style)),
-        "hs" => format!("{}", value.into_format_args(hectosecond, style)),
-        "das" => format!("{}", value.into_format_args(decasecond, style)),
-        "s" => format!("{}", value.into_format_args(second, style)),
-        "ds" => format!("{}", value.into_format_args(decisecond, style)),
-        "cs" => format!("{}", value.into_format_args(centisecond, style)),
-        "ms" => format!("{}", value.into_format_args(millisecond, style)),
-        "µs" => format!("{}", value.into_format_args(microsecond, style)),
-        "ns" => format!("{}", value.into_format_args(nanosecond, style)),
-        "ps" => format!("{}", value.into_format_args(picosecond, style)),
-        "d" => format!("{}", value.into_format_args(day, style)),
-        "h" => format!("{}", value.into_format_args(hour, style)),
-        "min" => format!("{}", value.into_format_args(minute, style)),
-        "a" => format!("{}", value.into_format_args(year, style)),
-        "placeholder" => panic!("Unit not specified for time_needed"),
-        x => panic!("{x} not recognized as a supported time unit abbreviation"),
-    }
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported time unit, or `value` is too large or too precise
+/// to convert to it
+pub fn time_unit_format_output(value: Time, unit_string: &str, style: DisplayStyle) -> Result<String, UnitParseError> {
+    let unit = parse_unit(UnitKind::Time, unit_string)?;
+    let converted = unit
+        .from_base(value.value)
+        .ok_or_else(|| UnitParseError::out_of_range(unit, value.value))?;
+    Ok(unit.format(converted, style))
 }
 
 /// takes a value and unit string and returns a `[uom::si::TemperatureInterval]` value.
-pub fn temp_interval_unit_input_parser(value: Rational64, unit_string: &str) -> TemperatureInterval {
-    match unit_string {
-        "TK" => TemperatureInterval::new::<terakelvin>(value),
-        "GK" => TemperatureInterval::new::<gigakelvin>(value),
-        "MK" => TemperatureInterval::new::<megakelvin>(value),
-        "kK" => TemperatureInterval::new::<kilokelvin>(value),
-        "hK" => TemperatureInterval::new::<hectokelvin>(value),
-        "daK" => TemperatureInterval::new::<decakelvin>(value),
-        "K" => TemperatureInterval::new::<kelvin>(value),
-        "dK" => TemperatureInterval::new::<decikelvin>(value),
-        "cK" => TemperatureInterval::new::<centikelvin>(value),
-        "mK" => TemperatureInterval::new::<millikelvin>(value),
-        "µK" => TemperatureInterval::new::<microkelvin>(value),
-        "nK" => TemperatureInterval::new::<nanokelvin>(value),
-        "pK" => TemperatureInterval::new::<picokelvin>(value),
-        "°C" => TemperatureInterval::new::<degree_celsius>(value),
-        "°F" => TemperatureInterval::new::<degree_fahrenheit>(value),
-        "°R" => TemperatureInterval::new::<degree_rankine>(value),
-        "placeholder" => panic!("Unit not specified for temperature"),
-        x => panic!("{x} not recognized as a supported temperature interval abbreviation"),
-    }
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported temperature interval unit, or `value` is too large or too precise
+/// to convert to it
+pub fn temp_interval_unit_input_parser(value: Rational64, unit_string: &str) -> Result<TemperatureInterval, UnitParseError> {
+    let unit = parse_unit(UnitKind::Temperature, unit_string)?;
+    let base = unit
+        .interval_to_base(value)
+        .ok_or_else(|| UnitParseError::out_of_range(unit, value))?;
+    Ok(TemperatureInterval::new::<kelvin>(base))
 }
 
 /// takes a `[uom::si::TemperatureInterval]` and unit string and returns the raw value in the
 /// specified unit for display or output to file.
-pub fn temp_interval_unit_raw_output(value: TemperatureInterval, unit_string: &str) -> Rational64 {
-    match unit_string {
-        "TK" => value.get::<terakelvin>(),
-        "GK" => value.get::<gigakelvin>(),
-        "MK" => value.get::<megakelvin>(),
-        "kK" => value.get::<kilokelvin>(),
-        "hK" => value.get::<hectokelvin>(),
-        "daK" => value.get::<decakelvin>(),
-        "K" => value.get::<kelvin>(),
-        "dK" => value.get::<decikelvin>(),
-        "cK" => value.get::<centikelvin>(),
-        "mK" => value.get::<millikelvin>(),
-        "µK" => value.get::<microkelvin>(),
-        "nK" => value.get::<nanokelvin>(),
-        "pK" => value.get::<picokelvin>(),
-        "°C" => value.get::<degree_celsius>(),
-        "°F" => value.get::<degree_fahrenheit>(),
-        "°R" => value.get::<degree_rankine>(),
-        "placeholder" => panic!("Unit not specified for temperature"),
-        x => panic!("{x} not recognized as a supported temperature interval abbreviation"),
-    }
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported temperature unit, or `value` is too large or too precise
+/// to convert to it
+pub fn temp_interval_unit_raw_output(value: TemperatureInterval, unit_string: &str) -> Result<Rational64, UnitParseError> {
+    let unit = parse_unit(UnitKind::Temperature, unit_string)?;
+    unit.interval_from_base(value.value)
+        .ok_or_else(|| UnitParseError::out_of_range(unit, value.value))
 }
 
 /// takes a `[uom::si::TemperatureInterval]` and unit string and returns a formatted string in the
 /// specified unit for display or output to file.
-pub fn temp_interval_unit_format_output(value: TemperatureInterval, unit_string: &str, style: DisplayStyle) -> String {
-    match unit_string {
-        "TK" => format!("{}", value.into_format_args(terakelvin, style)),
-        "GK" => format!("{}", value.into_format_args(gigakelvin, style)),
-        "MK" => format!("{}", value.into_format_args(megakelvin, style)),
-        "kK" => format!("{}", value.into_format_args(kilokelvin, style)),
-        "hK" => format!("{}", value.into_format_args(hectokelvin, style)),
-        "daK" => format!("{}", value.into_format_args(decakelvin, style)),
-        "K" => format!("{}", value.into_format_args(kelvin, style)),
-        "dK" => format!("{}", value.into_format_args(decikelvin, style)),
-        "cK" => format!("{}", value.into_format_args(centikelvin, style)),
-        "mK" => format!("{}", value.into_format_args(millikelvin, style)),
-        "µK" => format!("{}", value.into_format_args(microkelvin, style)),
-        "nK" => format!("{}", value.into_format_args(nanokelvin, style)),
-        "pK" => format!("{}", value.into_format_args(picokelvin, style)),
-        "°C" => format!("{}", value.into_format_args(degree_celsius, style)),
-        "°F" => format!("{}", value.into_format_args(degree_fahrenheit, style)),
-        "°R" => format!("{}", value.into_format_args(degree_rankine, style)),
-        "placeholder" => panic!("Unit not specified for temperature"),
-        x => panic!("{x} not recognized as a supported temperature interval abbreviation"),
-    }
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported temperature unit, or `value` is too large or too precise
+/// to convert to it
+pub fn temp_interval_unit_format_output(
+    value: TemperatureInterval,
+    unit_string: &str,
+    style: DisplayStyle,
+) -> Result<String, UnitParseError> {
+    let unit = parse_unit(UnitKind::Temperature, unit_string)?;
+    let converted = unit
+        .interval_from_base(value.value)
+        .ok_or_else(|| UnitParseError::out_of_range(unit, value.value))?;
+    Ok(unit.format(converted, style))
+}
+
+/// takes a value and unit string and returns a `[uom::si::ThermodynamicTemperature]` value.
+///
+/// This is an absolute temperature, like an oven setting, as opposed to a
+/// `[uom::si::TemperatureInterval]` which is a difference between two temperatures.
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported temperature unit, or `value` is too large or too precise
+/// to convert to it
+pub fn temperature_unit_input_parser(value: Rational64, unit_string: &str) -> Result<ThermodynamicTemperature, UnitParseError> {
+    let unit = parse_unit(UnitKind::Temperature, unit_string)?;
+    let base = unit.to_base(value).ok_or_else(|| UnitParseError::out_of_range(unit, value))?;
+    Ok(ThermodynamicTemperature::new::<thermo::kelvin>(base))
+}
+
+/// takes a `[uom::si::ThermodynamicTemperature]` and unit string and returns the raw value in
+/// the specified unit for display or output to file.
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported temperature unit, or `value` is too large or too precise
+/// to convert to it
+pub fn temperature_unit_raw_output(value: ThermodynamicTemperature, unit_string: &str) -> Result<Rational64, UnitParseError> {
+    let unit = parse_unit(UnitKind::Temperature, unit_string)?;
+    unit.from_base(value.value)
+        .ok_or_else(|| UnitParseError::out_of_range(unit, value.value))
+}
+
+/// takes a `[uom::si::ThermodynamicTemperature]` and unit string and returns a formatted string
+/// in the specified unit for display or output to file.
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported temperature unit, or `value` is too large or too precise
+/// to convert to it
+pub fn temperature_unit_format_output(
+    value: ThermodynamicTemperature,
+    unit_string: &str,
+    style: DisplayStyle,
+) -> Result<String, UnitParseError> {
+    let unit = parse_unit(UnitKind::Temperature, unit_string)?;
+    let converted = unit
+        .from_base(value.value)
+        .ok_or_else(|| UnitParseError::out_of_range(unit, value.value))?;
+    Ok(unit.format(converted, style))
 }
 
 /// takes a value and unit string and returns a `[uom::si::Mass]` value.
-pub fn mass_unit_input_parser(value: Rational64, unit_string: &str) -> Mass {
-    match unit_string {
-        "Tg" => Mass::new::<teragram>(value),
-        "Gg" => Mass::new::<gigagram>(value),
-        "Mg" => Mass::new::<megagram>(value),
-        "kg" => Mass::new::<kilogram>(value),
-        "hg" => Mass::new::<hectogram>(value),
-        "dag" => Mass::new::<decagram>(value),
-        "g" => Mass::new::<gram>(value),
-        "dg" => Mass::new::<decigram>(value),
-        "cg" => Mass::new::<centigram>(value),
-        "mg" => Mass::new::<milligram>(value),
-        "µg" => Mass::new::<microgram>(value),
-        "ng" => Mass::new::<nanogram>(value),
-        "pg" => Mass::new::<picogram>(value),
-        "oz" => Mass::new::<ounce>(value),
-        "lb" => Mass::new::<pound>(value),
-        "placeholder" => panic!("Unit not specified for ingredient mass"),
-        x => panic!("{x} not recognized as a supported mass unit abbreviation"),
-    }
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported mass unit, or `value` is too large or too precise
+/// to convert to it
+pub fn mass_unit_input_parser(value: Rational64, unit_string: &str) -> Result<Mass, UnitParseError> {
+    let unit = parse_unit(UnitKind::Mass, unit_string)?;
+    let base = unit.to_base(value).ok_or_else(|| UnitParseError::out_of_range(unit, value))?;
+    Ok(Mass::new::<kilogram>(base))
 }
 
 /// takes a `[uom::si::Mass]` value and unit string and returns the raw value in the
 /// specified unit for display or output to file.
-pub fn mass_unit_raw_output(value: Mass, unit_string: &str) -> Rational64 {
-    match unit_string {
-        "Tg" => value.get::<teragram>(),
-        "Gg" => value.get::<gigagram>(),
-        "Mg" => value.get::<megagram>(),
-        "kg" => value.get::<kilogram>(),
-        "hg" => value.get::<hectogram>(),
-        "dag" => value.get::<decagram>(),
-        "g" => value.get::<gram>(),
-        "dg" => value.get::<decigram>(),
-        "cg" => value.get::<centigram>(),
-        "mg" => value.get::<milligram>(),
-        "µg" => value.get::<microgram>(),
-        "ng" => value.get::<nanogram>(),
-        "pg" => value.get::<picogram>(),
-        "oz" => value.get::<ounce>(),
-        "lb" => value.get::<pound>(),
-        "placeholder" => panic!("Unit not specified for ingredient mass"),
-        x => panic!("{x} not recognized as a supported mass unit abbreviation"),
-    }
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported mass unit, or `value` is too large or too precise
+/// to convert to it
+pub fn mass_unit_raw_output(value: Mass, unit_string: &str) -> Result<Rational64, UnitParseError> {
+    let unit = parse_unit(UnitKind::Mass, unit_string)?;
+    unit.from_base(value.value)
+        .ok_or_else(|| UnitParseError::out_of_range(unit, value.value))
 }
 
 /// takes a `[uom::si::Mass]` value and unit string and returns a formatted string in the
 /// specified unit for display or output to file.
-pub fn mass_unit_format_output(value: Mass, unit_string: &str, style: DisplayStyle) -> String {
-    match unit_string {
-        "Tg" => format!("{}", value.into_format_args(teragram, style)),
-        "Gg" => format!("{}", value.into_format_args(gigagram, style)),
-        "Mg" => format!("{}", value.into_format_args(megagram, style)),
-        "kg" => format!("{}", value.into_format_args(kilogram, style)),
-        "hg" => format!("{}", value.into_format_args(hectogram, style)),
-        "dag" => format!("{}", value.into_format_args(decagram, style)),
-        "g" => format!("{}", value.into_format_args(gram, style)),
-        "dg" => format!("{}", value.into_format_args(decigram, style)),
-        "cg" => format!("{}", value.into_format_args(centigram, style)),
-        "mg" => format!("{}", value.into_format_args(milligram, style)),
-        "µg" => format!("{}", value.into_format_args(microgram, style)),
-        "ng" => format!("{}", value.into_format_args(nanogram, style)),
-        "pg" => format!("{}", value.into_format_args(picogram, style)),
-        "oz" => format!("{}", value.into_format_args(ounce, style)),
-        "lb" => format!("{}", value.into_format_args(pound, style)),
-        "placeholder" => panic!("Unit not specified for ingredient mass"),
-        x => panic!("{x} not recognized as a supported mass unit abbreviation"),
-    }
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported mass unit, or `value` is too large or too precise
+/// to convert to it
+pub fn mass_unit_format_output(value: Mass, unit_string: &str, style: DisplayStyle) -> Result<String, UnitParseError> {
+    let unit = parse_unit(UnitKind::Mass, unit_string)?;
+    let converted = unit
+        .from_base(value.value)
+        .ok_or_else(|| UnitParseError::out_of_range(unit, value.value))?;
+    Ok(unit.format(converted, style))
 }
 
 /// takes a value and unit string and returns a `[uom::si::Volume]` value.
-pub fn volume_unit_input_parser(value: Rational64, unit_string: &str) -> Volume {
-    match unit_string {
-        "Tm³" => Volume::new::<cubic_terameter>(value),
-        "Gm³" => Volume::new::<cubic_gigameter>(value),
-        "Mm³" => Volume::new::<cubic_megameter>(value),
-        "km³" => Volume::new::<cubic_kilometer>(value),
-        "hm³" => Volume::new::<cubic_hectometer>(value),
-        "dam³" => Volume::new::<cubic_decameter>(value),
-        "m³" => Volume::new::<cubic_meter>(value),
-        "dm³" => Volume::new::<cubic_decimeter>(value),
-        "cm³" => Volume::new::<cubic_centimeter>(value),
-        "mm³" => Volume::new::<cubic_millimeter>(value),
-        "µm³" => Volume::new::<cubic_micrometer>(value),
-        "nm³" => Volume::new::<cubic_nanometer>(value),
-        "pm³" => Volume::new::<cubic_picometer>(value),
-        "ac · ft" => Volume::new::<acre_foot>(value),
-        "bbl" => Volume::new::<barrel>(value),
-        "bu" => Volume::new::<bushel>(value),
-        "cords" => Volume::new::<cord>(value),
-        "ft³" => Volume::new::<cubic_foot>(value),
-        "in³" => Volume::new::<cubic_inch>(value),
-        "mi³" => Volume::new::<cubic_mile>(value),
-        "yd³" => Volume::new::<cubic_yard>(value),
-        "cup" => Volume::new::<cup>(value),
-        "fl oz" => Volume::new::<fluid_ounce>(value),
-        "fl oz (UK)" => Volume::new::<fluid_ounce_imperial>(value),
-        "gal (UK)" => Volume::new::<gallon_imperial>(value),
-        "gal" => Volume::new::<gallon>(value),
-        "gi (UK)" => Volume::new::<gill_imperial>(value),
-        "gi" => Volume::new::<gill>(value),
-        "TL" => Volume::new::<teraliter>(value),
-        "GL" => Volume::new::<gigaliter>(value),
-        "ML" => Volume::new::<megaliter>(value),
-        "kL" => Volume::new::<kiloliter>(value),
-        "hL" => Volume::new::<hectoliter>(value),
-        "daL" => Volume::new::<decaliter>(value),
-        "L" => Volume::new::<liter>(value),
-        "dL" => Volume::new::<deciliter>(value),
-        "cL" => Volume::new::<centiliter>(value),
-        "mL" => Volume::new::<milliliter>(value),
-        "µL" => Volume::new::<microliter>(value),
-        "nL" => Volume::new::<nanoliter>(value),
-        "pL" => Volume::new::<picoliter>(value),
-        "pk" => Volume::new::<peck>(value),
-        "dry pt" => Volume::new::<pint_dry>(value),
-        "liq pt" => Volume::new::<pint_liquid>(value),
-        "dry qt" => Volume::new::<quart_dry>(value),
-        "liq qt" => Volume::new::<quart_liquid>(value),
-        "tbsp" => Volume::new::<tablespoon>(value),
-        "tsp" => Volume::new::<teaspoon>(value),
-        "placeholder" => panic!("Unit not specified for ingredient mass"),
-        x => panic!("{x} not recognized as a supported mass unit abbreviation"),
-    }
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported volume unit, or `value` is too large or too precise
+/// to convert to it
+pub fn volume_unit_input_parser(value: Rational64, unit_string: &str) -> Result<Volume, UnitParseError> {
+    let unit = parse_unit(UnitKind::Volume, unit_string)?;
+    let base = unit.to_base(value).ok_or_else(|| UnitParseError::out_of_range(unit, value))?;
+    Ok(Volume::new::<cubic_meter>(base))
 }
 
 /// takes a `[uom::si::Volume]` value and unit string and returns the raw value in the
 /// specified unit for display or output to file.
-pub fn volume_unit_raw_output(value: Volume, unit_string: &str) -> Rational64 {
-    match unit_string {
-        "Tm³" => value.get::<cubic_terameter>(),
-        "Gm³" => value.get::<cubic_gigameter>(),
-        "Mm³" => value.get::<cubic_megameter>(),
-        "km³" => value.get::<cubic_kilometer>(),
-        "hm³" => value.get::<cubic_hectometer>(),
-        "dam³" => value.get::<cubic_decameter>(),
-        "m³" => value.get::<cubic_meter>(),
-        "dm³" => value.get::<cubic_decimeter>(),
-        "cm³" => value.get::<cubic_centimeter>(),
-        "mm³" => value.get::<cubic_millimeter>(),
-        "µm³" => value.get::<cubic_micrometer>(),
-        "nm³" => value.get::<cubic_nanometer>(),
-        "pm³" => value.get::<cubic_picometer>(),
-        "ac · ft" => value.get::<acre_foot>(),
-        "bbl" => value.get::<barrel>(),
-        "bu" => value.get::<bushel>(),
-        "cords" => value.get::<cord>(),
-        "ft³" => value.get::<cubic_foot>(),
-        "in³" => value.get::<cubic_inch>(),
-        "mi³" => value.get::<cubic_mile>(),
-        "yd³" => value.get::<cubic_yard>(),
-        "cup" => value.get::<cup>(),
-        "fl oz" => value.get::<fluid_ounce>(),
-        "fl oz (UK)" => value.get::<fluid_ounce_imperial>(),
-        "gal (UK)" => value.get::<gallon_imperial>(),
-        "gal" => value.get::<gallon>(),
-        "gi (UK)" => value.get::<gill_imperial>(),
-        "gi" => value.get::<gill>(),
-        "TL" => value.get::<teraliter>(),
-        "GL" => value.get::<gigaliter>(),
-        "ML" => value.get::<megaliter>(),
-        "kL" => value.get::<kiloliter>(),
-        "hL" => value.get::<hectoliter>(),
-        "daL" => value.get::<decaliter>(),
-        "L" => value.get::<liter>(),
-        "dL" => value.get::<deciliter>(),
-        "cL" => value.get::<centiliter>(),
-        "mL" => value.get::<milliliter>(),
-        "µL" => value.get::<microliter>(),
-        "nL" => value.get::<nanoliter>(),
-        "pL" => value.get::<picoliter>(),
-        "pk" => value.get::<peck>(),
-        "dry pt" => value.get::<pint_dry>(),
-        "liq pt" => value.get::<pint_liquid>(),
-        "dry qt" => value.get::<quart_dry>(),
-        "liq qt" => value.get::<quart_liquid>(),
-        "tbsp" => value.get::<tablespoon>(),
-        "tsp" => value.get::<teaspoon>(),
-        "placeholder" => panic!("Unit not specified for ingredient mass"),
-        x => panic!("{x} not recognized as a supported mass unit abbreviation"),
-    }
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported volume unit, or `value` is too large or too precise
+/// to convert to it
+pub fn volume_unit_raw_output(value: Volume, unit_string: &str) -> Result<Rational64, UnitParseError> {
+    let unit = parse_unit(UnitKind::Volume, unit_string)?;
+    unit.from_base(value.value)
+        .ok_or_else(|| UnitParseError::out_of_range(unit, value.value))
 }
 
 /// takes a `[uom::si::Volume]` value and unit string and returns a formatted string in the
 /// specified unit for display or output to file.
-pub fn volume_unit_format_output(value: Volume, unit_string: &str, style: DisplayStyle) -> String {
-    match unit_string {
-        "Tm³" => format!("{}", value.into_format_args(cubic_terameter, style)),
-        "Gm³" => format!("{}", value.into_format_args(cubic_gigameter, style)),
-        "Mm³" => format!("{}", value.into_format_args(cubic_megameter, style)),
-        "km³" => format!("{}", value.into_format_args(cubic_kilometer, style)),
-        "hm³" => format!("{}", value.into_format_args(cubic_hectometer, style)),
-        "dam³" => format!("{}", value.into_format_args(cubic_decameter, style)),
-        "m³" => format!("{}", value.into_format_args(cubic_meter, style)),
-        "dm³" => format!("{}", value.into_format_args(cubic_decimeter, style)),
-        "cm³" => format!("{}", value.into_format_args(cubic_centimeter, style)),
-        "mm³" => format!("{}", value.into_format_args(cubic_millimeter, style)),
-        "µm³" => format!("{}", value.into_format_args(cubic_micrometer, style)),
-        "nm³" => format!("{}", value.into_format_args(cubic_nanometer, style)),
-        "pm³" => format!("{}", value.into_format_args(cubic_picometer, style)),
-        "ac · ft" => format!("{}", value.into_format_args(acre_foot, style)),
-        "bbl" => format!("{}", value.into_format_args(barrel, style)),
-        "bu" => format!("{}", value.into_format_args(bushel, style)),
-        "cords" => format!("{}", value.into_format_args(cord, style)),
-        "ft³" => format!("{}", value.into_format_args(cubic_foot, style)),
-        "in³" => format!("{}", value.into_format_args(cubic_inch, style)),
-        "mi³" => format!("{}", value.into_format_args(cubic_mile, style)),
-        "yd³" => format!("{}", value.into_format_args(cubic_yard, style)),
-        "cup" => format!("{}", value.into_format_args(cup, style)),
-        "fl oz" => format!("{}", value.into_format_args(fluid_ounce, style)),
-        "fl oz (UK)" => format!("{}", value.into_format_args(fluid_ounce_imperial, style)),
-        "gal (UK)" => format!("{}", value.into_format_args(gallon_imperial, style)),
-        "gal" => format!("{}", value.into_format_args(gallon, style)),
-        "gi (UK)" => format!("{}", value.into_format_args(gill_imperial, style)),
-        "gi" => format!("{}", value.into_format_args(gill, style)),
-        "TL" => format!("{}", value.into_format_args(teraliter, style)),
-        "GL" => format!("{}", value.into_format_args(gigaliter, style)),
-        "ML" => format!("{}", value.into_format_args(megaliter, style)),
-        "kL" => format!("{}", value.into_format_args(kiloliter, style)),
-        "hL" => format!("{}", value.into_format_args(hectoliter, style)),
-        "daL" => format!("{}", value.into_format_args(decaliter, style)),
-        "L" => format!("{}", value.into_format_args(liter, style)),
-        "dL" => format!("{}", value.into_format_args(deciliter, style)),
-        "cL" => format!("{}", value.into_format_args(centiliter, style)),
-        "mL" => format!("{}", value.into_format_args(milliliter, style)),
-        "µL" => format!("{}", value.into_format_args(microliter, style)),
-        "nL" => format!("{}", value.into_format_args(nanoliter, style)),
-        "pL" => format!("{}", value.into_format_args(picoliter, style)),
-        "pk" => format!("{}", value.into_format_args(peck, style)),
-        "dry pt" => format!("{}", value.into_format_args(pint_dry, style)),
-        "liq pt" => format!("{}", value.into_format_args(pint_liquid, style)),
-        "dry qt" => format!("{}", value.into_format_args(quart_dry, style)),
-        "liq qt" => format!("{}", value.into_format_args(quart_liquid, style)),
-        "tbsp" => format!("{}", value.into_format_args(tablespoon, style)),
-        "tsp" => format!("{}", value.into_format_args(teaspoon, style)),
-        "placeholder" => panic!("Unit not specified for ingredient mass"),
-        x => panic!("{x} not recognized as a supported mass unit abbreviation"),
+///
+/// # Errors
+///
+/// Will error if `unit_string` is not a supported volume unit, or `value` is too large or too precise
+/// to convert to it
+pub fn volume_unit_format_output(value: Volume, unit_string: &str, style: DisplayStyle) -> Result<String, UnitParseError> {
+    let unit = parse_unit(UnitKind::Volume, unit_string)?;
+    let converted = unit
+        .from_base(value.value)
+        .ok_or_else(|| UnitParseError::out_of_range(unit, value.value))?;
+    Ok(unit.format(converted, style))
+}
+
+/// `MeasurementSystem` is a system of units that quantities can be displayed in
+#[non_exhaustive]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeasurementSystem {
+    /// grams, milliliters and degrees Celsius
+    #[default]
+    Metric,
+    /// ounces, pounds, teaspoons, cups and degrees Fahrenheit
+    UsCustomary,
+    /// ounces, pounds, Imperial teaspoons, cups and gallons, and degrees Celsius
+    Imperial,
+}
+
+impl MeasurementSystem {
+    /// `of_unit` returns the system the unit with abbreviation `unit` belongs to. Units that
+    /// aren't US customary or imperial are treated as metric
+    #[must_use]
+    pub fn of_unit(unit: &str) -> Self {
+        unit_registry::find_unit(unit).map_or(Self::Metric, |u| u.system)
+    }
+
+    /// `form_value` is the value used for this system in web forms and the configuration file
+    #[must_use]
+    pub fn form_value(self) -> &'static str {
+        match self {
+            Self::Metric => "metric",
+            Self::UsCustomary => "us_customary",
+            Self::Imperial => "imperial",
+        }
+    }
+
+    /// `from_form_value` is the inverse of [`MeasurementSystem::form_value`]
+    #[must_use]
+    pub fn from_form_value(value: &str) -> Option<Self> {
+        match value {
+            "metric" => Some(Self::Metric),
+            "us_customary" => Some(Self::UsCustomary),
+            "imperial" => Some(Self::Imperial),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MeasurementSystem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Metric => write!(f, "Metric"),
+            Self::UsCustomary => write!(f, "US Customary"),
+            Self::Imperial => write!(f, "Imperial"),
+        }
+    }
+}
+
+/// `TemperatureScale` is a scale temperatures can be displayed in
+#[non_exhaustive]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemperatureScale {
+    /// degrees Celsius
+    #[default]
+    Celsius,
+    /// degrees Fahrenheit
+    Fahrenheit,
+}
+
+impl TemperatureScale {
+    /// `of_system` returns the temperature scale used by `system`.
+    ///
+    /// Imperial recipes are British, which give oven temperatures in degrees Celsius.
+    #[must_use]
+    pub fn of_system(system: MeasurementSystem) -> Self {
+        match system {
+            MeasurementSystem::UsCustomary => Self::Fahrenheit,
+            MeasurementSystem::Metric | MeasurementSystem::Imperial => Self::Celsius,
+        }
+    }
+
+    /// `unit` returns the abbreviation of the temperature unit of the scale
+    #[must_use]
+    pub fn unit(self) -> &'static str {
+        match self {
+            Self::Celsius => "°C",
+            Self::Fahrenheit => "°F",
+        }
+    }
+
+    /// `form_value` is the value used for this scale in web forms and the configuration file
+    #[must_use]
+    pub fn form_value(self) -> &'static str {
+        match self {
+            Self::Celsius => "celsius",
+            Self::Fahrenheit => "fahrenheit",
+        }
+    }
+
+    /// `from_form_value` is the inverse of [`TemperatureScale::form_value`]
+    #[must_use]
+    pub fn from_form_value(value: &str) -> Option<Self> {
+        match value {
+            "celsius" => Some(Self::Celsius),
+            "fahrenheit" => Some(Self::Fahrenheit),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TemperatureScale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Celsius => write!(f, "Celsius"),
+            Self::Fahrenheit => write!(f, "Fahrenheit"),
+        }
+    }
+}
+
+/// `TimeFormat` is how lengths of time are written for display
+#[non_exhaustive]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeFormat {
+    /// days, hours, minutes and seconds, like `1 h 15 min`
+    #[default]
+    Units,
+    /// hours and minutes like a clock, like `1:15`
+    Clock,
+    /// whole minutes, like `75 min`
+    Minutes,
+}
+
+impl TimeFormat {
+    /// `form_value` is the value used for this format in web forms and the configuration file
+    #[must_use]
+    pub fn form_value(self) -> &'static str {
+        match self {
+            Self::Units => "units",
+            Self::Clock => "clock",
+            Self::Minutes => "minutes",
+        }
+    }
+
+    /// `from_form_value` is the inverse of [`TimeFormat::form_value`]
+    #[must_use]
+    pub fn from_form_value(value: &str) -> Option<Self> {
+        match value {
+            "units" => Some(Self::Units),
+            "clock" => Some(Self::Clock),
+            "minutes" => Some(Self::Minutes),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TimeFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Units => write!(f, "1 h 15 min"),
+            Self::Clock => write!(f, "1:15"),
+            Self::Minutes => write!(f, "75 min"),
+        }
+    }
+}
+
+/// `mass_display_unit` returns the abbreviation of the unit from `system` that `value` is
+/// easiest to read in, like `g` for 250 g rather than `kg`
+#[must_use]
+pub fn mass_display_unit(value: Mass, system: MeasurementSystem) -> &'static str {
+    let one = Rational64::from_integer(1);
+    match system {
+        MeasurementSystem::Metric if value < Mass::new::<gram>(one) => "mg",
+        MeasurementSystem::Metric if value < Mass::new::<kilogram>(one) => "g",
+        MeasurementSystem::Metric => "kg",
+        MeasurementSystem::UsCustomary | MeasurementSystem::Imperial if value < Mass::new::<pound>(one) => "oz",
+        MeasurementSystem::UsCustomary | MeasurementSystem::Imperial => "lb",
+    }
+}
+
+/// `volume_display_unit` returns the abbreviation of the unit from `system` that `value` is
+/// easiest to read in, like `tsp` for 2 tsp rather than a fraction of a cup
+#[must_use]
+pub fn volume_display_unit(value: Volume, system: MeasurementSystem) -> &'static str {
+    let one = Rational64::from_integer(1);
+    match system {
+        MeasurementSystem::Metric if value < Volume::new::<liter>(one) => "mL",
+        MeasurementSystem::Metric => "L",
+        MeasurementSystem::UsCustomary if value < Volume::new::<tablespoon>(one) => "tsp",
+        MeasurementSystem::UsCustomary if value < Volume::new::<cup>(Rational64::new(1, 4)) => "tbsp",
+        MeasurementSystem::UsCustomary if value < Volume::new::<gallon>(one) => "cup",
+        MeasurementSystem::UsCustomary => "gal",
+        // Imperial teaspoons, tablespoons and cups are a little larger than the US ones
+        MeasurementSystem::Imperial if value < Volume::new::<fluid_ounce_imperial>(Rational64::new(5, 8)) => "tsp (UK)",
+        MeasurementSystem::Imperial if value < Volume::new::<fluid_ounce_imperial>(Rational64::new(5, 2)) => "tbsp (UK)",
+        MeasurementSystem::Imperial if value < Volume::new::<gallon_imperial>(one) => "cup (UK)",
+        MeasurementSystem::Imperial => "gal (UK)",
+    }
+}
+
+/// `display_value` returns the value of the quantity `base_value` of `kind` in the unit with
+/// the abbreviation `unit`, along with the abbreviation, or `None` if `unit` isn't in the
+/// [`unit_registry`] or the value can't be converted to it
+fn display_value(kind: UnitKind, base_value: Rational64, unit: &str) -> Option<(Rational64, &'static str)> {
+    let unit = unit_registry::unit(kind, unit)?;
+    Some((unit.from_base(base_value)?, unit.abbreviation))
+}
+
+/// `mass_display` returns `value` formatted for display in the easiest to read unit from
+/// `system`, with fractions written in `style`. Ex: `250 g`, `1 ½ lb`
+#[must_use]
+pub fn mass_display(value: Mass, system: MeasurementSystem, style: FractionStyle) -> String {
+    // quantities that can't be shown in the unit are left in the SI base unit
+    let (number, unit) = display_value(UnitKind::Mass, value.value, mass_display_unit(value, system))
+        .unwrap_or((value.value, UnitKind::Mass.base_unit()));
+    format!("{} {unit}", display_number(number, system, style))
+}
+
+/// `volume_display` returns `value` formatted for display in the easiest to read unit from
+/// `system`, with fractions written in `style`. Ex: `⅓ tsp`, `500 mL`
+#[must_use]
+pub fn volume_display(value: Volume, system: MeasurementSystem, style: FractionStyle) -> String {
+    // quantities that can't be shown in the unit are left in the SI base unit
+    let (number, unit) = display_value(UnitKind::Volume, value.value, volume_display_unit(value, system))
+        .unwrap_or((value.value, UnitKind::Volume.base_unit()));
+    format!("{} {unit}", display_number(number, system, style))
+}
+
+/// `temperature_display` returns `value` formatted for display in `scale`, to the nearest degree.
+/// Ex: `180 °C`, `350 °F`
+#[must_use]
+pub fn temperature_display(value: ThermodynamicTemperature, scale: TemperatureScale) -> String {
+    // quantities that can't be shown in the unit are left in the SI base unit
+    let (number, unit) = display_value(UnitKind::Temperature, value.value, scale.unit())
+        .unwrap_or((value.value, UnitKind::Temperature.base_unit()));
+    format!("{} {unit}", number.round())
+}
+
+/// `time_display` returns `value` formatted for display in `format`.
+///
+/// With [`TimeFormat::Units`], seconds are left off times of an hour or more. The other formats
+/// round to the nearest minute, except for times under a minute.
+/// Ex: `1 h 15 min`, `1:15`, `75 min`, `45 s`
+#[must_use]
+pub fn time_display(value: Time, format: TimeFormat) -> String {
+    let mut seconds = value.get::<second>().round().to_integer();
+    if format != TimeFormat::Units && seconds > 0 && seconds < 60 {
+        return format!("{seconds} s");
+    }
+    if seconds >= 3600 || format != TimeFormat::Units {
+        seconds = Rational64::new(seconds, 60).round().to_integer().saturating_mul(60);
+    }
+    match format {
+        TimeFormat::Clock => return format!("{}:{:02}", seconds / 3600, seconds % 3600 / 60),
+        TimeFormat::Minutes => return format!("{} min", seconds / 60),
+        TimeFormat::Units => {}
+    }
+    let mut parts = Vec::new();
+    for (unit, length) in [("d", 86_400), ("h", 3600), ("min", 60), ("s", 1)] {
+        let count = seconds / length;
+        if count != 0 {
+            parts.push(format!("{count} {unit}"));
+        }
+        seconds %= length;
+    }
+    if parts.is_empty() {
+        "0 min".to_owned()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// `FractionStyle` is how amounts that aren't whole numbers are written for display
+#[non_exhaustive]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FractionStyle {
+    /// kitchen fractions written with unicode fraction characters, like `1 ½`
+    #[default]
+    Unicode,
+    /// kitchen fractions written with only ASCII characters, like `1 1/2`, for terminals and
+    /// fonts without the unicode fraction characters
+    Ascii,
+    /// decimals, like `1.5`
+    Decimal,
+}
+
+impl FractionStyle {
+    /// `from_locale` returns [`FractionStyle::Unicode`] if the locale set in the environment uses
+    /// UTF-8, or none is set, and [`FractionStyle::Ascii`] otherwise
+    #[must_use]
+    pub fn from_locale() -> Self {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty());
+        match locale {
+            Some(locale) if !locale.to_lowercase().replace('-', "").contains("utf8") => Self::Ascii,
+            _ => Self::Unicode,
+        }
+    }
+
+    /// `supported_by_locale` returns [`FractionStyle::Ascii`] in place of
+    /// [`FractionStyle::Unicode`] if the locale set in the environment doesn't use UTF-8, and
+    /// otherwise the style unchanged
+    #[must_use]
+    pub fn supported_by_locale(self) -> Self {
+        match self {
+            Self::Unicode => Self::from_locale(),
+            style => style,
+        }
+    }
+
+    /// `form_value` is the value used for this style in web forms and the configuration file
+    #[must_use]
+    pub fn form_value(self) -> &'static str {
+        match self {
+            Self::Unicode => "unicode",
+            Self::Ascii => "ascii",
+            Self::Decimal => "decimal",
+        }
+    }
+
+    /// `from_form_value` is the inverse of [`FractionStyle::form_value`]
+    #[must_use]
+    pub fn from_form_value(value: &str) -> Option<Self> {
+        match value {
+            "unicode" => Some(Self::Unicode),
+            "ascii" => Some(Self::Ascii),
+            "decimal" => Some(Self::Decimal),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FractionStyle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unicode => write!(f, "Fractions (1 ½)"),
+            Self::Ascii => write!(f, "Fractions (1 1/2)"),
+            Self::Decimal => write!(f, "Decimals (1.5)"),
+        }
+    }
+}
+
+/// denominators of the fractions used in recipes, in order of preference
+const KITCHEN_DENOMINATORS: [i64; 4] = [2, 3, 4, 8];
+
+/// `approximate_fraction` formats `value` as a kitchen fraction in `style`, like `1 ½` or `⅓`.
+///
+/// Values within 5%, and at most 1/16, of a fraction with a denominator of 2, 3, 4 or 8 are
+/// rounded to it, so 0.49 becomes `½`. Other values, and all values with [`FractionStyle::Decimal`], are written as
+/// decimals with three significant figures, like `0.1`.
+#[must_use]
+pub fn approximate_fraction(value: Rational64, style: FractionStyle) -> String {
+    if style == FractionStyle::Decimal {
+        return readable_number(value);
+    }
+    let sign = if value < Rational64::zero() { "-" } else { "" };
+    let value = if value < Rational64::zero() { -value } else { value };
+    let whole = value.trunc();
+    let tolerance = value
+        .checked_div(&Rational64::from_integer(20))
+        .unwrap_or_default()
+        .min(Rational64::new(1, 16));
+    for denominator in KITCHEN_DENOMINATORS {
+        let approximation = value
+            .fract()
+            .checked_mul(&Rational64::from_integer(denominator))
+            .map(|n| Rational64::new(n.round().to_integer(), denominator))
+            .and_then(|fraction| whole.checked_add(&fraction));
+        if let Some(approximation) = approximation
+            && approximation
+                .checked_sub(&value)
+                .is_some_and(|difference| difference <= tolerance && -difference <= tolerance)
+        {
+            let whole = approximation.to_integer();
+            let fraction = approximation.fract();
+            let fraction_text = match (style, fraction_character(fraction)) {
+                (FractionStyle::Unicode, Some(character)) => character.to_string(),
+                _ => fraction.to_string(),
+            };
+            return match (whole, fraction.is_zero()) {
+                (_, true) => format!("{sign}{whole}"),
+                (0, false) => format!("{sign}{fraction_text}"),
+                _ => format!("{sign}{whole} {fraction_text}"),
+            };
+        }
+    }
+    readable_number(if sign.is_empty() { value } else { -value })
+}
+
+/// `fraction_character` returns the unicode character for a kitchen fraction, if there is one
+fn fraction_character(fraction: Rational64) -> Option<char> {
+    match (fraction.numer(), fraction.denom()) {
+        (1, 2) => Some('½'),
+        (1, 3) => Some('⅓'),
+        (2, 3) => Some('⅔'),
+        (1, 4) => Some('¼'),
+        (3, 4) => Some('¾'),
+        (1, 8) => Some('⅛'),
+        (3, 8) => Some('⅜'),
+        (5, 8) => Some('⅝'),
+        (7, 8) => Some('⅞'),
+        _ => None,
+    }
+}
+
+/// `display_number` formats an amount in a unit from `system` for display. Metric amounts are
+/// always written as decimals, as kitchen fractions of metric units aren't commonly used
+fn display_number(value: Rational64, system: MeasurementSystem, style: FractionStyle) -> String {
+    match system {
+        MeasurementSystem::Metric => readable_number(value),
+        MeasurementSystem::UsCustomary | MeasurementSystem::Imperial => approximate_fraction(value, style),
+    }
+}
+
+/// `readable_number` formats `value` as a decimal rounded to three significant figures, or to a
+/// whole number if it has more than three digits before the decimal point, without trailing
+/// zeros. Ex: `454`, `14.8`, `0.333`, `0.0025`
+#[must_use]
+pub fn readable_number(value: Rational64) -> String {
+    let value = value.to_f64().unwrap_or_default();
+    let mut places = 2_usize;
+    let mut magnitude = value.abs();
+    if magnitude >= 1.0 {
+        while magnitude >= 10.0 && places > 0 {
+            magnitude /= 10.0;
+            places = places.saturating_sub(1);
+        }
+    } else if magnitude > 0.0 {
+        // values too small to show in 17 decimal places are shown as 0
+        while magnitude < 1.0 && places < 17 {
+            magnitude *= 10.0;
+            places = places.saturating_add(1);
+        }
+    }
+    let formatted = format!("{value:.places$}");
+    if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.').to_owned()
+    } else {
+        formatted
+    }
+}
+
+/// count units that the usual rules get wrong in one direction or the other, like `cookies`
+/// which would become `cooky`, as `(singular, plural)`
+const IRREGULAR_COUNT_UNITS: [(&str, &str); 8] = [
+    ("brownie", "brownies"),
+    ("cookie", "cookies"),
+    ("half", "halves"),
+    ("knife", "knives"),
+    ("leaf", "leaves"),
+    ("loaf", "loaves"),
+    ("sheaf", "sheaves"),
+    ("shoe", "shoes"),
+];
+
+/// count units ending in `o` whose plural adds `es`, like `tomatoes`. Others, like `avocados`,
+/// just add `s`
+const OES_COUNT_UNITS: [&str; 3] = ["mango", "potato", "tomato"];
+
+/// `count_unit_singular` returns the singular of the count unit written as `unit`, like `can`
+/// for `cans` or `bunch` for `bunches`, so count units are stored and compared the same way
+/// however they were written
+#[must_use]
+pub fn count_unit_singular(unit: &str) -> String {
+    let unit = unit.split_whitespace().collect::<Vec<_>>().join(" ");
+    let lowercase = unit.to_lowercase();
+    // how many letters to remove from the end of the unit, and what to replace them with
+    let (remove, replacement) =
+        if let Some((singular, plural)) = IRREGULAR_COUNT_UNITS.iter().find(|(_, plural)| lowercase.ends_with(plural)) {
+            (plural.len(), *singular)
+        } else if lowercase.ends_with("ies") && lowercase.len() > 4 {
+            (3, "y")
+        } else if ["ches", "shes", "sses", "xes", "zes"].iter().any(|e| lowercase.ends_with(e))
+            || OES_COUNT_UNITS.iter().any(|o| lowercase.ends_with(&format!("{o}es")))
+        {
+            (2, "")
+        } else if lowercase.ends_with('s') && !lowercase.ends_with("ss") {
+            (1, "")
+        } else {
+            (0, "")
+        };
+    unit.get(..unit.len().saturating_sub(remove))
+        .map_or_else(|| unit.clone(), |stem| format!("{stem}{replacement}"))
+}
+
+/// `count_unit_plural` returns the plural of the count unit `unit`, which is in the singular.
+/// Ex: `cloves`, `bunches`, `tomatoes`
+#[must_use]
+pub fn count_unit_plural(unit: &str) -> String {
+    let lowercase = unit.to_lowercase();
+    // how many letters to remove from the end of the unit, and what to replace them with
+    let (remove, replacement) = if let Some((singular, plural)) = IRREGULAR_COUNT_UNITS
+        .iter()
+        .find(|(singular, _)| lowercase.ends_with(singular))
+    {
+        (singular.len(), *plural)
+    } else if ["ch", "sh", "s", "x", "z"].iter().any(|e| lowercase.ends_with(e))
+        || OES_COUNT_UNITS.iter().any(|o| lowercase.ends_with(o))
+    {
+        (0, "es")
+    } else if lowercase.ends_with('y') && !["ay", "ey", "oy", "uy"].iter().any(|e| lowercase.ends_with(e)) {
+        (1, "ies")
+    } else {
+        (0, "s")
+    };
+    unit.get(..unit.len().saturating_sub(remove))
+        .map_or_else(|| unit.to_owned(), |stem| format!("{stem}{replacement}"))
+}
+
+/// `count_unit_name` returns the count unit `unit` as it is written after `value`: singular for
+/// amounts of one or less, like `½ can`, and plural otherwise, like `2 cans`
+#[must_use]
+pub fn count_unit_name(unit: &str, value: Rational64) -> String {
+    if value > Rational64::zero() && value <= Rational64::from_integer(1) {
+        unit.to_owned()
+    } else {
+        count_unit_plural(unit)
+    }
+}
+
+/// `same_count_unit` returns true if `lhs` and `rhs` are the same count unit, ignoring case.
+/// `None` is a count without a unit, like 3 eggs
+#[must_use]
+pub fn same_count_unit(lhs: Option<&str>, rhs: Option<&str>) -> bool {
+    match (lhs, rhs) {
+        (Some(l), Some(r)) => l.to_lowercase() == r.to_lowercase(),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// `mass_density` returns the density of an ingredient where `mass` of it takes up `volume`.
+///
+/// Returns `None` if `volume` is zero or the division overflows.
+#[must_use]
+pub fn mass_density(mass: Mass, volume: Volume) -> Option<MassDensity> {
+    // uom stores rational quantities in their base SI unit, so dividing the raw values gives
+    // kg/m³ exactly
+    mass.value
+        .checked_div(&volume.value)
+        .map(MassDensity::new::<kilogram_per_cubic_meter>)
+}
+
+/// `volume_to_mass` converts a volume of an ingredient to a mass using its density.
+///
+/// Returns `None` if the multiplication overflows.
+#[must_use]
+pub fn volume_to_mass(volume: Volume, density: MassDensity) -> Option<Mass> {
+    volume.value.checked_mul(&density.value).map(Mass::new::<kilogram>)
+}
+
+/// `mass_to_volume` converts a mass of an ingredient to a volume using its density.
+///
+/// Returns `None` if the density is zero or the division overflows.
+#[must_use]
+pub fn mass_to_volume(mass: Mass, density: MassDensity) -> Option<Volume> {
+    mass.value.checked_div(&density.value).map(Volume::new::<cubic_meter>)
+}
+
+/// `unit_type_convert` converts `value` to the same kind of quantity as `target`, keeping the unit
+/// of `target` for display and output.
+///
+/// Masses and volumes are converted using `density`. Counts are converted using `unit_mass`, the
+/// typical mass of one of the ingredient. Counts and volumes are converted through mass, so need
+/// both. Counts of a count unit, like 2 cans, are only converted to counts of the same unit; use
+/// [`CatalogEntry::convert`](super::ingredient_catalog::CatalogEntry::convert) to convert them
+/// to a mass or volume.
+///
+/// Returns `None` if the needed density or unit mass is missing, or the conversion overflows.
+#[must_use]
+pub fn unit_type_convert(
+    value: &UnitType,
+    target: &UnitType,
+    density: Option<MassDensity>,
+    unit_mass: Option<Mass>,
+) -> Option<UnitType> {
+    // values that are already the right kind don't need to go through mass
+    match (value, target) {
+        (UnitType::Quantity { value: q, unit: lu }, UnitType::Quantity { unit: ru, .. })
+            if same_count_unit(lu.as_deref(), ru.as_deref()) =>
+        {
+            return Some(UnitType::Quantity {
+                value: *q,
+                unit: ru.clone(),
+            });
+        }
+        (UnitType::Mass { value: m, .. }, UnitType::Mass { unit, .. }) => {
+            return Some(UnitType::Mass {
+                value: *m,
+                unit: unit.clone(),
+            });
+        }
+        (UnitType::Volume { value: v, .. }, UnitType::Volume { unit, .. }) => {
+            return Some(UnitType::Volume {
+                value: *v,
+                unit: unit.clone(),
+            });
+        }
+        _ => {}
+    }
+    let mass = match value {
+        UnitType::Quantity { value: q, unit: None } => q.checked_mul(&unit_mass?.value).map(Mass::new::<kilogram>)?,
+        UnitType::Quantity { unit: Some(_), .. } => return None,
+        UnitType::Mass { value: m, .. } => *m,
+        UnitType::Volume { value: v, .. } => volume_to_mass(*v, density?)?,
+    };
+    match target {
+        UnitType::Quantity { unit: None, .. } => mass
+            .value
+            .checked_div(&unit_mass?.value)
+            .map(|q| UnitType::Quantity { value: q, unit: None }),
+        UnitType::Quantity { unit: Some(_), .. } => None,
+        UnitType::Mass { unit, .. } => Some(UnitType::Mass {
+            value: mass,
+            unit: unit.clone(),
+        }),
+        UnitType::Volume { unit, .. } => mass_to_volume(mass, density?).map(|v| UnitType::Volume {
+            value: v,
+            unit: unit.clone(),
+        }),
     }
 }
 
 /// `print_units` prints all unit names and abbreviations that are usable
 /// in configuration and recipe files.
 pub fn print_units() {
-    // Time units
-    println!("Only abbreviations are allowed in config files and recipe files for now");
-    println!("Mass Units");
-
-    println!("{}: {}", terasecond::singular(), terasecond::abbreviation());
-    println!("{}: {}", gigasecond::singular(), gigasecond::abbreviation());
-    println!("{}: {}", megasecond::singular(), megasecond::abbreviation());
-    println!("{}: {}", kilosecond::singular(), kilosecond::abbreviation());
-    println!("{}: {}", hectosecond::singular(), hectosecond::abbreviation());
-    println!("{}: {}", decasecond::singular(), decasecond::abbreviation());
-    println!("{}: {}", second::singular(), second::abbreviation());
-    println!("{}: {}", decisecond::singular(), decisecond::abbreviation());
-    println!("{}: {}", centisecond::singular(), centisecond::abbreviation());
-    println!("{}: {}", millisecond::singular(), millisecond::abbreviation());
-    println!("{}: {}", microsecond::singular(), microsecond::abbreviation());
-    println!("{}: {}", nanosecond::singular(), nanosecond::abbreviation());
-    println!("{}: {}", picosecond::singular(), picosecond::abbreviation());
-    println!("{}: {}", day::singular(), day::abbreviation());
-    println!("{}: {}", hour::singular(), hour::abbreviation());
-    println!("{}: {}", minute::singular(), minute::abbreviation());
-    println!("{}: {}", year::singular(), year::abbreviation());
-
-    // Temp units
-    println!("Only abbreviations are allowed in config files and recipe files for now");
-    println!("Temperature Interval Units");
-
-    println!("{}: {}", terakelvin::singular(), terakelvin::abbreviation());
-    println!("{}: {}", gigakelvin::singular(), gigakelvin::abbreviation());
-    println!("{}: {}", megakelvin::singular(), megakelvin::abbreviation());
-    println!("{}: {}", kilokelvin::singular(), kilokelvin::abbreviation());
-    println!("{}: {}", hectokelvin::singular(), hectokelvin::abbreviation());
-    println!("{}: {}", decakelvin::singular(), decakelvin::abbreviation());
-    println!("{}: {}", kelvin::singular(), kelvin::abbreviation());
-    println!("{}: {}", decikelvin::singular(), decikelvin::abbreviation());
-    println!("{}: {}", centikelvin::singular(), centikelvin::abbreviation());
-    println!("{}: {}", millikelvin::singular(), millikelvin::abbreviation());
-    println!("{}: {}", microkelvin::singular(), microkelvin::abbreviation());
-    println!("{}: {}", nanokelvin::singular(), nanokelvin::abbreviation());
-    println!("{}: {}", picokelvin::singular(), picokelvin::abbreviation());
-    println!("{}: {}", degree_celsius::singular(), degree_celsius::abbreviation());
-    println!("{}: {}", degree_fahrenheit::singular(), degree_fahrenheit::abbreviation());
-    println!("{}: {}", degree_rankine::singular(), degree_rankine::abbreviation());
-
-    // Mass units
-    println!("Only abbreviations are allowed in config files and recipe files for now");
-    println!("Mass Units");
-
-    println!("{}: {}", teragram::singular(), teragram::abbreviation());
-    println!("{}: {}", gigagram::singular(), gigagram::abbreviation());
-    println!("{}: {}", megagram::singular(), megagram::abbreviation());
-    println!("{}: {}", kilogram::singular(), kilogram::abbreviation());
-    println!("{}: {}", hectogram::singular(), hectogram::abbreviation());
-    println!("{}: {}", decagram::singular(), decagram::abbreviation());
-    println!("{}: {}", gram::singular(), gram::abbreviation());
-    println!("{}: {}", decigram::singular(), decigram::abbreviation());
-    println!("{}: {}", centigram::singular(), centigram::abbreviation());
-    println!("{}: {}", milligram::singular(), milligram::abbreviation());
-    println!("{}: {}", microgram::singular(), microgram::abbreviation());
-    println!("{}: {}", nanogram::singular(), nanogram::abbreviation());
-    println!("{}: {}", picogram::singular(), picogram::abbreviation());
-    println!("{}: {}", ounce::singular(), ounce::abbreviation());
-    println!("{}: {}", pound::singular(), pound::abbreviation());
-
-    // Volume Units
-    println!("Only abbreviations are allowed in config files and recipe files for now");
-    println!("Volume Units");
-
-    println!("{}: {}", cubic_terameter::singular(), cubic_terameter::abbreviation());
-    println!("{}: {}", cubic_gigameter::singular(), cubic_gigameter::abbreviation());
-    println!("{}: {}", cubic_megameter::singular(), cubic_megameter::abbreviation());
-    println!("{}: {}", cubic_kilometer::singular(), cubic_kilometer::abbreviation());
-    println!("{}: {}", cubic_hectometer::singular(), cubic_hectometer::abbreviation());
-    println!("{}: {}", cubic_decameter::singular(), cubic_decameter::abbreviation());
-    println!("{}: {}", cubic_meter::singular(), cubic_meter::abbreviation());
-    println!("{}: {}", cubic_decimeter::singular(), cubic_decimeter::abbreviation());
-    println!("{}: {}", cubic_centimeter::singular(), cubic_centimeter::abbreviation());
-    println!("{}: {}", cubic_millimeter::singular(), cubic_millimeter::abbreviation());
-    println!("{}: {}", cubic_micrometer::singular(), cubic_micrometer::abbreviation());
-    println!("{}: {}", cubic_nanometer::singular(), cubic_nanometer::abbreviation());
-    println!("{}: {}", cubic_picometer::singular(), cubic_picometer::abbreviation());
-    println!("{}: {}", acre_foot::singular(), acre_foot::abbreviation());
-    println!("{}: {}", barrel::singular(), barrel::abbreviation());
-    println!("{}: {}", bushel::singular(), bushel::abbreviation());
-    println!("{}: {}", cord::singular(), cord::abbreviation());
-    println!("{}: {}", cubic_foot::singular(), cubic_foot::abbreviation());
-    println!("{}: {}", cubic_inch::singular(), cubic_inch::abbreviation());
-    println!("{}: {}", cubic_mile::singular(), cubic_mile::abbreviation());
-    println!("{}: {}", cubic_yard::singular(), cubic_yard::abbreviation());
-    println!("{}: {}", cup::singular(), cup::abbreviation());
-    println!("{}: {}", fluid_ounce::singular(), fluid_ounce::abbreviation());
-    println!(
-        "{}: {}",
-        fluid_ounce_imperial::singular(),
-        fluid_ounce_imperial::abbreviation()
-    );
-    println!("{}: {}", gallon_imperial::singular(), gallon_imperial::abbreviation());
-    println!("{}: {}", gallon::singular(), gallon::abbreviation());
-    println!("{}: {}", gill_imperial::singular(), gill_imperial::abbreviation());
-    println!("{}: {}", gill::singular(), gill::abbreviation());
-    println!("{}: {}", teraliter::singular(), teraliter::abbreviation());
-    println!("{}: {}", gigaliter::singular(), gigaliter::abbreviation());
-    println!("{}: {}", megaliter::singular(), megaliter::abbreviation());
-    println!("{}: {}", kiloliter::singular(), kiloliter::abbreviation());
-    println!("{}: {}", hectoliter::singular(), hectoliter::abbreviation());
-    println!("{}: {}", decaliter::singular(), decaliter::abbreviation());
-    println!("{}: {}", liter::singular(), liter::abbreviation());
-    println!("{}: {}", deciliter::singular(), deciliter::abbreviation());
-    println!("{}: {}", centiliter::singular(), centiliter::abbreviation());
-    println!("{}: {}", milliliter::singular(), milliliter::abbreviation());
-    println!("{}: {}", microliter::singular(), microliter::abbreviation());
-    println!("{}: {}", nanoliter::singular(), nanoliter::abbreviation());
-    println!("{}: {}", picoliter::singular(), picoliter::abbreviation());
-    println!("{}: {}", peck::singular(), peck::abbreviation());
-    println!("{}: {}", pint_dry::singular(), pint_dry::abbreviation());
-    println!("{}: {}", pint_liquid::singular(), pint_liquid::abbreviation());
-    println!("{}: {}", quart_dry::singular(), quart_dry::abbreviation());
-    println!("{}: {}", quart_liquid::singular(), quart_liquid::abbreviation());
-    println!("{}: {}", tablespoon::singular(), tablespoon::abbreviation());
-    println!("{}: {}", teaspoon::singular(), teaspoon::abbreviation());
-
-    println!("Only abbreviations are allowed in config files and recipe files for now");
+    println!("Units can be written in recipe and config files as abbreviations, or as singular or plural names.");
+    println!("Case, periods and extra spaces are ignored when a unit doesn't match exactly, so Tbsp, TSP. and");
+    println!("Cups all work. Shorthands that could mean more than one unit are not accepted.");
+
+    for kind in UnitKind::ALL {
+        println!();
+        println!("{kind} Units");
+        for unit in unit_registry::units_of(kind) {
+            println!("{}: {}", unit.singular, unit.abbreviation);
+        }
+        print_aliases(kind);
+    }
+}
+
+/// `print_aliases` prints the kitchen shorthands for one kind of unit, and what they mean
+fn print_aliases(kind: UnitKind) {
+    println!("Shorthands:");
+    let aliases: Vec<(&str, &str)> = unit_registry::units_of(kind)
+        .flat_map(|u| u.aliases.iter().map(move |alias| (*alias, u.abbreviation)))
+        .collect();
+    let mut printed: Vec<&str> = Vec::new();
+    for (alias, _) in &aliases {
+        if printed.contains(alias) {
+            continue;
+        }
+        printed.push(alias);
+        let meanings: Vec<&str> = aliases.iter().filter(|(a, _)| a == alias).map(|(_, m)| *m).collect();
+        if let [meaning] = meanings.as_slice() {
+            println!("{alias}: {meaning}");
+        } else {
+            println!("{alias}: not accepted, could be {}", meanings.join(" or "));
+        }
+    }
+}
+
+/// `parse_rational` parses user input such as `2`, `3/2`, `1 1/2` or `1.25` into an exact
+/// rational number.
+///
+/// Returns `None` if the input is not a number in one of those forms, or does not fit in a
+/// `[num_rational::Rational64]`.
+#[must_use]
+pub fn parse_rational(input: &str) -> Option<Rational64> {
+    let input = input.trim();
+    // mixed number like `1 1/2`. Both parts are unsigned, so `1 -1/2` isn't accepted
+    if let Some((whole, fraction)) = input.split_once(' ') {
+        let unsigned = |part: &str, symbols: &[char]| part.chars().all(|c| c.is_ascii_digit() || symbols.contains(&c));
+        if !unsigned(whole, &[]) || !fraction.contains('/') || !unsigned(fraction, &['/']) {
+            return None;
+        }
+        let whole = parse_rational(whole)?;
+        let fraction = parse_rational(fraction)?;
+        if fraction.is_integer() {
+            return None;
+        }
+        return whole.checked_add(&fraction);
+    }
+    if let Some((numerator, denominator)) = input.split_once('/') {
+        let numerator: i64 = numerator.trim().parse().ok()?;
+        let denominator: i64 = denominator.trim().parse().ok()?;
+        // only the numerator can be negative
+        if denominator <= 0 {
+            return None;
+        }
+        return Some(Rational64::new(numerator, denominator));
+    }
+    if let Some((whole, decimal)) = input.split_once('.') {
+        if decimal.is_empty() || !decimal.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let denominator = 10_i64.checked_pow(u32::try_from(decimal.len()).ok()?)?;
+        let negative = whole.starts_with('-');
+        let whole: i64 = match whole {
+            "" | "-" => 0,
+            whole => whole.parse().ok()?,
+        };
+        let decimal: i64 = decimal.parse().ok()?;
+        let numerator = whole.checked_abs()?.checked_mul(denominator)?.checked_add(decimal)?;
+        let value = Rational64::new(numerator, denominator);
+        return Some(if negative { -value } else { value });
+    }
+    input.parse::<i64>().ok().map(Rational64::from_integer)
+}
+
+#[cfg(test)]
+mod tests {
+    use num_rational::Rational64;
+
+    use super::{
+        UnitParseError, count_unit_plural, count_unit_singular, mass_unit_input_parser, parse_rational, volume_unit_input_parser,
+    };
+
+    #[test]
+    fn count_unit_singular_strips_plural_endings() {
+        for (plural, singular) in [
+            ("cans", "can"),
+            ("cloves", "clove"),
+            ("slices", "slice"),
+            ("tomatoes", "tomato"),
+            ("potatoes", "potato"),
+            ("bunches", "bunch"),
+            ("dashes", "dash"),
+            ("boxes", "box"),
+            ("glasses", "glass"),
+            ("patties", "patty"),
+            ("halves", "half"),
+            ("leaves", "leaf"),
+            ("pies", "pie"),
+            ("cookies", "cookie"),
+            ("brownies", "brownie"),
+            ("shoes", "shoe"),
+            ("sheaves", "sheaf"),
+            ("avocados", "avocado"),
+            ("mangoes", "mango"),
+        ] {
+            assert_eq!(count_unit_singular(plural), singular);
+        }
+    }
+
+    #[test]
+    fn count_unit_singular_keeps_singulars() {
+        for singular in [
+            "can", "slice", "tomato", "bunch", "glass", "patty", "half", "cookie", "shoe", "sheaf",
+        ] {
+            assert_eq!(count_unit_singular(singular), singular);
+        }
+    }
+
+    #[test]
+    fn count_unit_plural_round_trips() {
+        for singular in [
+            "can", "clove", "slice", "tomato", "bunch", "box", "glass", "patty", "leaf", "cookie", "shoe", "sheaf", "avocado",
+        ] {
+            assert_eq!(count_unit_singular(&count_unit_plural(singular)), singular);
+        }
+    }
+
+    #[test]
+    fn count_unit_plural_adds_plural_endings() {
+        for (singular, plural) in [
+            ("can", "cans"),
+            ("tomato", "tomatoes"),
+            ("avocado", "avocados"),
+            ("bunch", "bunches"),
+            ("patty", "patties"),
+            ("cookie", "cookies"),
+            ("sheaf", "sheaves"),
+            ("shoe", "shoes"),
+        ] {
+            assert_eq!(count_unit_plural(singular), plural);
+        }
+    }
+
+    #[test]
+    fn input_parsers_reject_values_too_large_to_convert() {
+        let huge = Rational64::from_integer(i64::MAX / 2);
+        assert!(matches!(
+            mass_unit_input_parser(huge, "lb"),
+            Err(UnitParseError::OutOfRange {
+                kind: "mass",
+                unit: "lb",
+                ..
+            })
+        ));
+        assert!(matches!(
+            volume_unit_input_parser(huge, "tbsp"),
+            Err(UnitParseError::OutOfRange {
+                kind: "volume",
+                unit: "tbsp",
+                ..
+            })
+        ));
+        assert!(mass_unit_input_parser(Rational64::from_integer(1_000_000), "lb").is_ok());
+    }
+
+    #[test]
+    fn parse_rational_accepts_numbers() {
+        for (input, value) in [
+            ("2", Rational64::from_integer(2)),
+            ("3/2", Rational64::new(3, 2)),
+            ("-1/2", Rational64::new(-1, 2)),
+            ("1 1/2", Rational64::new(3, 2)),
+            (" 2 3/4 ", Rational64::new(11, 4)),
+            ("1.25", Rational64::new(5, 4)),
+            ("-0.5", Rational64::new(-1, 2)),
+            (".5", Rational64::new(1, 2)),
+        ] {
+            assert_eq!(parse_rational(input), Some(value), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_rational_rejects_malformed_numbers() {
+        for input in [
+            "", "abc", "1 -1/2", "1 +1/2", "-1 1/2", "1 1.5", "1 3", "1 4/2", "1/2 1/2", "3/0", "3/-2", "1.", "1.-5",
+        ] {
+            assert_eq!(parse_rational(input), None, "{input}");
+        }
+    }
 }
@@ -0,0 +1,460 @@
+use std::{fmt, sync::LazyLock};
+
+use num_rational::Rational64;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Zero};
+use serde::Serialize;
+use uom::{
+    fmt::DisplayStyle,
+    si::{
+        Unit,
+        mass::{
+            centigram, decagram, decigram, gigagram, gram, hectogram, kilogram, megagram, microgram, milligram, nanogram, ounce,
+            picogram, pound, teragram,
+        },
+        thermodynamic_temperature::{
+            centikelvin, decakelvin, decikelvin, degree_celsius, degree_fahrenheit, degree_rankine, gigakelvin, hectokelvin,
+            kelvin, kilokelvin, megakelvin, microkelvin, millikelvin, nanokelvin, picokelvin, terakelvin,
+        },
+        time::{
+            centisecond, day, decasecond, decisecond, gigasecond, hectosecond, hour, kilosecond, megasecond, microsecond,
+            millisecond, minute, nanosecond, picosecond, second, terasecond, year,
+        },
+        volume::{
+            acre_foot, barrel, bushel, centiliter, cord, cubic_centimeter, cubic_decameter, cubic_decimeter, cubic_foot,
+            cubic_hectometer, cubic_inch, cubic_kilometer, cubic_megameter, cubic_meter, cubic_micrometer, cubic_mile,
+            cubic_millimeter, cubic_yard, cup, decaliter, deciliter, fluid_ounce, fluid_ounce_imperial, gallon, gallon_imperial,
+            gigaliter, gill, gill_imperial, hectoliter, kiloliter, liter, megaliter, microliter, milliliter, nanoliter, peck,
+            picoliter, pint_dry, pint_liquid, quart_dry, quart_liquid, tablespoon, teaspoon, teraliter,
+        },
+    },
+};
+
+use super::unit_helper::MeasurementSystem;
+
+/// `UnitKind` is the kind of physical quantity a unit measures
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitKind {
+    /// lengths of time, like step durations
+    Time,
+    /// temperatures and temperature intervals, like oven settings
+    Temperature,
+    /// masses of ingredients
+    Mass,
+    /// volumes of ingredients
+    Volume,
+}
+
+impl UnitKind {
+    /// every kind of unit, in the order they are listed
+    pub const ALL: [Self; 4] = [Self::Time, Self::Temperature, Self::Mass, Self::Volume];
+
+    /// `name` is the lowercase name of this kind, as used in error messages. Ex: `mass`
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Time => "time",
+            Self::Temperature => "temperature",
+            Self::Mass => "mass",
+            Self::Volume => "volume",
+        }
+    }
+
+    /// `base_unit` is the abbreviation of the SI base unit values of this kind are converted
+    /// through
+    #[must_use]
+    pub fn base_unit(self) -> &'static str {
+        match self {
+            Self::Time => "s",
+            Self::Temperature => "K",
+            Self::Mass => "kg",
+            Self::Volume => "m³",
+        }
+    }
+}
+
+impl fmt::Display for UnitKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Time => write!(f, "Time"),
+            Self::Temperature => write!(f, "Temperature"),
+            Self::Mass => write!(f, "Mass"),
+            Self::Volume => write!(f, "Volume"),
+        }
+    }
+}
+
+/// `UnitDefinition` is a unit that can be used in recipe, catalog and configuration files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnitDefinition {
+    /// kind of quantity the unit measures
+    pub kind: UnitKind,
+    /// abbreviation the unit is written to files with. Follows NIST and SI standards
+    pub abbreviation: &'static str,
+    /// full name of one of the unit
+    pub singular: &'static str,
+    /// full name of more than one of the unit
+    pub plural: &'static str,
+    /// kitchen shorthands for the unit that aren't abbreviations or full names. A shorthand
+    /// listed on more than one unit of the same kind is ambiguous, and isn't accepted
+    pub aliases: &'static [&'static str],
+    /// system of units the unit belongs to
+    pub system: MeasurementSystem,
+    /// value of one of the unit in the base unit of its kind
+    pub factor: Rational64,
+    /// amount added to a value in the unit before it is multiplied by `factor`. Only
+    /// temperatures, like degrees Celsius, have one
+    pub offset: Rational64,
+}
+
+impl UnitDefinition {
+    /// `to_base` converts `value` in this unit to the base unit of its kind.
+    /// Temperatures are absolute, so 0 °C is 273.15 K. Returns `None` if the result doesn't fit
+    /// in a `[num_rational::Rational64]`
+    #[must_use]
+    pub fn to_base(&self, value: Rational64) -> Option<Rational64> {
+        value.checked_add(&self.offset)?.checked_mul(&self.factor)
+    }
+
+    /// `from_base` converts `value` in the base unit of its kind to this unit.
+    /// The inverse of [`UnitDefinition::to_base`]
+    #[must_use]
+    pub fn from_base(&self, value: Rational64) -> Option<Rational64> {
+        value.checked_div(&self.factor)?.checked_sub(&self.offset)
+    }
+
+    /// `interval_to_base` converts a difference of `value` in this unit to the base unit of its
+    /// kind, so a difference of 1 °C is 1 K
+    #[must_use]
+    pub fn interval_to_base(&self, value: Rational64) -> Option<Rational64> {
+        value.checked_mul(&self.factor)
+    }
+
+    /// `interval_from_base` is the inverse of [`UnitDefinition::interval_to_base`]
+    #[must_use]
+    pub fn interval_from_base(&self, value: Rational64) -> Option<Rational64> {
+        value.checked_div(&self.factor)
+    }
+
+    /// `format` returns `value`, which is in this unit, followed by the abbreviation or name of
+    /// this unit. Ex: `3/2 tsp`, `1 teaspoon`
+    #[must_use]
+    pub fn format(&self, value: Rational64, style: DisplayStyle) -> String {
+        let name = match style {
+            DisplayStyle::Abbreviation => self.abbreviation,
+            DisplayStyle::Description if value.is_one() => self.singular,
+            DisplayStyle::Description => self.plural,
+        };
+        format!("{value} {name}")
+    }
+}
+
+/// `UnitRegistry` is the list of every supported unit, as written by `--print-units` in JSON or
+/// TOML for editor tooling
+#[derive(Debug, Clone, Serialize)]
+pub struct UnitRegistry {
+    /// supported units, grouped by kind
+    pub units: &'static [UnitDefinition],
+}
+
+impl Default for UnitRegistry {
+    fn default() -> Self {
+        Self { units: units() }
+    }
+}
+
+/// `definition` builds the registry entry for the uom unit `U`, one of which is `factor` in the
+/// base unit of `kind`.
+///
+/// uom's own coefficients are converted from `f64`, so most customary units come out as
+/// fractions with huge denominators that overflow as soon as quantities are added. The factors
+/// here are exact instead.
+fn definition<U: Unit>(
+    kind: UnitKind,
+    system: MeasurementSystem,
+    factor: Rational64,
+    aliases: &'static [&'static str],
+) -> UnitDefinition {
+    UnitDefinition {
+        kind,
+        abbreviation: U::abbreviation(),
+        singular: U::singular(),
+        plural: U::plural(),
+        aliases,
+        system,
+        factor,
+        offset: Rational64::zero(),
+    }
+}
+
+/// `imperial_measure` builds the registry entry for an Imperial kitchen measure uom doesn't
+/// have, one of which is `fluid_ounces` Imperial fluid ounces
+fn imperial_measure(
+    abbreviation: &'static str,
+    singular: &'static str,
+    plural: &'static str,
+    fluid_ounces: Rational64,
+) -> UnitDefinition {
+    UnitDefinition {
+        kind: UnitKind::Volume,
+        abbreviation,
+        singular,
+        plural,
+        aliases: &[],
+        system: MeasurementSystem::Imperial,
+        factor: imperial_fluid_ounce() * fluid_ounces,
+        offset: Rational64::zero(),
+    }
+}
+
+/// `ten_to` returns 10 raised to `exponent`, for the factors of metric prefixes
+fn ten_to(exponent: i32) -> Rational64 {
+    Rational64::from_integer(10).pow(exponent)
+}
+
+/// `us_fluid_ounce` returns one US fluid ounce, 29.5735295625 mL, in cubic meters. US kitchen
+/// measures are whole fractions of it
+fn us_fluid_ounce() -> Rational64 {
+    Rational64::new(473_176_473, 16_000_000_000_000)
+}
+
+/// `imperial_fluid_ounce` returns one Imperial fluid ounce, 28.4130625 mL, in cubic meters.
+/// Imperial kitchen measures are whole fractions of it
+fn imperial_fluid_ounce() -> Rational64 {
+    Rational64::new(454_609, 16_000_000_000)
+}
+
+/// `cubic_inch_factor` returns one cubic inch, 16.387064 cm³, in cubic meters. US dry measures
+/// and the larger customary volumes are multiples of it
+fn cubic_inch_factor() -> Rational64 {
+    Rational64::new(16_387_064, 1_000_000_000_000_000)
+}
+
+/// every supported unit.
+///
+/// Cubic terameters, cubic gigameters, cubic nanometers and cubic picometers are left out, since
+/// their conversion factors don't fit in a `[num_rational::Rational64]`.
+static UNITS: LazyLock<Vec<UnitDefinition>> = LazyLock::new(|| {
+    use MeasurementSystem::{Imperial, Metric, UsCustomary};
+    use UnitKind::{Mass, Temperature, Time, Volume};
+    let int = Rational64::from_integer;
+    let pound_factor = Rational64::new(45_359_237, 100_000_000);
+    let bushel_factor = cubic_inch_factor() * Rational64::new(215_042, 100);
+    let cubic_foot_factor = cubic_inch_factor() * int(1728);
+    // acre-feet are measured in US survey feet, 1200/3937 m
+    let survey_foot = Rational64::new(1200, 3937);
+    vec![
+        definition::<terasecond>(Time, Metric, ten_to(12), &[]),
+        definition::<gigasecond>(Time, Metric, ten_to(9), &[]),
+        definition::<megasecond>(Time, Metric, ten_to(6), &[]),
+        definition::<kilosecond>(Time, Metric, ten_to(3), &[]),
+        definition::<hectosecond>(Time, Metric, ten_to(2), &[]),
+        definition::<decasecond>(Time, Metric, ten_to(1), &[]),
+        definition::<second>(Time, Metric, ten_to(0), &["sec", "secs"]),
+        definition::<decisecond>(Time, Metric, ten_to(-1), &[]),
+        definition::<centisecond>(Time, Metric, ten_to(-2), &[]),
+        definition::<millisecond>(Time, Metric, ten_to(-3), &["msec"]),
+        definition::<microsecond>(Time, Metric, ten_to(-6), &["usec"]),
+        definition::<nanosecond>(Time, Metric, ten_to(-9), &[]),
+        definition::<picosecond>(Time, Metric, ten_to(-12), &[]),
+        definition::<day>(Time, Metric, int(86_400), &[]),
+        definition::<hour>(Time, Metric, int(3600), &["hr", "hrs"]),
+        definition::<minute>(Time, Metric, int(60), &["m", "mins"]),
+        definition::<year>(Time, Metric, int(31_536_000), &["yr", "yrs"]),
+        definition::<terakelvin>(Temperature, Metric, ten_to(12), &[]),
+        definition::<gigakelvin>(Temperature, Metric, ten_to(9), &[]),
+        definition::<megakelvin>(Temperature, Metric, ten_to(6), &[]),
+        definition::<kilokelvin>(Temperature, Metric, ten_to(3), &[]),
+        definition::<hectokelvin>(Temperature, Metric, ten_to(2), &[]),
+        definition::<decakelvin>(Temperature, Metric, ten_to(1), &[]),
+        definition::<kelvin>(Temperature, Metric, ten_to(0), &[]),
+        definition::<decikelvin>(Temperature, Metric, ten_to(-1), &[]),
+        definition::<centikelvin>(Temperature, Metric, ten_to(-2), &[]),
+        definition::<millikelvin>(Temperature, Metric, ten_to(-3), &[]),
+        definition::<microkelvin>(Temperature, Metric, ten_to(-6), &[]),
+        definition::<nanokelvin>(Temperature, Metric, ten_to(-9), &[]),
+        definition::<picokelvin>(Temperature, Metric, ten_to(-12), &[]),
+        UnitDefinition {
+            offset: Rational64::new(27_315, 100),
+            ..definition::<degree_celsius>(Temperature, Metric, int(1), &["C", "deg C", "degrees C", "celsius"])
+        },
+        UnitDefinition {
+            offset: Rational64::new(45_967, 100),
+            ..definition::<degree_fahrenheit>(
+                Temperature,
+                UsCustomary,
+                Rational64::new(5, 9),
+                &["F", "deg F", "degrees F", "fahrenheit"],
+            )
+        },
+        definition::<degree_rankine>(Temperature, UsCustomary, Rational64::new(5, 9), &[]),
+        definition::<teragram>(Mass, Metric, ten_to(9), &[]),
+        definition::<gigagram>(Mass, Metric, ten_to(6), &[]),
+        definition::<megagram>(Mass, Metric, ten_to(3), &[]),
+        definition::<kilogram>(Mass, Metric, ten_to(0), &["kilo", "kilos"]),
+        definition::<hectogram>(Mass, Metric, ten_to(-1), &[]),
+        definition::<decagram>(Mass, Metric, ten_to(-2), &[]),
+        definition::<gram>(Mass, Metric, ten_to(-3), &["gm", "gms"]),
+        definition::<decigram>(Mass, Metric, ten_to(-4), &[]),
+        definition::<centigram>(Mass, Metric, ten_to(-5), &[]),
+        definition::<milligram>(Mass, Metric, ten_to(-6), &[]),
+        definition::<microgram>(Mass, Metric, ten_to(-9), &["mcg", "ug"]),
+        definition::<nanogram>(Mass, Metric, ten_to(-12), &[]),
+        definition::<picogram>(Mass, Metric, ten_to(-15), &[]),
+        definition::<ounce>(Mass, UsCustomary, pound_factor / int(16), &["ozs"]),
+        definition::<pound>(Mass, UsCustomary, pound_factor, &["lbs", "#"]),
+        definition::<cubic_megameter>(Volume, Metric, ten_to(18), &[]),
+        definition::<cubic_kilometer>(Volume, Metric, ten_to(9), &[]),
+        definition::<cubic_hectometer>(Volume, Metric, ten_to(6), &[]),
+        definition::<cubic_decameter>(Volume, Metric, ten_to(3), &[]),
+        definition::<cubic_meter>(Volume, Metric, ten_to(0), &[]),
+        definition::<cubic_decimeter>(Volume, Metric, ten_to(-3), &[]),
+        definition::<cubic_centimeter>(Volume, Metric, ten_to(-6), &["cc"]),
+        definition::<cubic_millimeter>(Volume, Metric, ten_to(-9), &[]),
+        definition::<cubic_micrometer>(Volume, Metric, ten_to(-18), &[]),
+        definition::<acre_foot>(
+            Volume,
+            UsCustomary,
+            int(43_560) * survey_foot * survey_foot * survey_foot,
+            &[],
+        ),
+        definition::<barrel>(Volume, UsCustomary, us_fluid_ounce() * int(5376), &[]),
+        definition::<bushel>(Volume, UsCustomary, bushel_factor, &[]),
+        definition::<cord>(Volume, UsCustomary, cubic_foot_factor * int(128), &[]),
+        definition::<cubic_foot>(Volume, UsCustomary, cubic_foot_factor, &[]),
+        definition::<cubic_inch>(Volume, UsCustomary, cubic_inch_factor(), &[]),
+        definition::<cubic_mile>(Volume, UsCustomary, cubic_foot_factor * int(5280).pow(3), &[]),
+        definition::<cubic_yard>(Volume, UsCustomary, cubic_foot_factor * int(27), &[]),
+        definition::<cup>(Volume, UsCustomary, us_fluid_ounce() * int(8), &["c"]),
+        definition::<fluid_ounce>(Volume, UsCustomary, us_fluid_ounce(), &["oz", "floz"]),
+        definition::<fluid_ounce_imperial>(Volume, Imperial, imperial_fluid_ounce(), &[]),
+        definition::<gallon_imperial>(Volume, Imperial, imperial_fluid_ounce() * int(160), &[]),
+        definition::<gallon>(Volume, UsCustomary, us_fluid_ounce() * int(128), &[]),
+        definition::<gill_imperial>(Volume, Imperial, imperial_fluid_ounce() * int(5), &[]),
+        definition::<gill>(Volume, UsCustomary, us_fluid_ounce() * int(4), &[]),
+        definition::<teraliter>(Volume, Metric, ten_to(9), &[]),
+        definition::<gigaliter>(Volume, Metric, ten_to(6), &[]),
+        definition::<megaliter>(Volume, Metric, ten_to(3), &[]),
+        definition::<kiloliter>(Volume, Metric, ten_to(0), &[]),
+        definition::<hectoliter>(Volume, Metric, ten_to(-1), &[]),
+        definition::<decaliter>(Volume, Metric, ten_to(-2), &[]),
+        definition::<liter>(Volume, Metric, ten_to(-3), &["l", "litre", "litres"]),
+        definition::<deciliter>(Volume, Metric, ten_to(-4), &["dl"]),
+        definition::<centiliter>(Volume, Metric, ten_to(-5), &["cl"]),
+        definition::<milliliter>(Volume, Metric, ten_to(-6), &["ml", "millilitre", "millilitres"]),
+        definition::<microliter>(Volume, Metric, ten_to(-9), &[]),
+        definition::<nanoliter>(Volume, Metric, ten_to(-12), &[]),
+        definition::<picoliter>(Volume, Metric, ten_to(-15), &[]),
+        definition::<peck>(Volume, UsCustomary, bushel_factor / int(4), &[]),
+        // a pint or quart without "dry" in a recipe is a liquid measure
+        definition::<pint_dry>(Volume, UsCustomary, bushel_factor / int(64), &[]),
+        definition::<pint_liquid>(Volume, UsCustomary, us_fluid_ounce() * int(16), &["pt", "pint", "pints"]),
+        definition::<quart_dry>(Volume, UsCustomary, bushel_factor / int(32), &[]),
+        definition::<quart_liquid>(Volume, UsCustomary, us_fluid_ounce() * int(32), &["qt", "quart", "quarts"]),
+        definition::<tablespoon>(Volume, UsCustomary, us_fluid_ounce() / int(2), &["T", "tbs", "tbl", "t"]),
+        definition::<teaspoon>(Volume, UsCustomary, us_fluid_ounce() / int(6), &["t"]),
+        imperial_measure("cup (UK)", "Imperial cup", "Imperial cups", int(10)),
+        imperial_measure(
+            "tbsp (UK)",
+            "Imperial tablespoon",
+            "Imperial tablespoons",
+            Rational64::new(5, 8),
+        ),
+        imperial_measure("tsp (UK)", "Imperial teaspoon", "Imperial teaspoons", Rational64::new(5, 24)),
+    ]
+});
+
+/// `units` returns every supported unit, grouped by kind
+#[must_use]
+pub fn units() -> &'static [UnitDefinition] {
+    &UNITS
+}
+
+/// `units_of` returns the supported units of `kind`
+pub fn units_of(kind: UnitKind) -> impl Iterator<Item = &'static UnitDefinition> {
+    UNITS.iter().filter(move |u| u.kind == kind)
+}
+
+/// `unit` returns the supported unit of `kind` with the abbreviation `abbreviation`
+#[must_use]
+pub fn unit(kind: UnitKind, abbreviation: &str) -> Option<&'static UnitDefinition> {
+    units_of(kind).find(|u| u.abbreviation == abbreviation)
+}
+
+/// `find_unit` returns the supported unit of any kind with the abbreviation `abbreviation`
+#[must_use]
+pub fn find_unit(abbreviation: &str) -> Option<&'static UnitDefinition> {
+    UNITS.iter().find(|u| u.abbreviation == abbreviation)
+}
+
+#[cfg(test)]
+mod tests {
+    use num_rational::Rational64;
+
+    use super::{UnitKind, unit};
+
+    /// units recipes are usually written in, grouped by the units they add up with exactly
+    const KITCHEN_UNITS: [(UnitKind, &[&str]); 5] = [
+        (UnitKind::Volume, &["tsp", "tbsp", "fl oz", "cup", "liq pt", "liq qt", "gal"]),
+        (
+            UnitKind::Volume,
+            &["tsp (UK)", "tbsp (UK)", "fl oz (UK)", "cup (UK)", "gal (UK)"],
+        ),
+        (UnitKind::Volume, &["mL", "L"]),
+        (UnitKind::Mass, &["oz", "lb"]),
+        (UnitKind::Mass, &["g", "kg"]),
+    ];
+
+    #[test]
+    fn kitchen_units_round_trip() {
+        for (kind, abbreviations) in KITCHEN_UNITS {
+            for abbreviation in abbreviations {
+                let unit = unit(kind, abbreviation).expect("kitchen unit is registered");
+                for value in [Rational64::new(1, 3), Rational64::new(3, 2), Rational64::from_integer(250)] {
+                    assert_eq!(
+                        unit.to_base(value).and_then(|base| unit.from_base(base)),
+                        Some(value),
+                        "{abbreviation}"
+                    );
+                }
+            }
+        }
+    }
+
+    /// `base` returns `value` in the kitchen unit `abbreviation` of `kind`, in the base unit
+    fn base(kind: UnitKind, abbreviation: &str, value: i64) -> Rational64 {
+        let unit = unit(kind, abbreviation).expect("kitchen unit is registered");
+        unit.to_base(Rational64::from_integer(value))
+            .expect("kitchen amount converts")
+    }
+
+    /// `in_unit` returns the base unit value `value` of `kind` in the unit `abbreviation`
+    fn in_unit(kind: UnitKind, abbreviation: &str, value: Rational64) -> Rational64 {
+        let unit = unit(kind, abbreviation).expect("kitchen unit is registered");
+        unit.from_base(value).expect("kitchen amount converts")
+    }
+
+    #[test]
+    fn mixed_kitchen_units_add_up_exactly() {
+        for (kind, abbreviations) in KITCHEN_UNITS {
+            for a in abbreviations {
+                for b in abbreviations {
+                    let sum = in_unit(kind, a, base(kind, a, 1) + base(kind, b, 1));
+                    assert!(*sum.denom() <= 1000, "1 {a} + 1 {b} = {sum} {a}");
+                }
+            }
+        }
+        let volume = base(UnitKind::Volume, "tsp", 1) + base(UnitKind::Volume, "tbsp", 1) + base(UnitKind::Volume, "cup", 1);
+        assert_eq!(in_unit(UnitKind::Volume, "tsp", volume), Rational64::from_integer(52));
+        let mass = base(UnitKind::Mass, "lb", 1) + base(UnitKind::Mass, "oz", 4);
+        assert_eq!(in_unit(UnitKind::Mass, "lb", mass), Rational64::new(5, 4));
+    }
+
+    #[test]
+    fn conversions_too_large_to_represent_are_none() {
+        let pound = unit(UnitKind::Mass, "lb").expect("lb is registered");
+        assert_eq!(pound.to_base(Rational64::from_integer(i64::MAX / 2)), None);
+        assert_eq!(pound.from_base(Rational64::new(1, i64::MAX / 2)), None);
+    }
+}
@@ -1,5 +1,6 @@
 //! cookbook TODO: add more documentation
 
+use std::collections::HashMap;
 use std::io::{Write, stdin, stdout};
 #[cfg(feature = "wgui")]
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
@@ -24,11 +25,22 @@ use gix::{
 };
 #[cfg(any(feature = "tui", feature = "wgui"))]
 use log::{info, trace, warn};
+use num_rational::Rational64;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use cookbook_core::datatypes::{recipe::Recipe, step::Step, unit_helper};
-
-//TODO: allow specification of alternate ingredients
+use cookbook_core::datatypes::{
+    cook_log::{CookLog, CookLogEntry, RecipeSort},
+    dietary::DietaryFilter,
+    display_preferences::DisplayPreferences,
+    equipment_inventory::EquipmentInventory,
+    ingredient_catalog::IngredientCatalog,
+    recipe::Recipe,
+    step::Step,
+    tag::TagAliases,
+    unit_helper,
+    unit_registry::UnitRegistry,
+};
 
 //TODO: investigate crate-ci/typos, cargo-audit/cargo-deny, codecov, bacon, editorconfig.org
 //
@@ -78,7 +90,11 @@ fn main() -> anyhow::Result<()> {
     };
 
     if config.print_units {
-        unit_helper::print_units();
+        match config.units_format.as_deref() {
+            Some("json") => println!("{}", serde_json::to_string_pretty(&UnitRegistry::default())?),
+            Some("toml") => print!("{}", toml::to_string_pretty(&UnitRegistry::default())?),
+            _ => unit_helper::print_units(),
+        }
         return Ok(());
     }
 
@@ -94,24 +110,88 @@ fn main() -> anyhow::Result<()> {
     };
 
     let recipe_repo = load_git_repo(input_dir)?;
+    let tag_aliases = TagAliases::from(&config.tag_aliases);
 
     if config.check_recipe_files {
-        _ = Recipe::load_recipes_from_directory(input_dir)?;
+        let recipes = Recipe::load_recipes_from_directory(input_dir)?;
+        // photo problems don't stop recipes loading, so are reported separately
+        let photo_problems: Vec<anyhow::Error> = recipes.values().flat_map(|r| r.photo_problems(input_dir)).collect();
+        for problem in &photo_problems {
+            println!("{problem:#}");
+        }
+        _ = IngredientCatalog::load_from_directory(input_dir)?;
+        _ = EquipmentInventory::load_from_directory(input_dir)?;
+        _ = CookLog::load_from_directory(input_dir)?;
+        if !photo_problems.is_empty() {
+            anyhow::bail!("{} photo problems found", photo_problems.len());
+        }
+    } else if config.migrate_recipe_files {
+        let migration = Recipe::migrate_recipe_files(input_dir, config.use_references)?;
+        if migration.owned_equipment > 0 {
+            println!(
+                "Added {} owned equipment to the equipment inventory",
+                migration.owned_equipment
+            );
+        }
+        println!("Rewrote {} recipe files in the current format", migration.recipe_files);
     } else if config.print_recipe_files {
-        for recipe in Recipe::load_recipes_from_directory(input_dir)? {
+        let mut recipes = Recipe::load_recipes_from_directory(input_dir)?;
+        Recipe::apply_tag_aliases(&mut recipes, &tag_aliases);
+        for recipe in recipes {
             let output_string = toml::to_string_pretty(&recipe)?;
             println!("{output_string}");
         }
+    } else if let Some(diets) = &config.filter_diet {
+        let filters = diets
+            .split(',')
+            .map(|name| {
+                DietaryFilter::from_name(name).with_context(|| {
+                    format!(
+                        "{name} is not a dietary need. Choose from: {}",
+                        DietaryFilter::all()
+                            .into_iter()
+                            .map(DietaryFilter::name)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let recipes = Recipe::load_recipes_from_directory(input_dir)?;
+        let catalog = IngredientCatalog::load_from_directory(input_dir)?;
+        let mut recipes = Recipe::filter_recipes_by_diet(recipes, &filters, &catalog)
+            .into_values()
+            .collect::<Vec<_>>();
+        recipes.sort_by(|a, b| a.name.cmp(&b.name));
+        for recipe in recipes {
+            println!("{} ({})", recipe.name, recipe.id);
+        }
+    } else if let Some(recipe_id) = config.scale_recipe {
+        let recipes = Recipe::load_recipes_from_directory(input_dir)?;
+        let recipe = recipes
+            .get(&recipe_id)
+            .with_context(|| format!("Recipe with id {recipe_id} not found in {}", input_dir.display()))?;
+        let scale_factor_string = config.scale_factor.as_deref().unwrap_or("1");
+        let scale_factor = unit_helper::parse_rational(scale_factor_string)
+            .with_context(|| format!("{scale_factor_string} is not a valid scale factor"))?;
+        if config.save_scaled_recipe {
+            let scaled_recipe = recipe.new_scaled_copy(scale_factor)?;
+            println!("{}", scaled_recipe.to_toml_string()?);
+            let path = Recipe::write_new_recipe(scaled_recipe, input_dir)?;
+            info!("saved scaled recipe to {}", path.display());
+        } else {
+            println!("{}", recipe.scale(scale_factor)?.to_toml_string()?);
+        }
     } else if config.run_web_server && cfg!(feature = "wgui") {
         #[cfg(feature = "wgui")]
         let ip_addr = SocketAddr::new(config.server_address, config.server_port);
         #[cfg(feature = "wgui")]
         info!("running web server");
         #[cfg(feature = "wgui")]
-        run_web_server(input_dir, ip_addr, None, config.num_threads)?;
+        run_web_server(input_dir, ip_addr, None, config.num_threads, &tag_aliases, config.display)?;
     } else if cfg!(feature = "tui") {
         #[cfg(feature = "tui")]
-        run_tui(input_dir, recipe_repo)?;
+        run_tui(input_dir, recipe_repo, &tag_aliases, config.display)?;
     }
 
     Ok(())
@@ -130,19 +210,21 @@ fn run_web_server<T>(
     addrs: SocketAddr,
     ssl_conf: Option<tiny_http::SslConfig>,
     num_threads: usize,
+    tag_aliases: &TagAliases,
+    display: DisplayPreferences,
 ) -> anyhow::Result<()>
 where
     T: AsRef<Path>,
 {
     // A lot of this borrowed from https://github.com/tomaka/example-tiny-http/blob/master/src/lib.rs
     // as the official multi-thread example is borked
-    use std::collections::{HashMap, HashSet};
+    use std::collections::HashSet;
     use std::sync::{Arc, mpsc};
     use std::thread;
 
     use tiny_http::{ConfigListenAddr, Server, ServerConfig, http::method::Method};
-    use uuid::Uuid;
 
+    use cookbook_core::datatypes::photo::Photo;
     use cookbook_core::wgui::{browser, error_responses, http_helper, media_responses, recipe_editor, recipe_viewer, root};
 
     /// `ThreadMessage` are messages that worker threads can send back to the processing
@@ -162,6 +244,13 @@ where
         EditedRecipe(Recipe, bool),
         /// `NewRecipe` contains a newly created recipe from a worker thread.
         NewRecipe(Recipe),
+        /// `CookLog` is a request from the worker thread for the whole cook log, used to sort
+        /// recipes when browsing
+        CookLog,
+        /// `CookHistory` is a request from the worker thread for the cook log of a specific recipe
+        CookHistory(Uuid),
+        /// `MarkCooked` contains a new cook log entry for a specific recipe from a worker thread.
+        MarkCooked(Uuid, CookLogEntry),
     }
     /// `ThreadResponse` contains responses from processing thread to worker threads
     #[derive(Debug)]
@@ -169,10 +258,24 @@ where
         AllRecipes(HashMap<Uuid, Recipe>),
         Recipe(Recipe),
         EditingError(Uuid),
+        CookLog(CookLog),
+        CookHistory(Vec<CookLogEntry>),
     }
 
-    let mut recipes = Recipe::load_recipes_from_directory(input_dir)?;
-    // this is a pre-sorted list
+    let mut recipes = Recipe::load_recipes_from_directory(&input_dir)?;
+    Recipe::apply_tag_aliases(&mut recipes, tag_aliases);
+    // the catalog is read only, so is shared between worker threads rather than owned by the data
+    // owner thread
+    let catalog = Arc::new(IngredientCatalog::load_from_directory(&input_dir)?);
+    // the equipment inventory is only edited by hand, so is shared the same way
+    let inventory = Arc::new(EquipmentInventory::load_from_directory(&input_dir)?);
+    // the cook log is owned by the data owner thread, which saves it whenever it changes
+    let mut cook_log = CookLog::load_from_directory(&input_dir)?;
+    let recipe_dir = input_dir.as_ref().to_path_buf();
+    // photos are read and uploaded, and scaled copies of recipes saved, directly by the worker
+    // threads
+    let photo_dir = Arc::new(recipe_dir.clone());
+    // tags grouped by namespace, each group pre-sorted
     let tags = Recipe::compile_tag_list(recipes.clone());
 
     let server_config = ServerConfig {
@@ -271,6 +374,10 @@ where
                         locked_recipes.remove(&recipe.id);
                     }
                     let recipe_present = recipes.insert(recipe.id, recipe.clone());
+                    // update the copies of this recipe used as a sub-recipe in other recipes
+                    if let Err(e) = Recipe::resolve_sub_recipes(&mut recipes) {
+                        warn!("Resolving sub-recipes after editing recipe {} failed: {e}", recipe.id);
+                    }
                     if recipe_present.is_none() {
                         //TODO: handle this better
                         panic!("Edited recipe ID not found in master recipe list. This should not have happend.");
@@ -290,6 +397,33 @@ where
                     }
                     tx_channels[thread_id].clone().send(ThreadResponse::Recipe(recipe)).unwrap();
                 }
+                ThreadMessage::CookLog => {
+                    trace!("sending a CookLog response to thread id {thread_id}");
+                    tx_channels[thread_id]
+                        .clone()
+                        .send(ThreadResponse::CookLog(cook_log.clone()))
+                        .unwrap()
+                }
+                ThreadMessage::CookHistory(recipe_id) => {
+                    trace!("sending a CookHistory response with recipe_id {recipe_id} to thread id {thread_id}");
+                    tx_channels[thread_id]
+                        .clone()
+                        .send(ThreadResponse::CookHistory(cook_log.history(&recipe_id).to_vec()))
+                        .unwrap()
+                }
+                // TODO: properly handle the Option of HashMap.get() rather than unwrapping
+                ThreadMessage::MarkCooked(recipe_id, entry) => {
+                    //TODO: show these errors to the user
+                    if let Err(e) = cook_log.record(recipe_id, entry) {
+                        warn!("Marking recipe {recipe_id} as cooked failed: {e}");
+                    } else if let Err(e) = cook_log.write_to_directory(&recipe_dir) {
+                        warn!("Saving cook log failed: {e}");
+                    }
+                    tx_channels[thread_id]
+                        .clone()
+                        .send(ThreadResponse::Recipe(recipes.get(&recipe_id).unwrap().clone()))
+                        .unwrap()
+                }
             };
         }
     }));
@@ -299,6 +433,9 @@ where
         trace! {"starting thread: {i}"}
         let server = server.clone();
         let tags = tags.clone();
+        let catalog = Arc::clone(&catalog);
+        let inventory = Arc::clone(&inventory);
+        let photo_dir = Arc::clone(&photo_dir);
         let tx = tx.clone();
         let rx = rx_channels.pop().unwrap();
         let builder = thread::Builder::new().name(i.to_string());
@@ -308,7 +445,17 @@ where
                 let server = server.clone();
                 let tags = tags.clone();
                 let tx = tx.clone();
+                // the cook log of a recipe, shown in the recipe viewer
+                let cook_history = |recipe_id: Uuid| {
+                    tx.send((i, ThreadMessage::CookHistory(recipe_id))).unwrap();
+                    match rx.recv().unwrap() {
+                        ThreadResponse::CookHistory(history) => history,
+                        _ => panic!("Incorrect response to request for CookHistory"),
+                    }
+                };
                 for mut request in server.incoming_requests() {
+                    // display preferences saved in this browser override the configured ones
+                    let display = display.with_form_values(&http_helper::get_cookies(&request));
                     let method = request.method().clone();
                     let path = request.url().path();
                     trace!("{method} request received with path {path}");
@@ -318,9 +465,18 @@ where
                         // parameters in use.
                         Method::GET => match request.url().path() {
                             "/" => request.respond(root::webroot().unwrap()).unwrap(),
-                            "/favicon.ico" => request.respond(media_responses::icon(Path::new("./favicon.ico")).unwrap())?,
+                            "/favicon.ico" => request.respond(media_responses::media(Path::new("./favicon.ico")).unwrap())?,
                             "/database" => request.respond(error_responses::method_not_allowed([Method::POST]))?,
                             "/browse" => request.respond(error_responses::method_not_allowed([Method::POST]))?,
+                            path if path.starts_with(media_responses::PHOTO_ROUTE) => {
+                                match media_responses::photo(&photo_dir, path) {
+                                    Ok(response) => request.respond(response)?,
+                                    Err(e) => {
+                                        warn!("Serving photo failed: {e}");
+                                        request.respond(error_responses::not_found())?
+                                    }
+                                }
+                            }
                             _ => request.respond(error_responses::not_found())?,
                         },
                         Method::POST => match request.url().path() {
@@ -332,13 +488,24 @@ where
                             // reset tags is just pulling the full list of recipes again at this
                             // point
                             "/browse" | "/reset-tags" => {
+                                // the request from root has no form data
+                                let form_data = http_helper::parse_post_form_data(&mut request).unwrap_or_default();
+                                let sort = form_data
+                                    .get("sort")
+                                    .and_then(|s| RecipeSort::from_form_value(s))
+                                    .unwrap_or_default();
                                 tx.send((i, ThreadMessage::AllRecipes)).unwrap();
                                 let recipes = match rx.recv().unwrap() {
                                     ThreadResponse::AllRecipes(recipes) => recipes,
                                     _ => panic!("Incorrect response to request for AllRecipes"),
                                 };
-                                // tags is a pre-sorted list
-                                request.respond(browser::browser(recipes, &tags).unwrap())?
+                                tx.send((i, ThreadMessage::CookLog)).unwrap();
+                                let cook_log = match rx.recv().unwrap() {
+                                    ThreadResponse::CookLog(cook_log) => cook_log,
+                                    _ => panic!("Incorrect response to request for CookLog"),
+                                };
+                                // tags are grouped by namespace, each group pre-sorted
+                                request.respond(browser::browser(recipes, &tags, &[], false, &cook_log, sort).unwrap())?
                             }
                             // from browse
                             "/view-recipe" => {
@@ -353,7 +520,197 @@ where
                                         ThreadResponse::Recipe(recipe) => recipe,
                                         _ => panic!("Incorrect response to request for RecipeRO"),
                                     };
-                                    request.respond(recipe_viewer::recipe_viewer(recipe).unwrap())?
+                                    let history = cook_history(recipe.id);
+                                    request.respond(
+                                        recipe_viewer::recipe_viewer(
+                                            recipe,
+                                            Rational64::from_integer(1),
+                                            &catalog,
+                                            &inventory,
+                                            &history,
+                                            &display,
+                                            false,
+                                        )
+                                        .unwrap(),
+                                    )?
+                                }
+                            }
+                            // from view-recipe
+                            "/mark-cooked" => {
+                                let form_data = http_helper::parse_post_form_data(&mut request).unwrap();
+                                if form_data.contains_key("recipe_id") {
+                                    let uuid_string = form_data["recipe_id"].as_str();
+                                    trace!("Attempting to mark recipe with UUID: {uuid_string} as cooked");
+                                    let scale_factor = form_data
+                                        .get("scale_factor")
+                                        .and_then(|s| unit_helper::parse_rational(s))
+                                        .unwrap_or(Rational64::from_integer(1));
+                                    let mut entry = CookLogEntry::new(scale_factor);
+                                    if let Some(date) = form_data.get("cook_date").filter(|d| !d.is_empty()) {
+                                        match chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                                            Ok(date) => entry.date = date,
+                                            Err(e) => {
+                                                warn!("{date} is not a valid date: {e}");
+                                                // respond and move on to the next request, rather than
+                                                // returning and ending this worker thread
+                                                request.respond(error_responses::bad_request())?;
+                                                continue;
+                                            }
+                                        }
+                                    }
+                                    if let Some(rating) = form_data.get("rating").filter(|r| !r.is_empty()) {
+                                        match rating.parse() {
+                                            Ok(rating) => entry.rating = Some(rating),
+                                            Err(e) => {
+                                                warn!("{rating} is not a valid rating: {e}");
+                                                // respond and move on to the next request, rather than
+                                                // returning and ending this worker thread
+                                                request.respond(error_responses::bad_request())?;
+                                                continue;
+                                            }
+                                        }
+                                    }
+                                    entry.cook = form_data.get("cook").filter(|c| !c.is_empty()).cloned();
+                                    entry.notes = form_data.get("notes").filter(|n| !n.is_empty()).cloned();
+                                    tx.send((i, ThreadMessage::MarkCooked(Uuid::parse_str(uuid_string).unwrap(), entry)))
+                                        .unwrap();
+                                    let recipe = match rx.recv().unwrap() {
+                                        ThreadResponse::Recipe(recipe) => recipe,
+                                        _ => panic!("Incorrect response to request for MarkCooked"),
+                                    };
+                                    let history = cook_history(recipe.id);
+                                    request.respond(
+                                        recipe_viewer::recipe_viewer(
+                                            recipe,
+                                            Rational64::from_integer(1),
+                                            &catalog,
+                                            &inventory,
+                                            &history,
+                                            &display,
+                                            false,
+                                        )
+                                        .unwrap(),
+                                    )?
+                                }
+                            }
+                            // from view-recipe
+                            "/display-preferences" => {
+                                let form_data = http_helper::parse_post_form_data(&mut request).unwrap();
+                                if form_data.contains_key("recipe_id") {
+                                    let uuid_string = form_data["recipe_id"].as_str();
+                                    trace!("Changing display preferences while viewing recipe with UUID: {uuid_string}");
+                                    let display = display.with_form_values(&form_data);
+                                    tx.send((i, ThreadMessage::RecipeRO(Uuid::parse_str(uuid_string).unwrap())))
+                                        .unwrap();
+                                    let recipe = match rx.recv().unwrap() {
+                                        ThreadResponse::Recipe(recipe) => recipe,
+                                        _ => panic!("Incorrect response to request for RecipeRO"),
+                                    };
+                                    // keep the recipe scaled as it was being viewed
+                                    let scale_factor = form_data
+                                        .get("scale_factor")
+                                        .and_then(|s| unit_helper::parse_rational(s))
+                                        .unwrap_or(Rational64::from_integer(1));
+                                    match recipe.scale(scale_factor) {
+                                        Ok(scaled_recipe) => {
+                                            let history = cook_history(scaled_recipe.id);
+                                            request.respond(
+                                                recipe_viewer::recipe_viewer(
+                                                    scaled_recipe,
+                                                    scale_factor,
+                                                    &catalog,
+                                                    &inventory,
+                                                    &history,
+                                                    &display,
+                                                    true,
+                                                )
+                                                .unwrap(),
+                                            )?
+                                        }
+                                        Err(e) => {
+                                            warn!("Scaling recipe {uuid_string} failed: {e}");
+                                            request.respond(error_responses::bad_request())?
+                                        }
+                                    }
+                                }
+                            }
+                            // from view-recipe
+                            "/scale-recipe" | "/save-scaled-recipe" => {
+                                let form_data = http_helper::parse_post_form_data(&mut request).unwrap();
+                                if form_data.contains_key("recipe_id") && form_data.contains_key("scale_factor") {
+                                    let uuid_string = form_data["recipe_id"].as_str();
+                                    trace!("Attempting to scale recipe with UUID: {uuid_string}");
+                                    tx.send((i, ThreadMessage::RecipeRO(Uuid::parse_str(uuid_string).unwrap())))
+                                        .unwrap();
+                                    let recipe = match rx.recv().unwrap() {
+                                        ThreadResponse::Recipe(recipe) => recipe,
+                                        _ => panic!("Incorrect response to request for RecipeRO"),
+                                    };
+                                    //TODO: show scaling errors to the user rather than just a bad request
+                                    match unit_helper::parse_rational(&form_data["scale_factor"]) {
+                                        Some(scale_factor) if request.url().path() == "/save-scaled-recipe" => {
+                                            // the copy is saved to its own file before it is added to the
+                                            // cookbook, so it is still there after a restart
+                                            match recipe.new_scaled_copy(scale_factor).and_then(|scaled_recipe| {
+                                                let path = Recipe::write_new_recipe(scaled_recipe.clone(), photo_dir.as_path())?;
+                                                info!("saved scaled recipe to {}", path.display());
+                                                Ok(scaled_recipe)
+                                            }) {
+                                                Ok(scaled_recipe) => {
+                                                    tx.send((i, ThreadMessage::NewRecipe(scaled_recipe))).unwrap();
+                                                    let recipe = match rx.recv().unwrap() {
+                                                        ThreadResponse::Recipe(recipe) => recipe,
+                                                        x => {
+                                                            trace!("{x:?}");
+                                                            panic!("Incorrect response to request for NewRecipe");
+                                                        }
+                                                    };
+                                                    let history = cook_history(recipe.id);
+                                                    request.respond(
+                                                        recipe_viewer::recipe_viewer(
+                                                            recipe,
+                                                            Rational64::from_integer(1),
+                                                            &catalog,
+                                                            &inventory,
+                                                            &history,
+                                                            &display,
+                                                            false,
+                                                        )
+                                                        .unwrap(),
+                                                    )?
+                                                }
+                                                Err(e) => {
+                                                    warn!("Saving scaled copy of recipe {uuid_string} failed: {e:#}");
+                                                    request.respond(error_responses::bad_request())?
+                                                }
+                                            }
+                                        }
+                                        Some(scale_factor) => match recipe.scale(scale_factor) {
+                                            Ok(scaled_recipe) => {
+                                                let history = cook_history(scaled_recipe.id);
+                                                request.respond(
+                                                    recipe_viewer::recipe_viewer(
+                                                        scaled_recipe,
+                                                        scale_factor,
+                                                        &catalog,
+                                                        &inventory,
+                                                        &history,
+                                                        &display,
+                                                        false,
+                                                    )
+                                                    .unwrap(),
+                                                )?
+                                            }
+                                            Err(e) => {
+                                                warn!("Scaling recipe {uuid_string} failed: {e}");
+                                                request.respond(error_responses::bad_request())?
+                                            }
+                                        },
+                                        None => {
+                                            warn!("{} is not a valid scale factor", form_data["scale_factor"]);
+                                            request.respond(error_responses::bad_request())?
+                                        }
+                                    }
                                 }
                             }
                             // from browse
@@ -419,13 +776,37 @@ where
                                     ThreadResponse::AllRecipes(recipes) => recipes,
                                     _ => panic!("Incorrect response to request for AllRecipes"),
                                 };
-                                let filtered_recipes = Recipe::filter_recipes_by_tags(recipes, &tags);
-                                // tags is a pre-sorted list
-                                request.respond(browser::browser(filtered_recipes, &tags).unwrap())?
+                                tx.send((i, ThreadMessage::CookLog)).unwrap();
+                                let cook_log = match rx.recv().unwrap() {
+                                    ThreadResponse::CookLog(cook_log) => cook_log,
+                                    _ => panic!("Incorrect response to request for CookLog"),
+                                };
+                                let all_tags = tags.values().flatten().cloned().collect::<Vec<_>>();
+                                let filtered_recipes = Recipe::filter_recipes_by_tags(recipes, &all_tags);
+                                let diets = browser::diet_filters(&form_data);
+                                let filtered_recipes = Recipe::filter_recipes_by_diet(filtered_recipes, &diets, &catalog);
+                                let owned_only = form_data.contains_key(browser::OWNED_EQUIPMENT_FIELD);
+                                let filtered_recipes = if owned_only {
+                                    Recipe::filter_recipes_by_equipment(filtered_recipes, &inventory)
+                                } else {
+                                    filtered_recipes
+                                };
+                                // tags are grouped by namespace, each group pre-sorted
+                                request.respond(
+                                    browser::browser(
+                                        filtered_recipes,
+                                        &tags,
+                                        &diets,
+                                        owned_only,
+                                        &cook_log,
+                                        RecipeSort::default(),
+                                    )
+                                    .unwrap(),
+                                )?
                             }
                             // from recipe_editor
                             "/save-recipe-edit" | "/save-recipe" | "/save-new-recipe" => {
-                                let form_data = http_helper::parse_post_form_data(&mut request).unwrap();
+                                let (form_data, form_files) = http_helper::parse_post_form(&mut request).unwrap();
                                 trace!("{form_data:?}");
                                 // Requesting the whole recipe here, helps make sure that we
                                 // keep things like steps, etc together rather than just
@@ -462,7 +843,7 @@ where
                                 let comments = &form_data["comments"];
                                 let source = &form_data["source"];
                                 let author = &form_data["author"];
-                                let amount_made = str::parse(&form_data["amount_made"]).unwrap();
+                                let amount_made = unit_helper::parse_rational(&form_data["amount_made"]).unwrap();
                                 let amount_made_units = &form_data["amount_made_units"];
 
                                 if recipe.name != *name {
@@ -486,6 +867,26 @@ where
                                 if recipe.amount_made.units != *amount_made_units {
                                     recipe.amount_made.units = amount_made_units.clone()
                                 }
+                                let photo_caption = form_data
+                                    .get("photo_caption")
+                                    .filter(|c| !c.trim().is_empty())
+                                    .map(|c| c.trim().to_owned());
+                                let photo_step = form_data.get("photo_target").and_then(|t| t.parse::<usize>().ok());
+                                for file in form_files.iter().filter(|f| f.name == "photo") {
+                                    //TODO: show these errors to the user
+                                    let mut photo = match Photo::save(photo_dir.as_path(), &file.file_name, &file.data) {
+                                        Ok(photo) => photo,
+                                        Err(e) => {
+                                            warn!("Saving uploaded photo {} failed: {e}", file.file_name);
+                                            continue;
+                                        }
+                                    };
+                                    photo.caption.clone_from(&photo_caption);
+                                    match photo_step.and_then(|i| recipe.steps.get_mut(i)) {
+                                        Some(step) => step.photos.push(photo),
+                                        None => recipe.photos.push(photo),
+                                    }
+                                }
                                 if request.url().path() == "/save-recipe-edit" {
                                     // keeping edit lock in place
                                     tx.send((i, ThreadMessage::EditedRecipe(recipe, true))).unwrap();
@@ -507,7 +908,19 @@ where
                                             panic!("Incorrect response to request for EditedRecipe");
                                         }
                                     };
-                                    request.respond(recipe_viewer::recipe_viewer(recipe).unwrap())?
+                                    let history = cook_history(recipe.id);
+                                    request.respond(
+                                        recipe_viewer::recipe_viewer(
+                                            recipe,
+                                            Rational64::from_integer(1),
+                                            &catalog,
+                                            &inventory,
+                                            &history,
+                                            &display,
+                                            false,
+                                        )
+                                        .unwrap(),
+                                    )?
                                 } else if request.url().path() == "/save-new-recipe" {
                                     tx.send((i, ThreadMessage::NewRecipe(recipe))).unwrap();
                                     let recipe = match rx.recv().unwrap() {
@@ -517,7 +930,19 @@ where
                                             panic!("Incorrect response to request for NewRecipe");
                                         }
                                     };
-                                    request.respond(recipe_viewer::recipe_viewer(recipe).unwrap())?
+                                    let history = cook_history(recipe.id);
+                                    request.respond(
+                                        recipe_viewer::recipe_viewer(
+                                            recipe,
+                                            Rational64::from_integer(1),
+                                            &catalog,
+                                            &inventory,
+                                            &history,
+                                            &display,
+                                            false,
+                                        )
+                                        .unwrap(),
+                                    )?
                                 }
                             }
                             // from recipe_editor
@@ -585,7 +1010,12 @@ where
 
 //TODO: add a status message box at the bottom of the window and log some errors to it
 #[cfg(feature = "tui")]
-fn run_tui(input_dir: AsRef<Path>, recipe_repo: gix::Repository) -> anyhow::Result<()> {
+fn run_tui(
+    input_dir: AsRef<Path>,
+    recipe_repo: gix::Repository,
+    tag_aliases: &TagAliases,
+    display: DisplayPreferences,
+) -> anyhow::Result<()> {
     use cookbook_core::tui::{
         Tui,
         app::{self, App},
@@ -602,7 +1032,18 @@ fn run_tui(input_dir: AsRef<Path>, recipe_repo: gix::Repository) -> anyhow::Resu
     let mut app = App::new(keybinds, style);
     app.git_repo = Some(recipe_repo);
 
-    app.recipes = Recipe::load_recipes_from_directory(input_dir)?;
+    app.recipes = Recipe::load_recipes_from_directory(&input_dir)?;
+    Recipe::apply_tag_aliases(&mut app.recipes, tag_aliases);
+    app.tags = Recipe::compile_tag_list(app.recipes.clone());
+    app.ingredient_catalog = IngredientCatalog::load_from_directory(&input_dir)?;
+    app.equipment_inventory = EquipmentInventory::load_from_directory(&input_dir)?;
+    app.cook_log = CookLog::load_from_directory(&input_dir)?;
+    app.recipe_dir = Some(input_dir.as_ref().to_path_buf());
+    // fall back to ASCII fractions in terminals that can't show the unicode ones
+    app.display = DisplayPreferences {
+        fraction_style: display.fraction_style.supported_by_locale(),
+        ..display
+    };
 
     tui_panic_hook();
     let mut tui = Tui::init(events)?;
@@ -805,11 +1246,27 @@ struct Config {
     quiet: u8,
     /// Check recipe files for errors or bad formatting
     check_recipe_files: bool,
+    /// Rewrite all recipe files in the current file format
+    migrate_recipe_files: bool,
+    /// Move ingredients and equipment listed in full in steps to the recipe level lists while
+    /// migrating recipe files
+    use_references: bool,
     /// Prints all recipe files to console
     print_recipe_files: bool,
+    /// Prints the recipes meeting all of a comma separated list of dietary needs, like
+    /// vegan,nut-free
+    filter_diet: Option<String>,
     /// Print Units and Abbreviations that can be used in
     /// recipe files
     print_units: bool,
+    /// Format to print units in: text, or json or toml for editor tooling
+    units_format: Option<String>,
+    /// ID of a recipe to print a scaled copy of
+    scale_recipe: Option<Uuid>,
+    /// Factor to scale the recipe by. Accepts whole numbers, fractions like 3/2 and decimals
+    scale_factor: Option<String>,
+    /// Save the scaled recipe as a new recipe file in the cookbook directory
+    save_scaled_recipe: bool,
     // Export complete PDF
     //#[arg(short, long)]
     //export_pdf: bool,
@@ -822,6 +1279,12 @@ struct Config {
     /// Number of threads for the webgui. Only configurable via configuration file
     #[cfg(feature = "wgui")]
     num_threads: usize,
+    /// Alternate spellings of tags, mapped to the tag they should be treated as. Only configurable
+    /// via configuration file
+    tag_aliases: HashMap<String, String>,
+    /// How quantities, temperatures, times and numbers are shown. Only configurable via
+    /// configuration file
+    display: DisplayPreferences,
 }
 
 impl Default for Config {
@@ -833,14 +1296,23 @@ impl Default for Config {
             run_web_server: false,
             quiet: 0_u8,
             check_recipe_files: false,
+            migrate_recipe_files: false,
+            use_references: false,
             print_recipe_files: false,
+            filter_diet: None,
             print_units: false,
+            units_format: None,
+            scale_recipe: None,
+            scale_factor: None,
+            save_scaled_recipe: false,
             #[cfg(feature = "wgui")]
             server_address: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
             #[cfg(feature = "wgui")]
             server_port: 8080,
             #[cfg(feature = "wgui")]
             num_threads: 4,
+            tag_aliases: HashMap::new(),
+            display: DisplayPreferences::default(),
         }
     }
 }
@@ -874,13 +1346,40 @@ struct Cli {
     /// Check recipe files for errors or bad formatting
     #[arg(short, long)]
     check_recipe_files: bool,
+    /// Rewrite all recipe files in the current file format
+    #[arg(long)]
+    migrate_recipe_files: bool,
+    /// Move ingredients and equipment listed in full in steps to the recipe level lists while
+    /// migrating recipe files
+    #[arg(long, requires = "migrate_recipe_files")]
+    use_references: bool,
     /// Prints all recipe files to console
     #[arg(long)]
     print_recipe_files: bool,
+    /// Prints the recipes meeting all of a comma separated list of dietary needs, like
+    /// vegan,nut-free
+    #[arg(long)]
+    #[serde(skip_serializing_if = "::std::option::Option::is_none")]
+    filter_diet: Option<String>,
     /// Print Units and Abbreviations that can be used in
     /// recipe files
     #[arg(long)]
     print_units: bool,
+    /// Format to print units in: text, or json or toml for editor tooling
+    #[arg(long, requires = "print_units", value_parser = ["text", "json", "toml"])]
+    #[serde(skip_serializing_if = "::std::option::Option::is_none")]
+    units_format: Option<String>,
+    /// ID of a recipe to print a scaled copy of
+    #[arg(long)]
+    #[serde(skip_serializing_if = "::std::option::Option::is_none")]
+    scale_recipe: Option<Uuid>,
+    /// Factor to scale the recipe by. Accepts whole numbers, fractions like 3/2 and decimals
+    #[arg(long, requires = "scale_recipe")]
+    #[serde(skip_serializing_if = "::std::option::Option::is_none")]
+    scale_factor: Option<String>,
+    /// Save the scaled recipe as a new recipe file in the cookbook directory
+    #[arg(long, requires = "scale_recipe")]
+    save_scaled_recipe: bool,
     // Export complete PDF
     //#[arg(short, long)]
     //export_pdf: bool,
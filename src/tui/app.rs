@@ -2,10 +2,11 @@ use std::collections::HashMap;
 use std::fmt;
 use std::io;
 use std::num::Saturating;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use gix::Repository;
-use log::debug;
+use log::{debug, warn};
+use num_rational::Rational64;
 use num_traits::ToPrimitive;
 use ratatui::{
     Frame,
@@ -20,10 +21,17 @@ use uuid::Uuid;
 
 use crate::{
     datatypes::{
-        equipment, ingredient,
-        recipe::{self, Recipe, RecipeFieldOffset, RecipeFields},
+        cook_log::{CookLog, RecipeSort},
+        dietary::DietaryFilter,
+        display_preferences::DisplayPreferences,
+        equipment,
+        equipment_inventory::EquipmentInventory,
+        ingredient,
+        ingredient_catalog::{IngredientCatalog, Nutrition},
+        recipe::{self, IngredientListOptions, Recipe, RecipeFieldOffset, RecipeFields},
         step,
-        tag::Tag,
+        tag::TagGroups,
+        unit_helper::FractionStyle,
     },
     tui::{
         choice_popup::{self, ChoicePopup},
@@ -39,14 +47,34 @@ pub struct App {
     pub recipes: HashMap<Uuid, Recipe>,
     /// either a new recipe, or a clone of the recipe that is currently being edited
     pub edit_recipe: Option<Recipe>,
+    /// a possibly scaled copy of the recipe that is currently being viewed
+    pub view_recipe: Option<Recipe>,
+    /// the factor `view_recipe` has been scaled by
+    pub view_scale: Rational64,
     /// the current screen the application is on
     pub current_screen: CurrentScreen,
     /// editing flag, indicating which recipe you are editing. Not used for creating new recipes
     pub editing: Option<usize>,
     /// running flag
     pub running: bool,
-    /// tag list
-    pub tags: Vec<Tag>,
+    /// tag list, grouped by namespace
+    pub tags: TagGroups,
+    /// ingredient catalog used to combine ingredient amounts in different units
+    pub ingredient_catalog: IngredientCatalog,
+    /// record of when recipes were made
+    pub cook_log: CookLog,
+    /// order of the recipe list when browsing
+    pub browse_sort: RecipeSort,
+    /// only list recipes that meet this dietary requirement when browsing
+    pub browse_diet: Option<DietaryFilter>,
+    /// which equipment is owned
+    pub equipment_inventory: EquipmentInventory,
+    /// how quantities, temperatures, times and numbers are shown when viewing recipes
+    pub display: DisplayPreferences,
+    /// only list recipes that can be made with owned equipment when browsing
+    pub browse_owned_only: bool,
+    /// directory the recipes were loaded from, where the cook log is saved
+    pub recipe_dir: Option<PathBuf>,
     /// git repository reference
     pub git_repo: Option<Repository>,
     /// keybinds for app
@@ -112,10 +140,23 @@ impl App {
         Self {
             recipes: HashMap::new(),
             edit_recipe: None,
+            view_recipe: None,
+            view_scale: Rational64::from_integer(1),
             current_screen: CurrentScreen::default(),
             running: false,
             editing: None,
-            tags: Vec::new(),
+            tags: TagGroups::new(),
+            ingredient_catalog: IngredientCatalog::default(),
+            cook_log: CookLog::default(),
+            browse_sort: RecipeSort::default(),
+            browse_diet: None,
+            equipment_inventory: EquipmentInventory::default(),
+            display: DisplayPreferences {
+                fraction_style: FractionStyle::from_locale(),
+                ..Default::default()
+            },
+            browse_owned_only: false,
+            recipe_dir: None,
             git_repo: None,
             keybinds,
             style: style.clone(),
@@ -133,16 +174,24 @@ impl App {
     }
 
     /// `save_recipes_to_file` outputs all recipes contained in app to individual files in the
-    /// specified directory
+    /// specified directory.
+    ///
+    /// A recipe that fails to save doesn't stop the others being saved. The first failure is
+    /// returned once every recipe has been tried
     pub fn save_recipes_to_directory(&self, dir: &Path) -> anyhow::Result<()> {
         if dir.is_dir() {
             if !self.recipes.is_empty() {
+                let mut result = Ok(());
                 for recipe in self.recipes.values() {
-                    let mut path = dir.join(recipe.name.replace(' ', "_"));
-                    _ = path.set_extension("toml");
-                    Recipe::write_recipe(recipe.clone(), path.as_path())?
+                    let path = dir.join(recipe.file_name());
+                    if let Err(e) = Recipe::write_recipe(recipe.clone(), path.as_path()) {
+                        warn! {"saving recipe {} to {} failed: {e}", recipe.id, path.display()};
+                        if result.is_ok() {
+                            result = Err(e);
+                        }
+                    }
                 }
-                Ok(())
+                result
             } else {
                 // no recipes loaded
                 //TODO: log this
@@ -156,6 +205,21 @@ impl App {
         }
     }
 
+    /// `browse_list` returns the recipes in the order they are shown in the recipe list, leaving
+    /// out any that don't meet the dietary requirement being browsed, or need equipment that isn't
+    /// owned if only owned equipment is being browsed
+    #[must_use]
+    pub fn browse_list(&self) -> Vec<&Recipe> {
+        let mut recipes = self.cook_log.sort_recipes(&self.recipes, self.browse_sort);
+        if let Some(filter) = self.browse_diet {
+            recipes.retain(|r| r.dietary(&self.ingredient_catalog).satisfies(filter));
+        }
+        if self.browse_owned_only {
+            recipes.retain(|r| r.all_equipment_owned(&self.equipment_inventory));
+        }
+        recipes
+    }
+
     /// `tick` handles the tick event of the app
     pub fn tick(&self) {
         //TODO: investigate this further
@@ -175,10 +239,12 @@ impl App {
         //actually render everything at once, at the bottom of this function
         let mut recipe_list_items = Vec::<ListItem>::new();
 
-        if self.recipes.is_empty() {
+        let browse_list = self.browse_list();
+        // also covers all recipes being filtered out, so the list is never empty when scrolling
+        if browse_list.is_empty() {
             recipe_list_items.push(ListItem::new(Line::from(Span::styled("No Recipes", self.style.missing_text))));
         } else {
-            for recipe in self.recipes.values() {
+            for recipe in browse_list {
                 recipe_list_items.push(ListItem::new(Line::from(Span::styled(
                     recipe.name.clone(),
                     self.style.recipe_list_entries,
@@ -186,7 +252,14 @@ impl App {
             }
         }
 
-        let recipe_list = List::new(recipe_list_items).block(Block::default().borders(Borders::ALL).title("Recipe List"));
+        let mut recipe_list_title = format!("Recipe List by {}", self.browse_sort);
+        if let Some(filter) = self.browse_diet {
+            recipe_list_title.push_str(format!(", {filter}").as_str());
+        }
+        if self.browse_owned_only {
+            recipe_list_title.push_str(", my equipment");
+        }
+        let recipe_list = List::new(recipe_list_items).block(Block::default().borders(Borders::ALL).title(recipe_list_title));
         state.recipe_list_len = recipe_list.len();
 
         let mut tag_list = List::default();
@@ -213,8 +286,23 @@ impl App {
                 if self.tags.is_empty() {
                     tag_list_items.push(ListItem::new(Line::from(Span::styled("No Tags", self.style.missing_text))));
                 } else {
-                    for tag in &self.tags {
-                        tag_list_items.push(ListItem::new(Line::from(Span::styled(tag, self.style.tag_list_entries))));
+                    for (namespace, group) in &self.tags {
+                        // tags without a namespace are listed first, without a heading
+                        let indent = if let Some(namespace) = namespace {
+                            tag_list_items.push(ListItem::new(Line::from(Span::styled(
+                                format!("{namespace}:"),
+                                self.style.tag_list_namespaces,
+                            ))));
+                            "  "
+                        } else {
+                            ""
+                        };
+                        for tag in group {
+                            tag_list_items.push(ListItem::new(Line::from(Span::styled(
+                                format!("{indent}{}", tag.name),
+                                self.style.tag_list_entries,
+                            ))));
+                        }
                     }
                 }
 
@@ -226,10 +314,21 @@ impl App {
                     Span::raw(" | "),
                     Span::styled(format!("{}", self.keybinds.browsing.new), self.style.keyboard_shortcut_text),
                     Span::raw(" | "),
+                    Span::styled(format!("{}", self.keybinds.browsing.view), self.style.keyboard_shortcut_text),
+                    Span::raw(" | "),
                     Span::styled(
                         format!("{}", self.keybinds.browsing.recipe_scroll),
                         self.style.keyboard_shortcut_text,
                     ),
+                    Span::raw(" | "),
+                    Span::styled(format!("{}", self.keybinds.browsing.sort), self.style.keyboard_shortcut_text),
+                    Span::raw(" | "),
+                    Span::styled(format!("{}", self.keybinds.browsing.diet), self.style.keyboard_shortcut_text),
+                    Span::raw(" | "),
+                    Span::styled(
+                        format!("{}", self.keybinds.browsing.owned_equipment),
+                        self.style.keyboard_shortcut_text,
+                    ),
                 ];
 
                 //TODO: use fmt/display of recipe here to display a preview as folks are scrolling
@@ -241,15 +340,36 @@ impl App {
                 current_keybind_text.push(Line::from_iter(browser_kb_text));
             }
             CurrentScreen::RecipeViewer => {
-                //TODO: set title paragraph to name of viewing recipe.
-                //title_paragraph = Paragraph::new(Text::styled(recipe.name.clone(), self.style.view_title_text)).block(title_block);
+                if let Some(recipe) = &self.view_recipe {
+                    title_paragraph =
+                        Paragraph::new(Text::styled(recipe.name.clone(), self.style.view_title_text)).block(title_block);
+                }
                 //TODO: only show tags associated with recipe
-                status_paragraph = Paragraph::new(Text::styled("Viewing", self.style.viewing_status)).block(status_block);
+                status_paragraph = Paragraph::new(Text::styled(
+                    format!("Viewing. Scaled \u{d7}{}", self.display.number(self.view_scale)),
+                    self.style.viewing_status,
+                ))
+                .block(status_block);
                 //TODO: update this once keybinds for viewer are finished
                 let viewer_kb_text = vec![
                     Span::styled(format!("{}", self.keybinds.viewing.exit), self.style.keyboard_shortcut_text),
-                    //Span::styled(format!("{}", self.keybinds.browsing.quit), self.style.keyboard_shortcut_text),
-                    //Span::styled(format!("{}", self.keybinds.browsing.quit), self.style.keyboard_shortcut_text),
+                    Span::raw(" | "),
+                    Span::styled(format!("{}", self.keybinds.viewing.scale), self.style.keyboard_shortcut_text),
+                    Span::raw(" | "),
+                    Span::styled(
+                        format!("{}", self.keybinds.viewing.reset_scale),
+                        self.style.keyboard_shortcut_text,
+                    ),
+                    Span::raw(" | "),
+                    Span::styled(
+                        format!("{}", self.keybinds.viewing.save_scaled),
+                        self.style.keyboard_shortcut_text,
+                    ),
+                    Span::raw(" | "),
+                    Span::styled(
+                        format!("{}", self.keybinds.viewing.mark_cooked),
+                        self.style.keyboard_shortcut_text,
+                    ),
                 ];
                 // keybind area height should never be larger than half of the total height of the
                 // screen
@@ -424,13 +544,11 @@ impl App {
             CurrentScreen::RecipeViewer => {
                 //TODO use actual render widget methods here
                 StatefulWidget::render(tag_list, tag_list_area, frame.buffer_mut(), &mut state.tag_list_state);
-                if !self.recipes.is_empty() {
-                    //TODO: fix this state lookup, after switching to hashmap of recipes
-                    //WidgetRef::render_ref(
-                    //    &self.recipes[state.recipe_list_state.selected().unwrap_or_default()],
-                    //    recipe_area,
-                    //    frame.buffer_mut(),
-                    //);
+                if let Some(recipe) = &self.view_recipe {
+                    Paragraph::new(Text::from_iter(self.recipe_viewer_text(recipe)))
+                        .block(Block::default().borders(Borders::ALL))
+                        .wrap(Wrap { trim: true })
+                        .render(recipe_area, frame.buffer_mut());
                 } else {
                     clear.render(recipe_area, frame.buffer_mut());
                 }
@@ -561,6 +679,153 @@ impl App {
 
         status_paragraph.render(status_area, frame.buffer_mut());
     }
+
+    /// `recipe_viewer_text` returns the lines of text used to display a recipe in
+    /// [`CurrentScreen::RecipeViewer`]
+    fn recipe_viewer_text<'a>(&self, recipe: &'a Recipe) -> Vec<Line<'a>> {
+        let mut lines = Vec::new();
+        lines.push(Line::from(Span::styled(
+            recipe.amount_made.readable(self.display.fraction_style),
+            self.style.normal_text,
+        )));
+        lines.push(Line::from(Span::styled(
+            format!("Dietary: {}", recipe.dietary(&self.ingredient_catalog)),
+            self.style.normal_text,
+        )));
+        lines.push(Line::from(Span::styled(
+            format!(
+                "Elapsed time: {}, total effort: {}",
+                self.display.time_range(&recipe.elapsed_time()),
+                self.display.time_range(&recipe.total_time())
+            ),
+            self.style.normal_text,
+        )));
+        if let Some(description) = &recipe.description {
+            lines.push(Line::from(Span::styled(description.as_str(), self.style.normal_text)));
+        }
+        lines.push(Line::default());
+        lines.push(Line::from(Span::styled("Ingredients", self.style.view_title_text)));
+        let options = IngredientListOptions {
+            catalog: Some(&self.ingredient_catalog),
+            ..Default::default()
+        };
+        for ingredient in recipe.ingredient_list(&options) {
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "{}: {}{}{}",
+                    ingredient.name,
+                    self.display.quantity(&ingredient.unit_quantity),
+                    self.display.alternatives(ingredient),
+                    ingredient.optional_text()
+                ),
+                self.style.normal_text,
+            )));
+        }
+        lines.push(Line::default());
+        lines.push(Line::from(Span::styled("Steps", self.style.view_title_text)));
+        for (i, step) in recipe.steps.iter().enumerate() {
+            let time = step
+                .time_needed
+                .map(|t| format!(" ({})", self.display.time_range(&t)))
+                .unwrap_or_default();
+            let temperature = step
+                .temperature
+                .map(|t| format!(" at {}", self.display.temperature(t, step.temperature_unit.as_deref())))
+                .unwrap_or_default();
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "{}. {}{time}{temperature}: {}",
+                    i.saturating_add(1),
+                    step.step_type,
+                    step.instructions
+                ),
+                self.style.normal_text,
+            )));
+            for ingredient in &step.ingredients {
+                lines.push(Line::from(Span::styled(
+                    format!(
+                        "   {}: {}{}{}{}",
+                        ingredient.name,
+                        self.display.quantity(&ingredient.unit_quantity),
+                        ingredient.preparation_text(),
+                        self.display.alternatives(ingredient),
+                        ingredient.optional_text()
+                    ),
+                    self.style.normal_text,
+                )));
+            }
+            if let Some(sub_recipe) = &step.sub_recipe {
+                lines.push(Line::from(Span::styled(
+                    format!(
+                        "   Uses {} of {}",
+                        sub_recipe.quantity_text(self.display.fraction_style),
+                        sub_recipe.name()
+                    ),
+                    self.style.normal_text,
+                )));
+            }
+            for photo in &step.photos {
+                lines.push(Line::from(Span::styled(format!("   Photo: {photo}"), self.style.normal_text)));
+            }
+        }
+        // images can't be shown in the terminal, so list the files to open separately
+        if !recipe.photos.is_empty() {
+            lines.push(Line::default());
+            lines.push(Line::from(Span::styled("Photos", self.style.view_title_text)));
+            for photo in &recipe.photos {
+                lines.push(Line::from(Span::styled(photo.to_string(), self.style.normal_text)));
+            }
+        }
+        match recipe.nutrition(&self.ingredient_catalog) {
+            Ok(nutrition) if nutrition.total != Nutrition::default() => {
+                lines.push(Line::default());
+                lines.push(Line::from(Span::styled(
+                    "Nutrition (per serving / total)",
+                    self.style.view_title_text,
+                )));
+                let per_serving_rows = nutrition.per_serving.map(|n| n.rows());
+                for (i, (name, total)) in nutrition.total.rows().into_iter().enumerate() {
+                    let per_serving = per_serving_rows
+                        .as_ref()
+                        .and_then(|rows| rows.get(i))
+                        .map_or("-", |(_, amount)| amount.as_str());
+                    lines.push(Line::from(Span::styled(
+                        format!("{name}: {per_serving} / {total}"),
+                        self.style.normal_text,
+                    )));
+                }
+                if !nutrition.missing.is_empty() {
+                    lines.push(Line::from(Span::styled(
+                        format!("Not included, missing nutrition facts: {}", nutrition.missing.join(", ")),
+                        self.style.normal_text,
+                    )));
+                }
+                if !nutrition.unconverted.is_empty() {
+                    lines.push(Line::from(Span::styled(
+                        format!(
+                            "Not included, amount could not be converted: {}",
+                            nutrition.unconverted.join(", ")
+                        ),
+                        self.style.normal_text,
+                    )));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => debug! {"calculating nutrition of recipe {} failed: {e}", recipe.id},
+        }
+        let history = self.cook_log.history(&recipe.id);
+        if !history.is_empty() {
+            lines.push(Line::default());
+            lines.push(Line::from(Span::styled(
+                format!("Cooked {} times", history.len()),
+                self.style.view_title_text,
+            )));
+            for entry in history.iter().rev() {
+                lines.push(Line::from(Span::styled(entry.to_string(), self.style.normal_text)));
+            }
+        }
+        lines
+    }
 }
 
 /// [`State`] represents the main state of the application. It holds all states for subparts of
@@ -1,12 +1,15 @@
 use std::num::{Saturating, Wrapping};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
-use log::{debug, trace};
-use num_traits::FromPrimitive;
+use log::{debug, trace, warn};
+use num_rational::Rational64;
+use num_traits::{CheckedDiv, CheckedMul, FromPrimitive};
 use ranged_wrapping::RangedWrapping;
 
 use crate::{
     datatypes::{
+        cook_log::CookLogEntry,
+        dietary::DietaryFilter,
         equipment::{Equipment, EquipmentFields},
         ingredient::{Ingredient, IngredientFields},
         recipe::{Recipe, RecipeFields},
@@ -57,6 +60,22 @@ pub fn handle_key_events(app: &mut App, app_state: &mut app::State, key_event: K
                 app_state.editing_state = EditingState::Recipe;
                 debug! {"changing CurrentScreen to RecipeCreator"}
                 app.current_screen = CurrentScreen::RecipeCreator;
+            } else if key_event.code == app.keybinds.browsing.view.key
+                && key_event.modifiers == app.keybinds.browsing.view.modifiers
+            {
+                trace! {"key {} pressed with modifiers: {}", key_event.code, key_event.modifiers}
+                // recipe list is drawn in the order of app.browse_list()
+                let selected_recipe = app_state
+                    .recipe_list_state
+                    .selected()
+                    .and_then(|selected| app.browse_list().get(selected).map(|r| (*r).clone()));
+                if let Some(recipe) = selected_recipe {
+                    debug! {"viewing recipe {}", recipe.id}
+                    app.view_recipe = Some(recipe);
+                    app.view_scale = Rational64::from_integer(1);
+                    debug! {"changing CurrentScreen to RecipeViewer"}
+                    app.current_screen = CurrentScreen::RecipeViewer;
+                }
             } else if key_event.code == app.keybinds.browsing.recipe_scroll.keybinds["recipe_scroll_down"].key
                 && key_event.modifiers == app.keybinds.browsing.recipe_scroll.keybinds["recipe_scroll_down"].modifiers
             {
@@ -78,6 +97,33 @@ pub fn handle_key_events(app: &mut App, app_state: &mut app::State, key_event: K
                         ((Wrapping(selected) - Wrapping(1_usize)).0) % (app_state.recipe_list_len),
                     ));
                 }
+            } else if key_event.code == app.keybinds.browsing.sort.key
+                && key_event.modifiers == app.keybinds.browsing.sort.modifiers
+            {
+                trace! {"key {} pressed with modifiers: {}", key_event.code, key_event.modifiers}
+                app.browse_sort = app.browse_sort.next();
+                debug! {"sorting recipe list by {}", app.browse_sort}
+            } else if key_event.code == app.keybinds.browsing.diet.key
+                && key_event.modifiers == app.keybinds.browsing.diet.modifiers
+            {
+                trace! {"key {} pressed with modifiers: {}", key_event.code, key_event.modifiers}
+                // cycle through each dietary requirement, then back to showing all recipes
+                let filters = DietaryFilter::all();
+                app.browse_diet = match app.browse_diet.and_then(|current| filters.iter().position(|f| *f == current)) {
+                    Some(i) => filters.get(i.saturating_add(1)).copied(),
+                    None => filters.first().copied(),
+                };
+                // the filtered list may be shorter than the current selection
+                app_state.recipe_list_state.select(Some(0));
+                debug! {"filtering recipe list by {:?}", app.browse_diet}
+            } else if key_event.code == app.keybinds.browsing.owned_equipment.key
+                && key_event.modifiers == app.keybinds.browsing.owned_equipment.modifiers
+            {
+                trace! {"key {} pressed with modifiers: {}", key_event.code, key_event.modifiers}
+                app.browse_owned_only = !app.browse_owned_only;
+                // the filtered list may be shorter than the current selection
+                app_state.recipe_list_state.select(Some(0));
+                debug! {"only listing recipes that can be made with owned equipment: {}", app.browse_owned_only}
             }
         }
         CurrentScreen::RecipeViewer => {
@@ -86,8 +132,72 @@ pub fn handle_key_events(app: &mut App, app_state: &mut app::State, key_event: K
             // only match on constant values, and not variables for 'some' reason...
             if key_event.code == app.keybinds.viewing.exit.key && key_event.modifiers == app.keybinds.viewing.exit.modifiers {
                 trace! {"key {} pressed with modifiers: {}", key_event.code, key_event.modifiers}
+                app.view_recipe = None;
                 debug! {"changing CurrentScreen to RecipeBrowser"}
                 app.current_screen = CurrentScreen::RecipeBrowser
+            } else if key_event.code == app.keybinds.viewing.scale.keybinds["scale_up"].key
+                && key_event.modifiers == app.keybinds.viewing.scale.keybinds["scale_up"].modifiers
+            {
+                trace! {"key {} pressed with modifiers: {}", key_event.code, key_event.modifiers}
+                // ignore the key press once the scale can't be represented any more
+                if let Some(scale) = app.view_scale.checked_mul(&Rational64::from_integer(2)) {
+                    scale_viewed_recipe(app, scale);
+                }
+            } else if key_event.code == app.keybinds.viewing.scale.keybinds["scale_down"].key
+                && key_event.modifiers == app.keybinds.viewing.scale.keybinds["scale_down"].modifiers
+            {
+                trace! {"key {} pressed with modifiers: {}", key_event.code, key_event.modifiers}
+                // ignore the key press once the scale can't be represented any more
+                if let Some(scale) = app.view_scale.checked_div(&Rational64::from_integer(2)) {
+                    scale_viewed_recipe(app, scale);
+                }
+            } else if key_event.code == app.keybinds.viewing.reset_scale.key
+                && key_event.modifiers == app.keybinds.viewing.reset_scale.modifiers
+            {
+                trace! {"key {} pressed with modifiers: {}", key_event.code, key_event.modifiers}
+                scale_viewed_recipe(app, Rational64::from_integer(1));
+            } else if key_event.code == app.keybinds.viewing.save_scaled.key
+                && key_event.modifiers == app.keybinds.viewing.save_scaled.modifiers
+            {
+                trace! {"key {} pressed with modifiers: {}", key_event.code, key_event.modifiers}
+                if let Some(view_recipe) = &app.view_recipe
+                    && let Some(recipe) = app.recipes.get(&view_recipe.id)
+                {
+                    //TODO: show this error in the status bar once it exists
+                    // the copy is saved to its own file straight away, so it can't replace an existing
+                    // recipe file when all recipes are saved
+                    let saved = recipe.new_scaled_copy(app.view_scale).and_then(|scaled_recipe| {
+                        if let Some(dir) = &app.recipe_dir {
+                            _ = Recipe::write_new_recipe(scaled_recipe.clone(), dir)?;
+                        }
+                        Ok(scaled_recipe)
+                    });
+                    match saved {
+                        Ok(scaled_recipe) => {
+                            debug! {"saving scaled copy of recipe {} as {}", recipe.id, scaled_recipe.id}
+                            app.view_recipe = Some(scaled_recipe.clone());
+                            app.view_scale = Rational64::from_integer(1);
+                            app.recipes.insert(scaled_recipe.id, scaled_recipe);
+                        }
+                        Err(e) => warn! {"saving scaled copy of recipe {} failed: {e}", recipe.id},
+                    }
+                }
+            } else if key_event.code == app.keybinds.viewing.mark_cooked.key
+                && key_event.modifiers == app.keybinds.viewing.mark_cooked.modifiers
+            {
+                trace! {"key {} pressed with modifiers: {}", key_event.code, key_event.modifiers}
+                if let Some(view_recipe) = &app.view_recipe {
+                    debug! {"marking recipe {} as cooked", view_recipe.id}
+                    //TODO: prompt for cook, rating and notes once there is a text entry popup
+                    //TODO: show these errors in the status bar once it exists
+                    if let Err(e) = app.cook_log.record(view_recipe.id, CookLogEntry::new(app.view_scale)) {
+                        warn! {"marking recipe {} as cooked failed: {e}", view_recipe.id}
+                    } else if let Some(dir) = &app.recipe_dir
+                        && let Err(e) = app.cook_log.write_to_directory(dir)
+                    {
+                        warn! {"saving cook log failed: {e}"}
+                    }
+                }
             }
         }
         // TODO: finish implementing keybinds, want similar to VIM, but maybe hybrid of VIM and
@@ -996,6 +1106,11 @@ pub fn handle_key_events(app: &mut App, app_state: &mut app::State, key_event: K
                                     // existing key
                                     app.recipes.insert(edit_key, app.edit_recipe.clone().unwrap());
                                     app.edit_recipe = None;
+                                    // update the copies of this recipe used as a sub-recipe in
+                                    // other recipes
+                                    if let Err(e) = Recipe::resolve_sub_recipes(&mut app.recipes) {
+                                        warn! {"resolving sub-recipes after editing recipe {edit_key} failed: {e}"}
+                                    }
                                 }
                             }
                             // No
@@ -1018,3 +1133,21 @@ pub fn handle_key_events(app: &mut App, app_state: &mut app::State, key_event: K
         }
     }
 }
+
+/// `scale_viewed_recipe` replaces the recipe being viewed with a copy of the original recipe
+/// scaled by `scale`. The viewed recipe is left as is if scaling fails.
+fn scale_viewed_recipe(app: &mut App, scale: Rational64) {
+    if let Some(view_recipe) = &app.view_recipe
+        && let Some(recipe) = app.recipes.get(&view_recipe.id)
+    {
+        //TODO: show this error in the status bar once it exists
+        match recipe.scale(scale) {
+            Ok(scaled_recipe) => {
+                debug! {"scaling recipe {} by {scale}", recipe.id}
+                app.view_recipe = Some(scaled_recipe);
+                app.view_scale = scale;
+            }
+            Err(e) => warn! {"scaling recipe {} by {scale} failed: {e}", recipe.id},
+        }
+    }
+}
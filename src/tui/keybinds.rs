@@ -18,7 +18,15 @@ pub struct Keybinds {
 pub struct BrowsingKeybinds {
     pub quit: KeybindDefinition,
     pub new: KeybindDefinition,
+    /// view the selected recipe
+    pub view: KeybindDefinition,
     pub recipe_scroll: KeybindGroup,
+    /// change the order of the recipe list
+    pub sort: KeybindDefinition,
+    /// change the dietary requirement the recipe list is filtered by
+    pub diet: KeybindDefinition,
+    /// only list recipes that can be made with owned equipment
+    pub owned_equipment: KeybindDefinition,
 }
 
 impl Default for BrowsingKeybinds {
@@ -36,6 +44,12 @@ impl Default for BrowsingKeybinds {
                 instructional_text: "new".to_owned(),
                 display_text: "n".to_owned(),
             },
+            view: KeybindDefinition {
+                key: KeyCode::Enter,
+                modifiers: KeyModifiers::NONE,
+                instructional_text: "view recipe".to_owned(),
+                display_text: "\u{21B5}".to_owned(),
+            },
             recipe_scroll: KeybindGroup {
                 instructional_text: "scroll to select recipe".to_owned(),
                 display_text: "\u{2195}".to_owned(),
@@ -60,6 +74,24 @@ impl Default for BrowsingKeybinds {
                     ),
                 ]),
             },
+            sort: KeybindDefinition {
+                key: KeyCode::Char('o'),
+                modifiers: KeyModifiers::NONE,
+                instructional_text: "change recipe order".to_owned(),
+                display_text: "o".to_owned(),
+            },
+            diet: KeybindDefinition {
+                key: KeyCode::Char('d'),
+                modifiers: KeyModifiers::NONE,
+                instructional_text: "filter by dietary need".to_owned(),
+                display_text: "d".to_owned(),
+            },
+            owned_equipment: KeybindDefinition {
+                key: KeyCode::Char('m'),
+                modifiers: KeyModifiers::NONE,
+                instructional_text: "only recipes I can make".to_owned(),
+                display_text: "m".to_owned(),
+            },
         }
     }
 }
@@ -295,6 +327,14 @@ impl Default for EditingKeybinds {
 pub struct ViewingKeybinds {
     pub exit: KeybindDefinition,
     // scroll through entire recipe, go to previous/next step
+    /// double or halve the size of the viewed recipe
+    pub scale: KeybindGroup,
+    /// return the viewed recipe to its original size
+    pub reset_scale: KeybindDefinition,
+    /// save the scaled recipe as a new recipe
+    pub save_scaled: KeybindDefinition,
+    /// record that the recipe was made today at the current scale
+    pub mark_cooked: KeybindDefinition,
 }
 
 impl Default for ViewingKeybinds {
@@ -306,6 +346,48 @@ impl Default for ViewingKeybinds {
                 instructional_text: "Return to Browsing".to_owned(),
                 display_text: "ESC".to_owned(),
             },
+            scale: KeybindGroup {
+                instructional_text: "double or halve recipe".to_owned(),
+                display_text: "+ || -".to_owned(),
+                keybinds: HashMap::from([
+                    (
+                        "scale_up".to_owned(),
+                        KeybindDefinition {
+                            key: KeyCode::Char('+'),
+                            modifiers: KeyModifiers::NONE,
+                            instructional_text: "double recipe".to_owned(),
+                            display_text: "+".to_owned(),
+                        },
+                    ),
+                    (
+                        "scale_down".to_owned(),
+                        KeybindDefinition {
+                            key: KeyCode::Char('-'),
+                            modifiers: KeyModifiers::NONE,
+                            instructional_text: "halve recipe".to_owned(),
+                            display_text: "-".to_owned(),
+                        },
+                    ),
+                ]),
+            },
+            reset_scale: KeybindDefinition {
+                key: KeyCode::Char('r'),
+                modifiers: KeyModifiers::NONE,
+                instructional_text: "Reset recipe scale".to_owned(),
+                display_text: "r".to_owned(),
+            },
+            save_scaled: KeybindDefinition {
+                key: KeyCode::Char('s'),
+                modifiers: KeyModifiers::NONE,
+                instructional_text: "Save scaled copy as new recipe".to_owned(),
+                display_text: "s".to_owned(),
+            },
+            mark_cooked: KeybindDefinition {
+                key: KeyCode::Char('c'),
+                modifiers: KeyModifiers::NONE,
+                instructional_text: "Mark recipe as cooked".to_owned(),
+                display_text: "c".to_owned(),
+            },
         }
     }
 }
@@ -12,6 +12,7 @@ pub struct Style {
     pub edit_title_text: TUIStyle,
     pub recipe_list_entries: TUIStyle,
     pub tag_list_entries: TUIStyle,
+    pub tag_list_namespaces: TUIStyle,
     pub keyboard_shortcut_text: TUIStyle,
     pub status_block: TUIStyle,
     pub browsing_status: TUIStyle,
@@ -42,6 +43,7 @@ impl Default for Style {
             edit_title_text: base_text_style.blue(),
             recipe_list_entries: base_list_style.green(),
             tag_list_entries: base_list_style.white(),
+            tag_list_namespaces: base_list_style.white().bold(),
             keyboard_shortcut_text: base_text_style.white(),
             status_block: base_block_style,
             browsing_status: base_text_style.green(),
@@ -11,28 +11,61 @@ use tiny_http::{
 };
 use uuid::Uuid;
 
-use crate::datatypes::{recipe::Recipe, tag::Tag};
+use crate::datatypes::{
+    cook_log::{CookLog, RecipeSort},
+    dietary::DietaryFilter,
+    recipe::Recipe,
+    tag::TagGroups,
+};
 
 use super::{html_stubs::FOOTER, http_helper};
 
+/// prefix of the names of the dietary requirement checkboxes in the browser form, followed by the
+/// name of the [`DietaryFilter`]
+pub const DIET_FIELD_PREFIX: &str = "diet_";
+
+/// name of the checkbox in the browser form to only list recipes that can be made with owned
+/// equipment
+pub const OWNED_EQUIPMENT_FIELD: &str = "owned_equipment";
+
+/// `diet_filters` returns the dietary requirements selected in the browser form
+#[must_use]
+pub fn diet_filters(form_data: &HashMap<String, String>) -> Vec<DietaryFilter> {
+    DietaryFilter::all()
+        .into_iter()
+        .filter(|filter| form_data.contains_key(&format!("{DIET_FIELD_PREFIX}{}", filter.name())))
+        .collect()
+}
+
 /// `browser` returns the recipe browser page for the web server.
 ///
 /// This is the main page for the Cookbook. This page allows users to select a specific recipe
 /// or filter the recipe list via selecting tags.
-pub fn browser(recipes: HashMap<Uuid, Recipe>, tags: &[Tag]) -> anyhow::Result<Response<Box<dyn Read + Send>>> {
+///
+/// The recipe list is ordered by `sort`, using `cook_log` for the last cooked and most cooked
+/// orders. Tags are grouped by namespace, with tags that have no namespace listed first.
+/// Dietary requirements in `diets` and `owned_only` are shown as already selected.
+pub fn browser(
+    recipes: HashMap<Uuid, Recipe>,
+    tags: &TagGroups,
+    diets: &[DietaryFilter],
+    owned_only: bool,
+    cook_log: &CookLog,
+    sort: RecipeSort,
+) -> anyhow::Result<Response<Box<dyn Read + Send>>> {
     let page_len = 25;
     let mut headers = HeaderMap::with_capacity(2);
     headers.append(header::CONTENT_TYPE, HeaderValue::try_from("text/html; charset=utf-8")?);
 
     let mut recipe_list = String::new();
     let mut tag_list = String::new();
+    let mut sort_list = String::new();
+    let mut diet_list = String::new();
 
     if recipes.is_empty() {
         recipe_list.push_str("<option value=\"-1\">No Recipes Loaded</option>\n");
     } else {
-        let mut recipes_sorted = recipes.values().collect::<Vec<_>>();
-        recipes_sorted.sort_unstable_by_key(|k| k.name.clone());
-        for recipe in recipes_sorted {
+        for recipe in cook_log.sort_recipes(&recipes, sort) {
             let recipe_name = http_helper::html_escape(&recipe.name);
             let recipe_id = if !recipe.id.is_nil() {
                 recipe.id
@@ -40,24 +73,63 @@ pub fn browser(recipes: HashMap<Uuid, Recipe>, tags: &[Tag]) -> anyhow::Result<R
                 // don't want to list recipes without IDs
                 continue;
             };
-            recipe_list.push_str(format!("<option value=\"{recipe_id}\">{recipe_name}</option>\n").as_str());
+            let cooked = match sort {
+                RecipeSort::LastCooked => match cook_log.last_cooked(&recipe_id) {
+                    Some(date) => format!(" (last cooked {date})"),
+                    None => " (never cooked)".to_owned(),
+                },
+                RecipeSort::MostCooked => format!(" (cooked {} times)", cook_log.times_cooked(&recipe_id)),
+                _ => String::new(),
+            };
+            recipe_list.push_str(format!("<option value=\"{recipe_id}\">{recipe_name}{cooked}</option>\n").as_str());
         }
     }
 
     if tags.is_empty() {
         tag_list.push_str("<option value=\"-1\">No Tags Loaded</option>");
     } else {
-        for (i, tag) in tags.iter().map(|t| http_helper::html_escape(t)).enumerate() {
-            tag_list.push_str(format!("<option value=\"{i}\">{tag}</option>").as_str());
+        for (namespace, group) in tags {
+            if let Some(namespace) = namespace {
+                tag_list.push_str(format!("<optgroup label=\"{}\">", http_helper::html_escape(namespace)).as_str());
+            }
+            for tag in group {
+                tag_list.push_str(
+                    format!(
+                        "<option value=\"{}\">{}</option>",
+                        http_helper::html_escape(&tag.to_string()),
+                        http_helper::html_escape(&tag.name)
+                    )
+                    .as_str(),
+                );
+            }
+            if namespace.is_some() {
+                tag_list.push_str("</optgroup>");
+            }
         }
     }
 
-    let tag_list_size = if tags.len() > page_len {
+    // each requirement gets its own checkbox, so any number of them can be selected
+    for filter in DietaryFilter::all() {
+        let name = filter.name();
+        let checked = if diets.contains(&filter) { " checked" } else { "" };
+        diet_list.push_str(
+            format!("<label><input type=\"checkbox\" name=\"{DIET_FIELD_PREFIX}{name}\"{checked}/>{name}</label>\n").as_str(),
+        );
+    }
+
+    for option in [RecipeSort::Name, RecipeSort::LastCooked, RecipeSort::MostCooked] {
+        let selected = if option == sort { " selected" } else { "" };
+        sort_list.push_str(format!("<option value=\"{}\"{selected}>{option}</option>", option.form_value()).as_str());
+    }
+
+    // each namespace heading takes up a row in the list as well
+    let tag_count = tags.values().map(Vec::len).sum::<usize>() + tags.keys().filter(|n| n.is_some()).count();
+    let tag_list_size = if tag_count > page_len {
         page_len
-    } else if tags.len() < 2 {
+    } else if tag_count < 2 {
         2
     } else {
-        tags.len()
+        tag_count
     };
     let recipe_list_size = if recipes.len() > page_len {
         page_len
@@ -78,6 +150,10 @@ pub fn browser(recipes: HashMap<Uuid, Recipe>, tags: &[Tag]) -> anyhow::Result<R
             favicon = "/favicon.ico",
             tag_list = tag_list,
             recipe_list = recipe_list,
+            sort_list = sort_list,
+            diet_list = diet_list,
+            owned_field = OWNED_EQUIPMENT_FIELD,
+            owned_checked = if owned_only { " checked" } else { "" },
             tag_list_size = tag_list_size,
             recipe_list_size = recipe_list_size
         )
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::io::Read;
 
 use anyhow::anyhow;
 use form_urlencoded::parse;
@@ -13,24 +14,180 @@ pub fn get_content_type(request: &Request) -> Option<String> {
         .map(|s| s.to_owned())
 }
 
+/// `get_cookies()` returns the cookies sent with a [`Request`], keyed by name
+pub fn get_cookies(request: &Request) -> HashMap<String, String> {
+    request
+        .headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|x| x.to_str().ok())
+        .flat_map(|s| s.split(';'))
+        .filter_map(|c| c.split_once('='))
+        .map(|(name, value)| (name.trim().to_owned(), value.trim().trim_matches('"').to_owned()))
+        .collect()
+}
+
+/// how long cookies set by [`cookie()`] are kept by the browser, in seconds
+pub const COOKIE_MAX_AGE: u32 = 365 * 24 * 60 * 60;
+
+/// `cookie()` returns the value of a SET_COOKIE http header that stores `value` in the cookie
+/// `name` for the whole site. `value` must not contain `;`, `,`, `"`, `\` or whitespace
+pub fn cookie(name: &str, value: &str) -> String {
+    format!("{name}={value}; Path=/; Max-Age={COOKIE_MAX_AGE}; SameSite=Lax")
+}
+
+/// largest multipart/form-data request body accepted, to limit the size of uploaded files
+pub const MAX_UPLOAD_SIZE: usize = 32 * 1024 * 1024;
+
+/// `FormFile` is a file uploaded through a multipart/form-data form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormFile {
+    /// name of the form field the file was uploaded through
+    pub name: String,
+    /// name of the file on the uploading computer
+    pub file_name: String,
+    /// content type of the file, as reported by the browser
+    pub content_type: Option<String>,
+    /// contents of the file
+    pub data: Vec<u8>,
+}
+
+/// `parse_post_form_data()` parses form data from POST requests if present. Any uploaded files in
+/// multipart/form-data requests are discarded, use [`parse_post_form`] to keep them.
+pub fn parse_post_form_data(request: &mut Request) -> anyhow::Result<HashMap<String, String>> {
+    Ok(parse_post_form(request)?.0)
+}
+
 // Inspired by the fn raw_urlencoded_post_input from Rouille
 // https://docs.rs/rouille/latest/src/rouille/input/post.rs.html#676
 // This is probably vulnerable to buffer overflows, etc but I can't be arsed to fix that right now.
 // TODO: check and see if request.as_reader() is vulnerable to buffer overflow
 // TODO: check and see if there are multiple values with the same name
-/// `parse_post_form_data()` parses form data from POST requests if present
-pub fn parse_post_form_data(request: &mut Request) -> anyhow::Result<HashMap<String, String>> {
-    if get_content_type(request) == Some("application/x-www-form-urlencoded".to_owned()) {
+/// `parse_post_form()` parses form data and uploaded files from POST requests if present.
+///
+/// Both application/x-www-form-urlencoded and multipart/form-data requests are supported.
+/// Only multipart/form-data requests can contain files.
+pub fn parse_post_form(request: &mut Request) -> anyhow::Result<(HashMap<String, String>, Vec<FormFile>)> {
+    let content_type = get_content_type(request).unwrap_or_default();
+    if content_type == "application/x-www-form-urlencoded" {
         let mut content = String::new();
         request.as_reader().read_to_string(&mut content)?;
-        Ok(parse(content.as_bytes()).into_owned().collect())
-    } else if get_content_type(request) == Some("multipart/form-data".to_owned()) {
-        Err(anyhow!("Requests of type multipart/form-data are not supported currently"))
+        Ok((parse(content.as_bytes()).into_owned().collect(), Vec::new()))
+    } else if let Some(params) = content_type.strip_prefix("multipart/form-data") {
+        let boundary = params
+            .split(';')
+            .filter_map(|p| p.trim().strip_prefix("boundary="))
+            .next()
+            .map(|b| b.trim_matches('"'))
+            .ok_or_else(|| anyhow!("multipart/form-data request missing boundary"))?;
+        if request.body_length().is_some_and(|len| len > MAX_UPLOAD_SIZE) {
+            return Err(anyhow!("Form data larger than {MAX_UPLOAD_SIZE} bytes"));
+        }
+        let mut content = Vec::new();
+        request.as_reader().take(MAX_UPLOAD_SIZE as u64).read_to_end(&mut content)?;
+        parse_multipart(&content, boundary)
     } else {
         Err(anyhow!("No form data found in request"))
     }
 }
 
+/// `parse_multipart()` splits a multipart/form-data body into form fields and uploaded files.
+/// See RFC 7578 for the format.
+fn parse_multipart(body: &[u8], boundary: &str) -> anyhow::Result<(HashMap<String, String>, Vec<FormFile>)> {
+    let delimiter = format!("--{boundary}").into_bytes();
+    let mut fields = HashMap::new();
+    let mut files = Vec::new();
+    let mut rest = match find_bytes(body, &delimiter) {
+        Some(start) => &body[start + delimiter.len()..],
+        None => return Err(anyhow!("multipart/form-data body missing boundary {boundary}")),
+    };
+    // the last delimiter is followed by --
+    while !rest.starts_with(b"--") {
+        let end = find_bytes(rest, &delimiter).ok_or_else(|| anyhow!("multipart/form-data body is incomplete"))?;
+        let part = &rest[..end];
+        rest = &rest[end + delimiter.len()..];
+        // each part starts after the line break following the delimiter, and ends with the line
+        // break before the next delimiter
+        let part = part.strip_prefix(b"\r\n").unwrap_or(part);
+        let part = part.strip_suffix(b"\r\n").unwrap_or(part);
+        let header_end = find_bytes(part, b"\r\n\r\n").ok_or_else(|| anyhow!("multipart/form-data part missing headers"))?;
+        let headers = std::str::from_utf8(&part[..header_end])?;
+        let data = &part[header_end + 4..];
+
+        let mut name = None;
+        let mut file_name = None;
+        let mut content_type = None;
+        for (key, value) in headers.split("\r\n").filter_map(|line| line.split_once(':')) {
+            if key.trim().eq_ignore_ascii_case("content-disposition") {
+                for (param, param_value) in value.split(';').filter_map(|p| p.trim().split_once('=')) {
+                    let param_value = param_value.trim_matches('"').to_owned();
+                    match param {
+                        "name" => name = Some(param_value),
+                        "filename" => file_name = Some(param_value),
+                        _ => {}
+                    }
+                }
+            } else if key.trim().eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.trim().to_owned());
+            }
+        }
+        let name = name.ok_or_else(|| anyhow!("multipart/form-data part missing a name"))?;
+        match file_name {
+            // browsers send a part with an empty file name when no file was chosen
+            Some(file_name) if file_name.is_empty() => {}
+            Some(file_name) => files.push(FormFile {
+                name,
+                file_name,
+                content_type,
+                data: data.to_vec(),
+            }),
+            None => {
+                _ = fields.insert(name, String::from_utf8(data.to_vec())?);
+            }
+        }
+    }
+    Ok((fields, files))
+}
+
+/// `find_bytes()` returns the index of the first occurrence of `needle` in `haystack`
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// `percent_encode_path()` percent encodes `input` for use in the path of a URL. `/` is left as is
+/// so paths keep their structure.
+pub fn percent_encode_path(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~' | b'/') {
+            output.push(char::from(byte));
+        } else {
+            output.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    output
+}
+
+/// `percent_decode()` decodes a percent encoded URL path
+pub fn percent_decode(input: &str) -> anyhow::Result<String> {
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .ok_or_else(|| anyhow!("Incomplete percent encoding in {input}"))?;
+            output.push(u8::from_str_radix(std::str::from_utf8(hex)?, 16)?);
+            i += 3;
+        } else {
+            output.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Ok(String::from_utf8(output)?)
+}
+
 pub fn html_escape(input: &str) -> String {
     input
         .replace("&", "&amp;")
@@ -1,6 +1,7 @@
 use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use anyhow::anyhow;
 use log::trace;
 use tiny_http::{
     Response,
@@ -10,17 +11,37 @@ use tiny_http::{
     },
 };
 
+use crate::datatypes::photo::{self, Photo};
+
+use super::http_helper;
+
+/// URL path that photos are served under, followed by the path of the image file relative to the
+/// cookbook directory
+pub const PHOTO_ROUTE: &str = "/photos/";
+
+/// `content_type` returns the content type of a media file, based on its extension.
+/// Returns `None` if the file is not a supported media type
+#[must_use]
+pub fn content_type(file_path: &Path) -> Option<&'static str> {
+    match file_path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("ico") => Some("image/x-icon"),
+        _ => photo::content_type(file_path),
+    }
+}
+
 // This is based on the Response<File>::from_file() function from tiny_http. I wanted more control
 // and the ability to set the headers before returning the response
 // so I reimplemented it here
-/// `icon` returns a `[Response]` filled with an icon
-pub fn icon(file_path: &Path) -> anyhow::Result<Response<File>> {
+/// `media` returns a `[Response]` filled with a media file, like the favicon or a photo, with the
+/// content type set from the file extension
+pub fn media(file_path: &Path) -> anyhow::Result<Response<File>> {
     trace!("{file_path:?}");
+    let content_type = content_type(file_path).ok_or_else(|| anyhow!("{} is not a supported media type", file_path.display()))?;
     let file = File::open(file_path)?;
     let file_size = file.metadata()?.len();
     let mut headers = HeaderMap::with_capacity(3);
-    headers.append(header::CONTENT_TYPE, HeaderValue::try_from("image/x-icon")?);
-    //TODO: parse file to make sure it is an ICO file.
+    headers.append(header::CONTENT_TYPE, HeaderValue::try_from(content_type)?);
+    //TODO: parse file to make sure its contents match the extension.
 
     Ok(Response::new(
         StatusCode::OK,
@@ -30,3 +51,34 @@ pub fn icon(file_path: &Path) -> anyhow::Result<Response<File>> {
         None,
     ))
 }
+
+/// `photo` returns a `[Response]` filled with the photo at `url_path`, which starts with
+/// [`PHOTO_ROUTE`]. Photos are looked up in the cookbook directory `dir`.
+///
+/// # Errors
+///
+/// Will error if the path leaves the cookbook directory, is not a supported image type, or the
+/// image file can't be read
+pub fn photo(dir: &Path, url_path: &str) -> anyhow::Result<Response<File>> {
+    let file = url_path
+        .strip_prefix(PHOTO_ROUTE)
+        .ok_or_else(|| anyhow!("{url_path} is not a photo path"))?;
+    let file = PathBuf::from(http_helper::percent_decode(file)?);
+    if !Photo::is_valid_path(&file) || photo::content_type(&file).is_none() {
+        return Err(anyhow!("{url_path} is not a photo in the cookbook"));
+    }
+    media(&dir.join(file))
+}
+
+/// `photo_url` returns the URL that `photo` is served at
+#[must_use]
+pub fn photo_url(photo: &Photo) -> String {
+    // URLs always use / between path components
+    let path = photo
+        .file
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("{PHOTO_ROUTE}{}", http_helper::percent_encode_path(&path))
+}
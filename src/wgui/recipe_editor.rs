@@ -10,7 +10,11 @@ use tiny_http::{
 };
 use uuid::Uuid;
 
-use crate::datatypes::recipe::Recipe;
+use crate::datatypes::{
+    photo,
+    recipe::Recipe,
+    unit_registry::{self, UnitKind},
+};
 
 use super::{html_stubs::FOOTER, http_helper};
 
@@ -39,6 +43,64 @@ pub fn recipe_editor(recipe: Recipe) -> anyhow::Result<Response<Box<dyn Read + S
         }
     }
 
+    // photos already attached to the recipe and its steps, and where new photos can be attached
+    let mut photo_list = String::new();
+    let mut photo_targets = String::new();
+    let attached = recipe
+        .photos
+        .iter()
+        .map(|p| ("Finished Recipe".to_owned(), p))
+        .chain(
+            recipe
+                .steps
+                .iter()
+                .enumerate()
+                .flat_map(|(i, s)| s.photos.iter().map(move |p| (format!("Step {i}"), p))),
+        )
+        .collect::<Vec<_>>();
+    if attached.is_empty() {
+        photo_list.push_str("<p>No Photos Attached</p>\n");
+    } else {
+        photo_list.push_str("<ul>\n");
+        for (target, photo) in attached {
+            photo_list.push_str(
+                format!(
+                    "<li>{target}: {}{}</li>\n",
+                    http_helper::html_escape(&photo.file.to_string_lossy()),
+                    photo
+                        .caption
+                        .as_deref()
+                        .map(|c| format!(" ({})", http_helper::html_escape(c)))
+                        .unwrap_or_default()
+                )
+                .as_str(),
+            );
+        }
+        photo_list.push_str("</ul>\n");
+    }
+    for (i, step) in recipe.steps.iter().enumerate() {
+        let step_type = step.step_type;
+        photo_targets.push_str(format!("<option value=\"{i}\">Step {i}: {step_type}</option>\n").as_str());
+    }
+    let photo_types = photo::IMAGE_TYPES.map(|(_, content_type)| content_type).join(",");
+
+    // amounts made can be any word, like loaves, but suggest the mass and volume units so the
+    // recipe can be scaled by weight or volume
+    let mut unit_options = String::new();
+    for unit in [UnitKind::Mass, UnitKind::Volume]
+        .into_iter()
+        .flat_map(unit_registry::units_of)
+    {
+        unit_options.push_str(
+            format!(
+                "<option value=\"{}\">{}</option>\n",
+                http_helper::html_escape(unit.abbreviation),
+                unit.plural
+            )
+            .as_str(),
+        );
+    }
+
     //https://github.com/rust-lang/rust/issues/85846
     let data = format!(
         "{}",
@@ -57,8 +119,12 @@ pub fn recipe_editor(recipe: Recipe) -> anyhow::Result<Response<Box<dyn Read + S
             author = http_helper::html_escape(&recipe.author),
             amount_made_number = recipe.amount_made.quantity,
             amount_made_units = recipe.amount_made.units,
+            unit_options = unit_options,
             num_steps = recipe.steps.len(),
             step_list = step_list,
+            photo_list = photo_list,
+            photo_targets = photo_targets,
+            photo_types = photo_types,
         )
     );
     // Don't fully understand why Box + Cursor, but thats what Rouille used and it seems to work.
@@ -1,6 +1,7 @@
 use std::boxed::Box;
 use std::io::{Cursor, Read};
 
+use num_rational::Rational64;
 use tiny_http::{
     Response,
     http::{
@@ -8,25 +9,57 @@ use tiny_http::{
         status::StatusCode,
     },
 };
-use uom::{
-    fmt::DisplayStyle::{Abbreviation, Description},
-    si::rational64::Time,
-};
+use uom::si::rational64::Time;
 
-use crate::datatypes::{ingredient::UnitType, recipe::Recipe, step::StepType, unit_helper};
+use crate::datatypes::{
+    cook_log::{self, CookLogEntry},
+    display_preferences::DisplayPreferences,
+    equipment_inventory::EquipmentInventory,
+    ingredient_catalog::{IngredientCatalog, Nutrition},
+    photo::Photo,
+    recipe::{IngredientListOptions, Recipe},
+    step::StepType,
+    unit_helper::{FractionStyle, MeasurementSystem, TemperatureScale, TimeFormat},
+};
 
-use super::{html_stubs::FOOTER, http_helper};
+use super::{html_stubs::FOOTER, http_helper, media_responses};
 
 /// `recipe_viewer` returns the recipe browser page for the web server.
 ///
 /// This is the main page for the Cookbook. This page allows users to select a specific recipe
 /// or filter the recipe list via selecting tags.
-pub fn recipe_viewer(recipe: Recipe) -> anyhow::Result<Response<Box<dyn Read + Send>>> {
+///
+/// `scale_factor` is the factor the displayed recipe has already been scaled by, and is used to
+/// prefill the scaling form.
+///
+/// `catalog` is used to combine amounts of the same ingredient given in different units in the
+/// ingredient list.
+///
+/// `inventory` is used to show which equipment is owned.
+///
+/// `cook_history` is the cook log of the recipe, shown below the recipe along with a form to mark
+/// the recipe as cooked.
+///
+/// `display` is how quantities, temperatures, times and numbers are shown. If `remember_display`
+/// is true, `display` is also saved in cookies so it is used for later pages in the same browser.
+pub fn recipe_viewer(
+    recipe: Recipe,
+    scale_factor: Rational64,
+    catalog: &IngredientCatalog,
+    inventory: &EquipmentInventory,
+    cook_history: &[CookLogEntry],
+    display: &DisplayPreferences,
+    remember_display: bool,
+) -> anyhow::Result<Response<Box<dyn Read + Send>>> {
     //let page_len = 25;
-    let mut headers = HeaderMap::with_capacity(2);
+    let mut headers = HeaderMap::with_capacity(6);
     headers.append(header::CONTENT_TYPE, HeaderValue::try_from("text/html; charset=utf-8")?);
+    if remember_display {
+        for (name, value) in display.form_values() {
+            headers.append(header::SET_COOKIE, HeaderValue::try_from(http_helper::cookie(name, value))?);
+        }
+    }
 
-    //TODO: want to be able to change unit based on configuration options and sigfigs
     let is_new_recipe = recipe == Recipe::new();
 
     let recipe_name = if is_new_recipe { "New Recipe" } else { recipe.name.as_str() };
@@ -37,40 +70,43 @@ pub fn recipe_viewer(recipe: Recipe) -> anyhow::Result<Response<Box<dyn Read + S
     if recipe.steps.is_empty() {
         step_list.push_str("<strong>No Steps in Recipe</strong>\n");
     } else {
+        let timeline = recipe.timeline();
         step_list.push_str("<ol>\n");
-        for step in &recipe.steps {
+        for (i, step) in recipe.steps.iter().enumerate() {
             // ingredients
             // equipment
             step_list.push_str("<li>\n");
             step_list.push_str("<section>\n");
             step_list.push_str(&format!("<h3>{}</h3>\n", step.step_type));
+            if let Some(entry) = timeline.iter().find(|e| e.step == i)
+                && entry.start > Time::default()
+            {
+                step_list.push_str(&format!("<p>Starts at: {}</p>\n", display.time(entry.start)));
+            }
             if let Some(time) = step.time_needed {
-                //TODO: fix units
-                //TODO: print this using approximate_float method
-                step_list.push_str(&format!(
-                    "<p>Takes: {}</p>\n",
-                    unit_helper::time_unit_format_output(time, "min", Abbreviation)
-                ));
+                step_list.push_str(&format!("<p>Takes: {}</p>\n", display.time_range(&time)));
             }
             if let Some(temp) = step.temperature {
-                //TODO: fix units
-                //TODO: print this using approximate_float method
                 step_list.push_str(&format!(
                     "<p>Cook at: {}</p>\n",
-                    unit_helper::temp_interval_unit_format_output(temp, "°C", Abbreviation)
+                    display.temperature(temp, step.temperature_unit.as_deref())
                 ));
             }
             if !step.ingredients.is_empty() {
                 step_list.push_str("<ul>");
                 for ingredient in &step.ingredients {
-                    let unit_string = match ingredient.unit_quantity {
-                        UnitType::Quantity(q) => q.to_string(),
-                        //TODO: need to be able to specify which units to use for mass and volume
-                        //TODO: print this using approximate_float method
-                        UnitType::Mass { value: m, unit: _ } => unit_helper::mass_unit_format_output(m, "g", Abbreviation),
-                        UnitType::Volume { value: v, unit: _ } => unit_helper::volume_unit_format_output(v, "m³", Abbreviation),
-                    };
-                    step_list.push_str(format!("<li>{}: {}</li>", ingredient.name, unit_string).as_str());
+                    let unit_string = display.quantity(&ingredient.unit_quantity);
+                    step_list.push_str(
+                        format!(
+                            "<li>{}: {}{}{}{}</li>",
+                            ingredient.name,
+                            unit_string,
+                            ingredient.preparation_text(),
+                            display.alternatives(ingredient),
+                            ingredient.optional_text()
+                        )
+                        .as_str(),
+                    );
                 }
                 step_list.push_str("</ul>");
             }
@@ -81,7 +117,22 @@ pub fn recipe_viewer(recipe: Recipe) -> anyhow::Result<Response<Box<dyn Read + S
                 }
                 step_list.push_str("</ul>");
             }
+            if let Some(sub_recipe) = &step.sub_recipe {
+                // the view-recipe route expects the recipe id from the browse page recipe list
+                step_list.push_str(
+                    format!(
+                        "<form method=\"post\"><p>Uses {} of \
+                        <button formaction=\"/view-recipe\" type=\"submit\" name=\"recipe_list\" value=\"{}\">{}</button>\
+                        </p></form>",
+                        sub_recipe.quantity_text(display.fraction_style),
+                        sub_recipe.id,
+                        sub_recipe.name()
+                    )
+                    .as_str(),
+                );
+            }
             step_list.push_str(format!("<p>{}</p>", step.instructions).as_str());
+            step_list.push_str(&photo_list(&step.photos));
             step_list.push_str("</section>");
             step_list.push_str("</li>");
         }
@@ -90,20 +141,28 @@ pub fn recipe_viewer(recipe: Recipe) -> anyhow::Result<Response<Box<dyn Read + S
 
     // Create ingredient list
     let mut ingredient_list = String::new();
-    if recipe.ingredient_list().is_empty() {
+    let total_ingredients = recipe.ingredient_list(&IngredientListOptions {
+        catalog: Some(catalog),
+        ..Default::default()
+    });
+    if total_ingredients.is_empty() {
         ingredient_list.push_str("<strong>No Ingredients in Recipe</strong>\n");
     } else {
         ingredient_list.push_str("<ul>\n");
-        for ingredient in recipe.ingredient_list() {
+        for ingredient in total_ingredients {
             // TODO: description
-            let unit_string = match ingredient.unit_quantity {
-                UnitType::Quantity(q) => q.to_string(),
-                //TODO: need to be able to specify which units to use for mass and volume
-                //TODO: print this using approximate_float method
-                UnitType::Mass { value: m, unit: _ } => unit_helper::mass_unit_format_output(m, "g", Abbreviation),
-                UnitType::Volume { value: v, unit: _ } => unit_helper::volume_unit_format_output(v, "m³", Abbreviation),
-            };
-            ingredient_list.push_str(format!("<li>{}: {}</li>", ingredient.name, unit_string).as_str());
+            // without a preferred system, totals are shown in the system of the unit the
+            // ingredient was first listed in
+            ingredient_list.push_str(
+                format!(
+                    "<li>{}: {}{}{}</li>",
+                    ingredient.name,
+                    display.quantity(&ingredient.unit_quantity),
+                    display.alternatives(ingredient),
+                    ingredient.optional_text()
+                )
+                .as_str(),
+            );
         }
         ingredient_list.push_str("</ul>\n");
     }
@@ -118,16 +177,65 @@ pub fn recipe_viewer(recipe: Recipe) -> anyhow::Result<Response<Box<dyn Read + S
             // name
             // description
             // is_owned
-            equipment_list.push_str(format!("<li>{}. Owned: {}</li>\n", equipment.name, equipment.is_owned).as_str());
+            equipment_list.push_str(format!("<li>{}. Owned: {}</li>\n", equipment.name, inventory.is_owned(&equipment)).as_str());
         }
         equipment_list.push_str("</ul>\n");
     }
+
+    // Create nutrition panel
+    let mut nutrition_panel = String::new();
+    let nutrition = recipe.nutrition(catalog)?;
+    if nutrition.total == Nutrition::default() {
+        nutrition_panel.push_str("<strong>No Nutrition Facts for Ingredients in Recipe</strong>\n");
+    } else {
+        nutrition_panel.push_str("<table>\n<tr><th>Nutrient</th><th>Per Serving</th><th>Total</th></tr>\n");
+        let per_serving_rows = nutrition.per_serving.map(|n| n.rows());
+        for (i, (name, total)) in nutrition.total.rows().into_iter().enumerate() {
+            let per_serving = per_serving_rows
+                .as_ref()
+                .and_then(|rows| rows.get(i))
+                .map_or("", |(_, amount)| amount.as_str());
+            nutrition_panel.push_str(format!("<tr><td>{name}</td><td>{per_serving}</td><td>{total}</td></tr>\n").as_str());
+        }
+        nutrition_panel.push_str("</table>\n");
+        if !nutrition.missing.is_empty() {
+            nutrition_panel.push_str(
+                format!(
+                    "<p>Not included, missing nutrition facts: {}</p>\n",
+                    http_helper::html_escape(&nutrition.missing.join(", "))
+                )
+                .as_str(),
+            );
+        }
+        if !nutrition.unconverted.is_empty() {
+            nutrition_panel.push_str(
+                format!(
+                    "<p>Not included, amount could not be converted: {}</p>\n",
+                    http_helper::html_escape(&nutrition.unconverted.join(", "))
+                )
+                .as_str(),
+            );
+        }
+    }
+
+    // Create cook log
+    let mut cook_history_list = String::new();
+    if cook_history.is_empty() {
+        cook_history_list.push_str("<strong>Recipe has not been cooked yet</strong>\n");
+    } else {
+        cook_history_list.push_str(format!("<p>Cooked {} times</p>\n<ul>\n", cook_history.len()).as_str());
+        // most recent first
+        for entry in cook_history.iter().rev() {
+            cook_history_list.push_str(format!("<li>{}</li>\n", http_helper::html_escape(&entry.to_string())).as_str());
+        }
+        cook_history_list.push_str("</ul>\n");
+    }
+    let mut rating_list = String::new();
+    for rating in 1..=cook_log::MAX_RATING {
+        rating_list.push_str(format!("<option value=\"{rating}\">{rating}</option>").as_str());
+    }
+
     let step_type_time_totals = recipe.step_time_totals();
-    let prep_time_unit = "min";
-    let cook_time_unit = "min";
-    let wait_time_unit = "min";
-    let other_time_unit = "min";
-    let total_time_unit = "min";
     //https://github.com/rust-lang/rust/issues/85846
     let data = format!(
         "{}",
@@ -144,66 +252,49 @@ pub fn recipe_viewer(recipe: Recipe) -> anyhow::Result<Response<Box<dyn Read + S
             //TODO see if we can detect URLs here and properly format them in html
             source = http_helper::html_escape(&recipe.source),
             author = http_helper::html_escape(&recipe.author),
-            amount_made_number = recipe.amount_made.quantity,
+            amount_made_number = display.number(recipe.amount_made.quantity),
             amount_made_units = recipe.amount_made.units,
-            //TODO: adjust these units depending on total time
-            //TODO: print this using approximate_float method
-            prep_time = unit_helper::time_unit_format_output(
-                {
-                    if let Some(prep_time_total) = step_type_time_totals.get(&StepType::Prep)
-                        && let Some(prep_time_value) = *prep_time_total
-                    {
-                        prep_time_value
-                    } else {
-                        Time::default()
-                    }
-                },
-                prep_time_unit,
-                Description
+            scale_factor = scale_factor,
+            prep_time = display.time_range(
+                &step_type_time_totals
+                    .get(&StepType::Prep)
+                    .copied()
+                    .flatten()
+                    .unwrap_or_default()
             ),
-            cook_time = unit_helper::time_unit_format_output(
-                {
-                    if let Some(cook_time_total) = step_type_time_totals.get(&StepType::Cook)
-                        && let Some(cook_time_value) = *cook_time_total
-                    {
-                        cook_time_value
-                    } else {
-                        Time::default()
-                    }
-                },
-                cook_time_unit,
-                Description
+            cook_time = display.time_range(
+                &step_type_time_totals
+                    .get(&StepType::Cook)
+                    .copied()
+                    .flatten()
+                    .unwrap_or_default()
             ),
-            wait_time = unit_helper::time_unit_format_output(
-                {
-                    if let Some(wait_time_total) = step_type_time_totals.get(&StepType::Wait)
-                        && let Some(wait_time_value) = *wait_time_total
-                    {
-                        wait_time_value
-                    } else {
-                        Time::default()
-                    }
-                },
-                wait_time_unit,
-                Description
+            wait_time = display.time_range(
+                &step_type_time_totals
+                    .get(&StepType::Wait)
+                    .copied()
+                    .flatten()
+                    .unwrap_or_default()
             ),
-            other_time = unit_helper::time_unit_format_output(
-                {
-                    if let Some(other_time_total) = step_type_time_totals.get(&StepType::Other)
-                        && let Some(other_time_value) = *other_time_total
-                    {
-                        other_time_value
-                    } else {
-                        Time::default()
-                    }
-                },
-                other_time_unit,
-                Description
+            other_time = display.time_range(
+                &step_type_time_totals
+                    .get(&StepType::Other)
+                    .copied()
+                    .flatten()
+                    .unwrap_or_default()
             ),
-            total_time = unit_helper::time_unit_format_output(recipe.total_time(), total_time_unit, Description),
+            total_time = display.time_range(&recipe.total_time()),
+            elapsed_time = display.time_range(&recipe.elapsed_time()),
+            photos = photo_list(&recipe.photos),
             step_list = step_list,
             equipment_list = equipment_list,
             ingredient_list = ingredient_list,
+            nutrition_panel = nutrition_panel,
+            dietary = http_helper::html_escape(&recipe.dietary(catalog).to_string()),
+            cook_history = cook_history_list,
+            rating_list = rating_list,
+            display_options = display_options(display),
+            today = chrono::Local::now().date_naive(),
         )
     );
     // Don't fully understand why Box + Cursor, but thats what Rouille used and it seems to work.
@@ -216,3 +307,82 @@ pub fn recipe_viewer(recipe: Recipe) -> anyhow::Result<Response<Box<dyn Read + S
         None,
     ))
 }
+
+/// `display_options` returns the display preference choices for the recipe viewer form, with the
+/// choices in `display` selected
+fn display_options(display: &DisplayPreferences) -> String {
+    let option = |value: &str, label: String, selected: bool| {
+        let selected = if selected { " selected" } else { "" };
+        format!("<option value=\"{value}\"{selected}>{label}</option>")
+    };
+    let [system_field, temperature_field, time_format_field, fraction_style_field] = DisplayPreferences::FORM_FIELDS;
+    let mut output = String::new();
+    output.push_str(format!("<label for=\"{system_field}\">Units</label>\n").as_str());
+    output.push_str(format!("<select id=\"{system_field}\" name=\"{system_field}\">\n").as_str());
+    output.push_str(&option("default", "As Written".to_owned(), display.system.is_none()));
+    for system in [
+        MeasurementSystem::Metric,
+        MeasurementSystem::UsCustomary,
+        MeasurementSystem::Imperial,
+    ] {
+        output.push_str(&option(
+            system.form_value(),
+            system.to_string(),
+            display.system == Some(system),
+        ));
+    }
+    output.push_str("</select>\n");
+    output.push_str(format!("<label for=\"{temperature_field}\">Temperature</label>\n").as_str());
+    output.push_str(format!("<select id=\"{temperature_field}\" name=\"{temperature_field}\">\n").as_str());
+    output.push_str(&option("default", "Same as Units".to_owned(), display.temperature.is_none()));
+    for scale in [TemperatureScale::Celsius, TemperatureScale::Fahrenheit] {
+        output.push_str(&option(
+            scale.form_value(),
+            scale.to_string(),
+            display.temperature == Some(scale),
+        ));
+    }
+    output.push_str("</select>\n");
+    output.push_str(format!("<label for=\"{time_format_field}\">Times</label>\n").as_str());
+    output.push_str(format!("<select id=\"{time_format_field}\" name=\"{time_format_field}\">\n").as_str());
+    for format in [TimeFormat::Units, TimeFormat::Clock, TimeFormat::Minutes] {
+        output.push_str(&option(
+            format.form_value(),
+            format.to_string(),
+            display.time_format == format,
+        ));
+    }
+    output.push_str("</select>\n");
+    output.push_str(format!("<label for=\"{fraction_style_field}\">Numbers</label>\n").as_str());
+    output.push_str(format!("<select id=\"{fraction_style_field}\" name=\"{fraction_style_field}\">\n").as_str());
+    for style in [FractionStyle::Unicode, FractionStyle::Ascii, FractionStyle::Decimal] {
+        output.push_str(&option(
+            style.form_value(),
+            style.to_string(),
+            display.fraction_style == style,
+        ));
+    }
+    output.push_str("</select>\n");
+    output
+}
+
+/// `photo_list` returns a figure element showing each photo, with its caption if it has one
+fn photo_list(photos: &[Photo]) -> String {
+    let mut output = String::new();
+    for photo in photos {
+        let caption = photo.caption.as_deref().map(http_helper::html_escape);
+        output.push_str(
+            format!(
+                "<figure><img src=\"{}\" alt=\"{}\" loading=\"lazy\"/>{}</figure>\n",
+                http_helper::html_escape(&media_responses::photo_url(photo)),
+                caption.as_deref().unwrap_or_default(),
+                caption
+                    .as_deref()
+                    .map(|c| format!("<figcaption>{c}</figcaption>"))
+                    .unwrap_or_default()
+            )
+            .as_str(),
+        );
+    }
+    output
+}